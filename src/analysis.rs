@@ -0,0 +1,403 @@
+//! Statistical tests of randomness, for checking generator output or any file.
+//!
+//! The bit tests follow NIST SP 800-22: monobit, block frequency, runs, longest run of ones,
+//! serial and approximate entropy, with the parameters the NIST suite uses by default where
+//! the input is long enough. Bytes and password characters get a chi-square test against the
+//! uniform distribution. Every test yields one or two p-values and fails if any of them is
+//! below `SIGNIFICANCE_LEVEL`.
+//!
+//! Passing shows the absence of the biases these tests look for, nothing more. Perfectly
+//! random input also fails each test about once in a hundred runs; a real bias fails it
+//! again and again.
+
+use std::f64::consts::{LN_2, PI};
+use std::fmt;
+
+/// P-values below this fail.
+pub const SIGNIFICANCE_LEVEL: f64 = 0.01;
+
+/// Block length for the block frequency test.
+const BLOCK_FREQUENCY_LENGTH: usize = 128;
+/// Largest pattern lengths for the serial and approximate entropy tests.
+const MAX_SERIAL_LENGTH: u32 = 16;
+const MAX_APPROXIMATE_ENTROPY_LENGTH: u32 = 10;
+/// Chi-square tests need at least this many expected occurrences of every value.
+const MIN_EXPECTED_COUNT: usize = 5;
+
+/// The outcome of one test: its p-values, or why it could not run.
+#[derive(Clone, Debug)]
+pub struct TestResult {
+    pub name: String,
+    pub result: Result<Vec<f64>, String>,
+}
+
+impl TestResult {
+    fn new(name: &str, result: Result<Vec<f64>, String>) -> Self {
+        Self { name: name.to_string(), result }
+    }
+
+    /// Whether the test ran and any of its p-values fell below `SIGNIFICANCE_LEVEL`.
+    pub fn failed(&self) -> bool {
+        matches!(&self.result, Ok(p_values) if p_values.iter().any(|&p_value| p_value < SIGNIFICANCE_LEVEL))
+    }
+}
+
+impl fmt::Display for TestResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.result {
+            Ok(p_values) => {
+                let p_values: Vec<String> = p_values.iter().map(|p_value| format!("{:.6}", p_value)).collect();
+                write!(f, "{:<32} p = {:<20} {}", self.name, p_values.join(", "), if self.failed() { "FAIL" } else { "PASS" })
+            }
+            Err(reason) => write!(f, "{:<32} skipped, {}", self.name, reason),
+        }
+    }
+}
+
+/// Runs every bit test on `data`, most significant bit of each byte first, and the
+/// chi-square test on its bytes.
+pub fn analyze_bytes(data: &[u8]) -> Vec<TestResult> {
+    let bits: Vec<u8> = data.iter().flat_map(|&byte| (0..8).rev().map(move |shift| (byte >> shift) & 1)).collect();
+    vec![
+        TestResult::new("Monobit", monobit_test(&bits)),
+        TestResult::new("Block frequency", block_frequency_test(&bits, BLOCK_FREQUENCY_LENGTH)),
+        TestResult::new("Runs", runs_test(&bits)),
+        TestResult::new("Longest run of ones", longest_run_test(&bits)),
+        TestResult::new("Serial", serial_test(&bits, MAX_SERIAL_LENGTH.min(pattern_length_limit(bits.len(), 3)))),
+        TestResult::new("Approximate entropy", approximate_entropy_test(&bits, MAX_APPROXIMATE_ENTROPY_LENGTH.min(pattern_length_limit(bits.len(), 6)))),
+        TestResult::new("Byte chi-square", chi_square_test(data.iter().map(|&byte| byte as usize), 256)),
+    ]
+}
+
+/// Chi-square test of how often each character of `alphabet` appears in `text`. Fails to
+/// run if `text` holds a character that is not in `alphabet`.
+pub fn analyze_characters(name: &str, text: &str, alphabet: &str) -> TestResult {
+    let alphabet: Vec<char> = alphabet.chars().collect();
+    let indexes: Result<Vec<usize>, String> = text.chars()
+        .map(|character| alphabet.iter().position(|&candidate| candidate == character).ok_or(format!("{:?} is not in the alphabet", character)))
+        .collect();
+    TestResult::new(name, indexes.and_then(|indexes| chi_square_test(indexes.into_iter(), alphabet.len())))
+}
+
+fn require_bits(bits: &[u8], minimum: usize) -> Result<(), String> {
+    if bits.len() < minimum {
+        return Err(format!("needs at least {} bits", minimum));
+    }
+    Ok(())
+}
+
+/// Largest pattern length `m` with `m <= floor(log2 n) - margin`. SP 800-22 asks for
+/// `m < floor(log2 n) - 2` in the serial test and `- 5` in the approximate entropy test, so
+/// their margins are 3 and 6.
+fn pattern_length_limit(bit_count: usize, margin: u32) -> u32 {
+    bit_count.max(1).ilog2().saturating_sub(margin)
+}
+
+/// Whether the proportion of ones is close enough to one half.
+fn monobit_test(bits: &[u8]) -> Result<Vec<f64>, String> {
+    require_bits(bits, 100)?;
+    let ones = bits.iter().filter(|&&bit| bit == 1).count() as f64;
+    let sum = 2.0 * ones - bits.len() as f64;
+    Ok(vec![erfc(sum.abs() / (2.0 * bits.len() as f64).sqrt())])
+}
+
+/// Whether the proportion of ones is close to one half within every block.
+fn block_frequency_test(bits: &[u8], block_length: usize) -> Result<Vec<f64>, String> {
+    require_bits(bits, 100.max(block_length))?;
+    let blocks = bits.len() / block_length;
+    let chi_square: f64 = bits.chunks_exact(block_length)
+        .map(|block| block.iter().filter(|&&bit| bit == 1).count() as f64 / block_length as f64 - 0.5)
+        .map(|deviation| 4.0 * block_length as f64 * deviation * deviation)
+        .sum();
+    Ok(vec![igamc(blocks as f64 / 2.0, chi_square / 2.0)])
+}
+
+/// Whether the bits switch between zero and one as often as they should.
+fn runs_test(bits: &[u8]) -> Result<Vec<f64>, String> {
+    require_bits(bits, 100)?;
+    let n = bits.len() as f64;
+    let proportion = bits.iter().filter(|&&bit| bit == 1).count() as f64 / n;
+    // The test assumes the monobit test passes; if it fails by this much there is no point.
+    if (proportion - 0.5).abs() >= 2.0 / n.sqrt() {
+        return Ok(vec![0.0]);
+    }
+    let runs = 1 + bits.windows(2).filter(|pair| pair[0] != pair[1]).count();
+    let expected = 2.0 * n * proportion * (1.0 - proportion);
+    Ok(vec![erfc((runs as f64 - expected).abs() / (2.0 * (2.0 * n).sqrt() * proportion * (1.0 - proportion)))])
+}
+
+/// Whether the longest run of ones in each block has the expected distribution.
+fn longest_run_test(bits: &[u8]) -> Result<Vec<f64>, String> {
+    require_bits(bits, 128)?;
+    // Block length, the run length of the first category and each category's probability.
+    let (block_length, shortest_run, probabilities): (usize, usize, &[f64]) = match bits.len() {
+        750_000.. => (10_000, 10, &[0.0882, 0.2092, 0.2483, 0.1933, 0.1208, 0.0675, 0.0727]),
+        6272.. => (128, 4, &[0.1174, 0.2430, 0.2493, 0.1752, 0.1027, 0.1124]),
+        _ => (8, 1, &[0.2148, 0.3672, 0.2305, 0.1875]),
+    };
+
+    let mut frequencies = vec![0usize; probabilities.len()];
+    for block in bits.chunks_exact(block_length) {
+        let longest_run = block.split(|&bit| bit == 0).map(|run| run.len()).max().unwrap_or(0);
+        frequencies[longest_run.clamp(shortest_run, shortest_run + probabilities.len() - 1) - shortest_run] += 1;
+    }
+
+    let blocks = (bits.len() / block_length) as f64;
+    let chi_square: f64 = frequencies.iter().zip(probabilities)
+        .map(|(&frequency, &probability)| (frequency as f64 - blocks * probability).powi(2) / (blocks * probability))
+        .sum();
+    Ok(vec![igamc((probabilities.len() - 1) as f64 / 2.0, chi_square / 2.0)])
+}
+
+/// Whether every overlapping pattern of `pattern_length` bits is equally common.
+fn serial_test(bits: &[u8], pattern_length: u32) -> Result<Vec<f64>, String> {
+    require_bits(bits, 100)?;
+    if pattern_length < 3 {
+        return Err("needs at least 64 bits".to_string());
+    }
+    let psi_square = |length: u32| -> f64 {
+        let counts = pattern_counts(bits, length);
+        let sum_of_squares: f64 = counts.iter().map(|&count| (count as f64).powi(2)).sum();
+        sum_of_squares * (1u64 << length) as f64 / bits.len() as f64 - bits.len() as f64
+    };
+    let (psi_square_m, psi_square_m1, psi_square_m2) = (psi_square(pattern_length), psi_square(pattern_length - 1), psi_square(pattern_length - 2));
+    let delta = psi_square_m - psi_square_m1;
+    let second_delta = psi_square_m - 2.0 * psi_square_m1 + psi_square_m2;
+    Ok(vec![
+        igamc((1u64 << (pattern_length - 2)) as f64, delta / 2.0),
+        igamc((1u64 << (pattern_length - 3)) as f64, second_delta / 2.0),
+    ])
+}
+
+/// Whether patterns of `pattern_length` and `pattern_length + 1` bits are as unpredictable
+/// from one another as they should be.
+fn approximate_entropy_test(bits: &[u8], pattern_length: u32) -> Result<Vec<f64>, String> {
+    require_bits(bits, 100)?;
+    if pattern_length < 2 {
+        return Err("needs at least 256 bits".to_string());
+    }
+    let n = bits.len() as f64;
+    let phi = |length: u32| -> f64 {
+        pattern_counts(bits, length).iter()
+            .filter(|&&count| count > 0)
+            .map(|&count| count as f64 / n * (count as f64 / n).ln())
+            .sum()
+    };
+    let approximate_entropy = phi(pattern_length) - phi(pattern_length + 1);
+    let chi_square = 2.0 * n * (LN_2 - approximate_entropy);
+    Ok(vec![igamc((1u64 << (pattern_length - 1)) as f64, chi_square / 2.0)])
+}
+
+/// Chi-square test of `values`, each below `categories`, against the uniform distribution.
+fn chi_square_test(values: impl Iterator<Item = usize>, categories: usize) -> Result<Vec<f64>, String> {
+    let mut counts = vec![0usize; categories];
+    let mut total = 0;
+    for value in values {
+        counts[value] += 1;
+        total += 1;
+    }
+    if categories < 2 || total < categories * MIN_EXPECTED_COUNT {
+        return Err(format!("needs at least {} values", categories.max(2) * MIN_EXPECTED_COUNT));
+    }
+    let expected = total as f64 / categories as f64;
+    let chi_square: f64 = counts.iter().map(|&count| (count as f64 - expected).powi(2) / expected).sum();
+    Ok(vec![igamc((categories - 1) as f64 / 2.0, chi_square / 2.0)])
+}
+
+/// How often each `length`-bit pattern occurs, overlapping, with the sequence wrapped around
+/// so every position starts one.
+fn pattern_counts(bits: &[u8], length: u32) -> Vec<u64> {
+    let mut counts = vec![0u64; 1 << length];
+    if length == 0 {
+        counts[0] = bits.len() as u64;
+        return counts;
+    }
+    let mask = (1usize << length) - 1;
+    let mut pattern = 0usize;
+    for (index, &bit) in bits.iter().chain(&bits[..length as usize - 1]).enumerate() {
+        pattern = ((pattern << 1) | bit as usize) & mask;
+        if index >= length as usize - 1 {
+            counts[pattern] += 1;
+        }
+    }
+    counts
+}
+
+/// Complementary error function, for non-negative `x`.
+fn erfc(x: f64) -> f64 {
+    igamc(0.5, x * x)
+}
+
+/// Regularized upper incomplete gamma function Q(a, x), by its series below `a + 1` and its
+/// continued fraction above.
+fn igamc(a: f64, x: f64) -> f64 {
+    const EPSILON: f64 = 1e-15;
+    const TINY: f64 = 1e-300;
+    if x <= 0.0 {
+        return 1.0;
+    }
+    let prefactor = (a * x.ln() - x - ln_gamma(a)).exp();
+
+    if x < a + 1.0 {
+        let (mut denominator, mut term) = (a, 1.0 / a);
+        let mut sum = term;
+        while term.abs() > sum.abs() * EPSILON {
+            denominator += 1.0;
+            term *= x / denominator;
+            sum += term;
+        }
+        return (1.0 - sum * prefactor).max(0.0);
+    }
+
+    // Modified Lentz's method.
+    let mut b = x + 1.0 - a;
+    let mut c = 1.0 / TINY;
+    let mut d = 1.0 / b;
+    let mut fraction = d;
+    for i in 1..10_000 {
+        let an = -(i as f64) * (i as f64 - a);
+        b += 2.0;
+        d = an * d + b;
+        if d.abs() < TINY {
+            d = TINY;
+        }
+        c = b + an / c;
+        if c.abs() < TINY {
+            c = TINY;
+        }
+        d = 1.0 / d;
+        let delta = d * c;
+        fraction *= delta;
+        if (delta - 1.0).abs() < EPSILON {
+            break;
+        }
+    }
+    (fraction * prefactor).min(1.0)
+}
+
+/// Natural logarithm of the gamma function, by the Lanczos approximation.
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_9, 676.520_368_121_885_1, -1_259.139_216_722_402_8, 771.323_428_777_653_1,
+        -176.615_029_162_140_6, 12.507_343_278_686_905, -0.138_571_095_265_720_12, 9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+    if x < 0.5 {
+        return (PI / (PI * x).sin()).ln() - ln_gamma(1.0 - x);
+    }
+    let x = x - 1.0;
+    let sum = COEFFICIENTS[1..].iter().enumerate().fold(COEFFICIENTS[0], |sum, (i, coefficient)| sum + coefficient / (x + i as f64 + 1.0));
+    let t = x + 7.5;
+    0.5 * (2.0 * PI).ln() + (x + 0.5) * t.ln() - t + sum.ln()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The first 100 bits of pi, the input of most of the SP 800-22 worked examples.
+    const PI_BITS: &str = "1100100100001111110110101010001000100001011010001100001000110100110001001100011001100010100010111000";
+    /// The input of the SP 800-22 example for the longest run of ones.
+    const LONGEST_RUN_BITS: &str = "11001100000101010110110001001100111000000000001001001101010100010001001111010110100000001101011111001100111001101101100010110010";
+
+    fn bits(text: &str) -> Vec<u8> {
+        text.bytes().map(|bit| bit - b'0').collect()
+    }
+
+    fn assert_p_values(result: Result<Vec<f64>, String>, expected: &[f64]) {
+        let p_values = result.unwrap();
+        assert_eq!(p_values.len(), expected.len());
+        for (p_value, expected) in p_values.iter().zip(expected) {
+            assert!((p_value - expected).abs() < 1e-6, "p = {}, expected {}", p_value, expected);
+        }
+    }
+
+    #[test]
+    fn special_functions_match_reference_values() {
+        assert!((erfc(1.0) - 0.157299207050285).abs() < 1e-12);
+        assert!((igamc(3.5, 1.2) - 0.934437079577971).abs() < 1e-12);
+        assert!((igamc(10.0, 20.0) - 0.00499541230830759).abs() < 1e-12);
+        assert!((igamc(127.5, 100.0) - 0.995425444541952).abs() < 1e-10);
+        assert_eq!(igamc(2.0, 0.0), 1.0);
+        assert!((ln_gamma(5.0) - 24f64.ln()).abs() < 1e-12);
+        assert!((ln_gamma(0.5) - PI.sqrt().ln()).abs() < 1e-12);
+    }
+
+    /// The worked examples of SP 800-22 sections 2.1.8, 2.2.8, 2.3.8, 2.4.8 and 2.12.8.
+    #[test]
+    fn bit_tests_match_the_sp_800_22_examples() {
+        assert_p_values(monobit_test(&bits(PI_BITS)), &[0.109599]);
+        assert_p_values(block_frequency_test(&bits(PI_BITS), 10), &[0.706438]);
+        assert_p_values(runs_test(&bits(PI_BITS)), &[0.500798]);
+        // The example works out chi-square as 4.882605 but prints a P-value of 0.180609; Q(1.5,
+        // 4.882605 / 2) is 0.180598.
+        assert_p_values(longest_run_test(&bits(LONGEST_RUN_BITS)), &[0.180598]);
+        assert_p_values(approximate_entropy_test(&bits(PI_BITS), 2), &[0.235301]);
+    }
+
+    /// The serial test's own example has fewer than 100 bits, so this is its formula worked
+    /// through by hand on the pi bits: delta psi-squared is 4.8 and the second difference 2.08.
+    #[test]
+    fn serial_test_matches_its_definition() {
+        assert_p_values(serial_test(&bits(PI_BITS), 3), &[0.308441041184003, 0.35345468195878]);
+        assert!(serial_test(&bits(PI_BITS), 2).is_err());
+    }
+
+    /// The counts in the serial test's example, SP 800-22 section 2.11.4.
+    #[test]
+    fn pattern_counts_wrap_around() {
+        assert_eq!(pattern_counts(&bits("0011011101"), 3), [0, 1, 1, 2, 1, 2, 2, 1]);
+        assert_eq!(pattern_counts(&bits("0011011101"), 0), [10]);
+        assert_eq!(pattern_counts(&bits("0011011101"), 1), [4, 6]);
+    }
+
+    /// SP 800-22 section 2.11.7: with a million bits, the serial test allows up to m = 17.
+    #[test]
+    fn pattern_lengths_stay_below_the_limit() {
+        assert_eq!(pattern_length_limit(1 << 20, 3), 17);
+        assert_eq!(pattern_length_limit((1 << 20) - 1, 3), 16);
+        assert_eq!(pattern_length_limit(1 << 20, 6), 14);
+        assert_eq!(pattern_length_limit(4, 3), 0);
+        assert_eq!(pattern_length_limit(0, 3), 0);
+    }
+
+    #[test]
+    fn short_input_is_skipped() {
+        let results = analyze_bytes(&[0x5a; 8]);
+        assert!(results.iter().all(|result| result.result.is_err() && !result.failed()));
+        assert!(results[0].to_string().contains("skipped, needs at least 100 bits"));
+    }
+
+    #[test]
+    fn biased_input_fails() {
+        let results = analyze_bytes(&[0xff; 4096]);
+        assert!(results.iter().all(|result| result.failed()), "{:?}", results);
+
+        // Balanced but far too regular.
+        let results = analyze_bytes(&[0x55; 4096]);
+        assert!(!results[0].failed());
+        assert!(results[2].failed());
+        assert!(results[6].failed());
+    }
+
+    #[test]
+    fn generator_output_passes() {
+        let mut data = vec![0u8; 1 << 16];
+        crate::generator::stream::StreamGenerator::from_key(&[7; 32], &[0; 12]).fill_bytes(&mut data);
+        let results = analyze_bytes(&data);
+        assert_eq!(results.len(), 7);
+        for result in &results {
+            assert!(result.result.is_ok() && !result.failed(), "{}", result);
+        }
+    }
+
+    #[test]
+    fn character_tests_check_the_alphabet() {
+        let even = "abcd".repeat(10);
+        assert_p_values(analyze_characters("Characters", &even, "abcd").result, &[1.0]);
+        assert!(analyze_characters("Characters", &"a".repeat(40), "abcd").failed());
+        let outside = analyze_characters("Characters", &format!("{}e", even), "abcd");
+        assert_eq!(outside.result.unwrap_err(), "'e' is not in the alphabet");
+        assert!(analyze_characters("Characters", "abcd", "abcd").result.is_err());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,967 @@
+use std::fs;
+use std::io::{self, BufRead, IsTerminal};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+use std::time::Duration;
+
+use num_bigint::BigInt;
+use pgen::analysis::{self, TestResult};
+use pgen::config::{self, Config};
+use pgen::encoding;
+use pgen::generator::{self, GeneratePasswordParams};
+use pgen::entropy::dice::DiceEntropyMode;
+use pgen::entropy::health::HealthStatus;
+use pgen::generator::bip39::{Bip39Language, Mnemonic};
+use pgen::generator::bytes;
+use pgen::generator::ceremony::{self, Ceremony, CeremonyReveal, CeremonyTranscript, Commitment, Contribution};
+use pgen::generator::draw::{Draw, DrawTranscript};
+use pgen::generator::stream::StreamGenerator;
+use pgen::generator::utilities::{self, DiceRoll};
+use pgen::generator::identifier::{self, GenerateIdentifierParams, IdentifierKind};
+use pgen::generator::site_password::{self, LessPassRules, SitePasswordAlgorithm, SitePasswordParams, SpectreTemplate};
+use pgen::generator::token::{self, GenerateTokenParams, TokenEncoding};
+use pgen::pool::{EntropyGateMode, EntropyPool, GenerationError, OperatingMode};
+use pgen::seed_file::{self, SeedFileStatus};
+use pgen::self_test;
+
+/// How long to harvest kernel state for the entropy gate before giving up.
+const SYSTEM_ENTROPY_TIMEOUT: Duration = Duration::from_secs(30);
+/// Bytes `analyze` generates when not given a file.
+const ANALYZE_DEFAULT_SIZE: u64 = 1 << 20;
+/// Password characters `analyze` generates for each character class.
+const ANALYZE_DEFAULT_CHARACTERS: usize = 100_000;
+/// How long `health` harvests kernel state before reporting.
+const HEALTH_SYSTEM_ENTROPY_DURATION: Duration = Duration::from_secs(1);
+
+const USAGE: &str = "\
+Usage: P-GEN [--fips | --no-fips] [--metadata] [--config <path> | --no-config]
+             [--dice <coin|dN> [--dice-bits <n>] [--dice-only]]
+             [--min-entropy <bits>] [--seed-file <path> | --no-seed-file]
+             [--entropy-file <path>]... [--entropy-text <text>] <command> [options]
+
+Commands:
+  bytes <size> [-o <file>] [--progress]
+      Write <size> random bytes to <file>, or to stdout if no file is given.
+      <size> accepts K, M and G suffixes (powers of 1024). Existing files are
+      never overwritten.
+  token [--bits <n> | --bytes <n>] [--encoding <name>] [--padding] [--count <n>]
+      Print random tokens, 256 bits of lowercase hex by default. Encodings:
+      hex, HEX, base32, crockford, base58, base64, base64url, z85.
+  id <uuid4|uuid7|ulid|nanoid> [--count <n>] [--size <n>] [--alphabet <chars>]
+      Print identifiers. ULIDs in one batch are monotonic. NanoIDs default to
+      21 characters of the URL-safe alphabet.
+  random int <min> <max> [--count <n>]
+  random float <min> <max> [--count <n>]
+  random dice <notation> [--count <n>]
+  random shuffle [<item>...]
+  random sample <k> [<item>...]
+      Unbiased integers (any size), floats in [min, max), dice rolls such as
+      3d6+2, shuffles and samples without replacement. Items are read from
+      stdin, one per line, when none are given.
+  site-password <site> <login> [--counter <n>] [--lesspass | --spectre <type>]
+                [--length <n>] [--characters <classes>]
+      Derive a site's password from a master password, read from the first
+      line of stdin, without storing anything: the same inputs always give
+      the same password, and a new counter gives a new one. By default P-GEN's
+      own algorithm (Argon2id) picks 20 characters from --characters, a comma
+      separated list of uppercase, lowercase, numbers, logograms, punctuation,
+      quotes, dashes, maths and brackets (the first three by default).
+      --lesspass derives LessPass v2 passwords, 16 characters by default, from
+      lowercase, uppercase, numbers and symbols. --spectre derives Spectre
+      (Master Password) passwords of type maximum, long, medium, short, basic,
+      pin, name or phrase, with <login> as the user's full name. The counter
+      starts at 1.
+  mnemonic [--words <n>] [--language <name>]
+      Print a new BIP39 mnemonic of 12, 15, 18, 21 or 24 words (24 by
+      default) with a valid checksum. Languages: english, chinese-simplified,
+      chinese-traditional, czech, french, italian, japanese, korean,
+      portuguese, spanish.
+  mnemonic check [--language <name>] [--seed]
+      Check the BIP39 phrase on the first line of stdin: its words, length and
+      checksum. With --seed, also print its 64-byte seed in hex, using the
+      second line of stdin, if any, as the passphrase.
+  draw commit <participants-file> <winners> <state-file>
+      Start a verifiable draw. Prints the commitment to publish and writes the
+      secret seed to <state-file>. Participants are listed one per line.
+  draw reveal <state-file> [--beacon <value>]
+      Perform the draw and print the transcript to publish, seed included.
+  draw verify <transcript-file>
+      Check a transcript against its commitment and replay the draw.
+  ceremony contribute <name>
+      Make a random contribution to a key ceremony. Hand in the commitment line
+      now and the contribution line once everyone has committed.
+  ceremony open <commitments-file> <state-file> [--size <bytes>]
+      Open a ceremony with the participants' commitment lines, adding one from
+      P-GEN itself. Prints the public key and P-GEN's commitment to announce,
+      and writes the secret state to <state-file>. Secrets are 32 bytes by
+      default.
+  ceremony reveal <state-file> <contributions-file> -o <secret-file> --reveal <reveal-file>
+      Check every contribution against its commitment, write the shared secret
+      to a new <secret-file> and the contributions to a new <reveal-file>, and
+      print the signed transcript. The reveal file and the transcript together
+      give the secret away; keep the reveal file as safe as the secret.
+  ceremony verify <transcript-file> [<reveal-file>]
+      Check a transcript's signature and, given the reveal file, that every
+      contribution went into the secret.
+  health
+      Run the entropy sources through their startup health tests and report
+      on each of them.
+  analyze [<file>] [--size <bytes>] [--characters <n>]
+      Run statistical randomness tests and print their p-values: the NIST
+      SP 800-22 monobit, block frequency, runs, longest run, serial and
+      approximate entropy tests and a chi-square test on bytes, on <file> or
+      on <size> freshly generated bytes (1M by default). Without a file, also
+      run a chi-square test on <n> password characters (100000 by default)
+      for every character class and for all of them together. Tests fail
+      below p = 0.01, so random data fails about one test in a hundred.
+  self-test
+      Run the known-answer tests for every hash and cipher P-GEN uses and
+      report on each of them. They also run on startup, and generation is
+      refused while any of them fails.
+  help
+      Show this message.
+
+Dice entropy:
+  --dice <coin|dN>
+      Before each generation, read physical coin flips or rolls of an N-sided
+      die from stdin until enough bits are collected, and mix them in. Coins
+      take H/T or 1/0; dice take numbers, or runs of digits below d10.
+  --dice-bits <n>
+      Bits to collect before each generation, 256 by default.
+  --dice-only
+      Seed from the rolls alone, without the OS RNG or the clock.
+  --min-entropy <bits>
+      Refuse to generate unless sources gathered ahead of time, such as dice,
+      have been credited with at least <bits> bits since the last generation.
+      On Linux, kernel counters from /proc are harvested for up to 30 seconds
+      to make up the difference.
+
+Your own entropy:
+  --entropy-file <path>
+      Mix a file, such as a photo or a recording, into the pool. May be given
+      more than once. Credited with at most 64 bits, however large it is.
+  --entropy-text <text>
+      Mix some text into the pool, credited conservatively.
+
+Seed file:
+  A seed saved by the previous run is mixed in at startup and replaced at
+  once, then saved again on exit. It lives in the user's data directory.
+  Only commands that generate output read or write it.
+  --seed-file <path>
+      Use another seed file.
+  --no-seed-file
+      Neither read nor write a seed file.
+
+Configuration file:
+  Settings that hold for every run, one `key = value` per line. It lives in
+  the user's config directory. The only setting is fips-mode = on or off.
+  --config <path>
+      Use another configuration file.
+  --no-config
+      Don't read a configuration file.
+
+FIPS-approved mode:
+  --fips
+      Generate with FIPS-approved algorithms only, also turned on by setting
+      PGEN_FIPS_MODE=1 or fips-mode = on in the configuration file. Streams come
+      from an SP 800-90A HMAC_DRBG with SHA-512 seeded by the OS RNG, with CPU
+      jitter, kernel state and dice conditioned by SHA-3 and SHA-512. Whirlpool,
+      BLAKE3 and ChaCha20 are not used, so the seed file, --entropy-file,
+      --entropy-text, --dice-only, draws and key ceremonies are unavailable. The
+      mode is reported on stderr after output.
+  --no-fips
+      Generate in standard mode, whatever PGEN_FIPS_MODE or the configuration
+      file say. --fips and --no-fips override PGEN_FIPS_MODE, which overrides
+      the configuration file.
+  --metadata
+      Start the output of every command that generates with a line naming the
+      operating mode and its algorithms, in either mode. bytes needs -o <file>
+      with it, since stdout carries the raw bytes otherwise.";
+
+/// Runs a command-line invocation and returns the process exit code.
+pub fn run(args: &[String]) -> i32 {
+    attach_parent_console();
+
+    let pool = EntropyPool::default();
+    let mut seed_path = seed_file::default_path();
+    let mut print_metadata = false;
+    let result = parse_global_options(&pool, args, &mut seed_path, &mut print_metadata).and_then(|args| {
+        // The seed file is sealed with XChaCha20 and BLAKE3 and mixed in with Whirlpool.
+        if pool.operating_mode() == OperatingMode::FipsApproved || !uses_seed_file(args) {
+            seed_path = None;
+        }
+        if let Some(path) = &seed_path {
+            match seed_file::load(&pool, path) {
+                Ok(SeedFileStatus::Corrupted(reason)) => eprintln!("P-GEN: replaced unusable seed file {}: {}", path.display(), reason),
+                Err(error) => eprintln!("P-GEN: could not update seed file {}: {}", path.display(), error),
+                Ok(_) => {}
+            }
+        }
+        if print_metadata && generates(args) {
+            if args[0] == "bytes" && !args.windows(2).any(|pair| matches!(pair[0].as_str(), "-o" | "--out") && pair[1] != "-") {
+                return Err("--metadata needs -o <file> with bytes".to_string());
+            }
+            print_operating_mode(&pool);
+        }
+        run_command(&pool, args)?;
+        if !print_metadata {
+            report_operating_mode(&pool, args);
+        }
+        Ok(())
+    });
+    if let Some(path) = seed_path.filter(|_| result.is_ok()) {
+        if let Err(error) = seed_file::save(&pool, &path) {
+            eprintln!("P-GEN: could not save seed file {}: {}", path.display(), error);
+        }
+    }
+
+    match result {
+        Ok(()) => 0,
+        Err(message) => {
+            eprintln!("P-GEN: {}", message);
+            eprintln!("Run `P-GEN help` for usage.");
+            1
+        }
+    }
+}
+
+fn run_command(pool: &EntropyPool, args: &[String]) -> Result<(), String> {
+    match args.first().map(|arg| arg.as_str()) {
+        Some("bytes") => run_bytes(pool, &args[1..]),
+        Some("token") => run_token(pool, &args[1..]),
+        Some("id") => run_id(pool, &args[1..]),
+        Some("random") => run_random(pool, &args[1..]),
+        Some("site-password") => run_site_password(pool, &args[1..]),
+        Some("mnemonic") => run_mnemonic(pool, &args[1..]),
+        Some("draw") => run_draw(pool, &args[1..]),
+        Some("ceremony") => run_ceremony(pool, &args[1..]),
+        Some("health") => run_health(pool),
+        Some("analyze") => run_analyze(pool, &args[1..]),
+        Some("self-test") => run_self_test(pool),
+        Some("help" | "-h" | "--help") => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        Some(command) => Err(format!("unknown command: {}", command)),
+        None => Err("missing command".to_string()),
+    }
+}
+
+/// Takes the global options off the front of the arguments and configures the pool with them
+/// and with the configuration file.
+fn parse_global_options<'a>(pool: &EntropyPool, mut args: &'a [String], seed_path: &mut Option<PathBuf>, print_metadata: &mut bool) -> Result<&'a [String], String> {
+    let mut dice_entropy = pool.dice_entropy();
+    let mut user_content_option = None;
+    let mut config_path = config::default_path();
+    let mut operating_mode = None;
+    while let Some(arg) = args.first() {
+        let value = || args.get(1).ok_or(format!("missing value after {}", arg));
+        match arg.as_str() {
+            "--fips" => {
+                operating_mode = Some(OperatingMode::FipsApproved);
+                args = &args[1..];
+            }
+            "--no-fips" => {
+                operating_mode = Some(OperatingMode::Standard);
+                args = &args[1..];
+            }
+            "--config" => {
+                config_path = Some(PathBuf::from(value()?));
+                args = &args[2..];
+            }
+            "--no-config" => {
+                config_path = None;
+                args = &args[1..];
+            }
+            "--metadata" => {
+                *print_metadata = true;
+                args = &args[1..];
+            }
+            "--dice" => {
+                let sides = parse_dice_sides(value()?)?;
+                dice_entropy.set_sides(sides);
+                if dice_entropy.mode == DiceEntropyMode::Disabled {
+                    dice_entropy.mode = DiceEntropyMode::Mixed;
+                }
+                args = &args[2..];
+            }
+            "--dice-bits" => {
+                dice_entropy.required_bits = parse_count(value()?)?;
+                args = &args[2..];
+            }
+            "--dice-only" => {
+                dice_entropy.mode = DiceEntropyMode::Exclusive;
+                args = &args[1..];
+            }
+            "--entropy-file" => {
+                let path = value()?;
+                let mut content = fs::read(path).map_err(|error| format!("could not read {}: {}", path, error))?;
+                pool.add_user_content(&content);
+                content.fill(0);
+                user_content_option = Some("--entropy-file");
+                args = &args[2..];
+            }
+            "--entropy-text" => {
+                pool.add_user_content(value()?.as_bytes());
+                user_content_option = Some("--entropy-text");
+                args = &args[2..];
+            }
+            "--seed-file" => {
+                *seed_path = Some(PathBuf::from(value()?));
+                args = &args[2..];
+            }
+            "--no-seed-file" => {
+                *seed_path = None;
+                args = &args[1..];
+            }
+            "--min-entropy" => {
+                let mut entropy_gate = pool.entropy_gate();
+                entropy_gate.threshold_bits = parse_count(value()?)? as f64;
+                entropy_gate.mode = EntropyGateMode::Refuse;
+                args = &args[2..];
+            }
+            _ => break,
+        }
+    }
+    let config = match &config_path {
+        Some(path) => Config::load(path)?,
+        None => Config::default(),
+    };
+    pool.set_operating_mode(operating_mode.or_else(OperatingMode::from_environment).or(config.operating_mode).unwrap_or_default());
+
+    if dice_entropy.mode == DiceEntropyMode::Exclusive && dice_entropy.required_bits < 128 {
+        return Err("--dice-only needs at least 128 bits".to_string());
+    }
+    if pool.operating_mode() == OperatingMode::FipsApproved {
+        // User content is conditioned with Whirlpool, and the dice-only path skips the DRBG.
+        if let Some(option) = user_content_option.or((dice_entropy.mode == DiceEntropyMode::Exclusive).then_some("--dice-only")) {
+            return Err(GenerationError::UnavailableInFipsMode { feature: option }.to_string());
+        }
+    }
+    Ok(args)
+}
+
+fn parse_dice_sides(text: &str) -> Result<u32, String> {
+    let sides = match text.to_ascii_lowercase().as_str() {
+        "coin" => Some(2),
+        name => name.strip_prefix('d').and_then(|sides| sides.parse().ok()),
+    };
+    sides.filter(|sides| (2..=1000).contains(sides)).ok_or(format!("invalid die: {} (use coin or d2 to d1000)", text))
+}
+
+/// Gathers what the next generation needs: dice rolls if they are in use, then kernel state
+/// until the entropy gate is satisfied, if it is on.
+fn collect_entropy(pool: &EntropyPool) -> Result<(), String> {
+    collect_dice_entropy(pool)?;
+    let gate = *pool.entropy_gate();
+    if gate.mode != EntropyGateMode::Off {
+        pool.collect_system_entropy(gate.threshold_bits - pool.gathered_entropy_bits(), SYSTEM_ENTROPY_TIMEOUT);
+    }
+    Ok(())
+}
+
+/// Prompts for rolls on stderr and reads them from stdin, one batch per line, until the pool
+/// holds enough dice bits for the next generation. Does nothing if dice are not in use.
+fn collect_dice_entropy(pool: &EntropyPool) -> Result<(), String> {
+    let mut dice_entropy = pool.dice_entropy();
+    let sides = dice_entropy.sides();
+    let die_name = if sides == 2 { "coin flips".to_string() } else { format!("d{} rolls", sides) };
+
+    let stdin = io::stdin();
+    while dice_entropy.mode != DiceEntropyMode::Disabled && !dice_entropy.has_required_bits() {
+        eprint!("{} of {} bits collected. Enter {}: ", dice_entropy.available_bits(), dice_entropy.required_bits, die_name);
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line).map_err(|error| format!("could not read stdin: {}", error))? == 0 {
+            return Err(format!("stdin ended after {} of {} bits", dice_entropy.available_bits(), dice_entropy.required_bits));
+        }
+        if let Err(message) = dice_entropy.add_typed_rolls(sides, line.trim()) {
+            eprintln!("{}", message);
+        }
+    }
+    Ok(())
+}
+
+fn run_bytes(pool: &EntropyPool, args: &[String]) -> Result<(), String> {
+    let mut size = None;
+    let mut output_path = None;
+    let mut show_progress = false;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-o" | "--out" => output_path = Some(args.next().ok_or("missing file name after -o")?.clone()),
+            "--progress" => show_progress = true,
+            _ if size.is_none() => size = Some(parse_size(arg)?),
+            _ => return Err(format!("unexpected argument: {}", arg)),
+        }
+    }
+    let size = size.ok_or("missing byte count")?;
+
+    collect_entropy(pool)?;
+    let cancel = AtomicBool::new(false);
+    let mut last_percent = None;
+    let mut progress = |written: u64, total: u64| {
+        if !show_progress {
+            return;
+        }
+        let percent = written * 100 / total.max(1);
+        if last_percent != Some(percent) {
+            last_percent = Some(percent);
+            eprint!("\r{:>3}% ({} / {} bytes)", percent, written, total);
+            if written == total {
+                eprintln!();
+            }
+        }
+    };
+
+    match output_path.as_deref() {
+        Some(path) if path != "-" => bytes::write_random_bytes_to_file(pool, Path::new(path), size, &mut progress, &cancel)
+            .map_err(|error| format!("could not write {}: {}", path, error)),
+        _ => {
+            let stdout = io::stdout();
+            if stdout.is_terminal() {
+                return Err("refusing to write raw bytes to a terminal; redirect stdout or use -o <file>".to_string());
+            }
+            bytes::write_random_bytes(pool, &mut stdout.lock(), size, &mut progress, &cancel)
+                .map_err(|error| format!("could not write to stdout: {}", error))
+        }
+    }
+}
+
+fn run_token(pool: &EntropyPool, args: &[String]) -> Result<(), String> {
+    let mut params = GenerateTokenParams::from_bits(256, TokenEncoding::HexLower, false);
+    let mut count = 1;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("missing value after {}", arg));
+        match arg.as_str() {
+            "--bits" => params.size = parse_count(value()?)?.div_ceil(8),
+            "--bytes" => params.size = parse_count(value()?)?,
+            "--encoding" => {
+                let name = value()?;
+                params.encoding = TokenEncoding::from_name(name).ok_or(format!("unknown encoding: {}", name))?;
+            }
+            "--padding" => params.padding = true,
+            "--count" => count = parse_count(value()?)?,
+            _ => return Err(format!("unexpected argument: {}", arg)),
+        }
+    }
+
+    for _ in 0..count {
+        collect_entropy(pool)?;
+        println!("{}", token::generate_token(pool, &params).map_err(|error| error.to_string())?);
+    }
+    Ok(())
+}
+
+fn run_id(pool: &EntropyPool, args: &[String]) -> Result<(), String> {
+    let kind_name = args.first().ok_or("missing identifier kind")?;
+    let mut params = GenerateIdentifierParams {
+        kind: IdentifierKind::from_name(kind_name).ok_or(format!("unknown identifier kind: {}", kind_name))?,
+        ..Default::default()
+    };
+    let mut count = 1;
+
+    let mut args = args[1..].iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("missing value after {}", arg));
+        match arg.as_str() {
+            "--count" => count = parse_count(value()?)?,
+            "--size" => params.nanoid_size = parse_count(value()?)?,
+            "--alphabet" => params.nanoid_alphabet = value()?.clone(),
+            _ => return Err(format!("unexpected argument: {}", arg)),
+        }
+    }
+
+    collect_entropy(pool)?;
+    let identifiers = identifier::generate_identifiers(pool, &params, count).map_err(|error| error.to_string())?;
+    for identifier in identifiers {
+        println!("{}", identifier);
+    }
+    Ok(())
+}
+
+fn run_random(pool: &EntropyPool, args: &[String]) -> Result<(), String> {
+    let utility = args.first().ok_or("missing utility: int, float, dice, shuffle or sample")?;
+    let mut positional = Vec::new();
+    let mut count = 1;
+
+    let mut rest = args[1..].iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--count" => count = parse_count(rest.next().ok_or("missing value after --count")?)?,
+            _ => positional.push(arg.clone()),
+        }
+    }
+    let argument = |i: usize, name: &str| positional.get(i).cloned().ok_or(format!("missing {}", name));
+
+    collect_entropy(pool)?;
+    let mut stream = StreamGenerator::new(pool, 0).map_err(|error| error.to_string())?;
+    match utility.as_str() {
+        "int" => {
+            let min: BigInt = argument(0, "minimum")?.parse().map_err(|_| "invalid minimum")?;
+            let max: BigInt = argument(1, "maximum")?.parse().map_err(|_| "invalid maximum")?;
+            for _ in 0..count {
+                println!("{}", utilities::uniform_bigint(&mut stream, &min, &max).ok_or("the minimum is larger than the maximum")?);
+            }
+        }
+        "float" => {
+            let min: f64 = argument(0, "minimum")?.parse().map_err(|_| "invalid minimum")?;
+            let max: f64 = argument(1, "maximum")?.parse().map_err(|_| "invalid maximum")?;
+            for _ in 0..count {
+                println!("{}", utilities::uniform_f64(&mut stream, min, max).ok_or("the range is empty")?);
+            }
+        }
+        "dice" => {
+            let dice = DiceRoll::parse(&argument(0, "dice notation")?)?;
+            for _ in 0..count {
+                println!("{}", dice.describe(&dice.roll(&mut stream)));
+            }
+        }
+        "shuffle" => {
+            let mut items = if positional.is_empty() { read_stdin_lines()? } else { positional };
+            utilities::shuffle(&mut stream, &mut items);
+            for item in items {
+                println!("{}", item);
+            }
+        }
+        "sample" => {
+            let sample_size = parse_count(&argument(0, "sample size")?)?;
+            let items = if positional.len() > 1 { positional[1..].to_vec() } else { read_stdin_lines()? };
+            for item in utilities::sample(&mut stream, &items, sample_size).ok_or("the sample is larger than the list")? {
+                println!("{}", item);
+            }
+        }
+        _ => return Err(format!("unknown utility: {}", utility)),
+    }
+    Ok(())
+}
+
+fn run_site_password(pool: &EntropyPool, args: &[String]) -> Result<(), String> {
+    let mut positional = Vec::new();
+    let mut counter = 1;
+    let mut spectre_template = None;
+    let mut lesspass = false;
+    let mut length = None;
+    let mut characters = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("missing value after {}", arg));
+        match arg.as_str() {
+            "--counter" => {
+                let text = value()?;
+                counter = text.parse().map_err(|_| format!("invalid counter: {}", text))?;
+            }
+            "--lesspass" => lesspass = true,
+            "--spectre" => {
+                let name = value()?;
+                spectre_template = Some(SpectreTemplate::ALL.into_iter().find(|template| template.name() == name).ok_or(format!("unknown Spectre type: {}", name))?);
+            }
+            "--length" => length = Some(parse_count(value()?)?),
+            "--characters" => characters = Some(value()?.split(',').map(|class| class.trim().to_string()).collect::<Vec<String>>()),
+            _ => positional.push(arg.clone()),
+        }
+    }
+    let (site, login) = match positional.as_slice() {
+        [site, login] => (site, login),
+        [] | [_] => return Err("missing site or login".to_string()),
+        [_, _, unexpected, ..] => return Err(format!("unexpected argument: {}", unexpected)),
+    };
+
+    let algorithm = match (lesspass, spectre_template) {
+        (true, Some(_)) => return Err("--lesspass and --spectre can't be combined".to_string()),
+        (false, Some(_)) if length.is_some() || characters.is_some() => return Err("Spectre types set the length and characters".to_string()),
+        (false, Some(template)) => SitePasswordAlgorithm::Spectre(template),
+        (true, None) => {
+            let mut rules = LessPassRules { length: length.unwrap_or(LessPassRules::default().length), ..LessPassRules::default() };
+            if let Some(classes) = &characters {
+                rules = LessPassRules { length: rules.length, lowercase: false, uppercase: false, digits: false, symbols: false };
+                for class in classes {
+                    match class.as_str() {
+                        "lowercase" => rules.lowercase = true,
+                        "uppercase" => rules.uppercase = true,
+                        "numbers" => rules.digits = true,
+                        "symbols" => rules.symbols = true,
+                        _ => return Err(format!("unknown LessPass character class: {}", class)),
+                    }
+                }
+            }
+            SitePasswordAlgorithm::LessPass(rules)
+        }
+        (false, None) => {
+            let size = u32::try_from(length.unwrap_or(20)).map_err(|_| "the password is too long")?;
+            let mut params = GeneratePasswordParams { size, use_uppercase_chars: true, use_lowercase_chars: true, use_numbers: true, ..Default::default() };
+            if let Some(classes) = &characters {
+                params = GeneratePasswordParams { size, ..Default::default() };
+                for class in classes {
+                    match class.as_str() {
+                        "uppercase" => params.use_uppercase_chars = true,
+                        "lowercase" => params.use_lowercase_chars = true,
+                        "numbers" => params.use_numbers = true,
+                        "logograms" => params.use_logograms = true,
+                        "punctuation" => params.use_punctuation = true,
+                        "quotes" => params.use_quotation_marks = true,
+                        "dashes" => params.use_dashes_and_slashes = true,
+                        "maths" => params.use_maths_symbols = true,
+                        "brackets" => params.use_brackets = true,
+                        _ => return Err(format!("unknown character class: {}", class)),
+                    }
+                }
+            }
+            SitePasswordAlgorithm::Pgen(params)
+        }
+    };
+    if pool.operating_mode() == OperatingMode::FipsApproved && !algorithm.is_fips_approved() {
+        return Err(GenerationError::UnavailableInFipsMode { feature: "P-GEN and Spectre site passwords" }.to_string());
+    }
+
+    if io::stdin().is_terminal() {
+        eprint!("Master password: ");
+    }
+    let mut master_password = String::new();
+    io::stdin().lock().read_line(&mut master_password).map_err(|error| format!("could not read stdin: {}", error))?;
+    let master_password_length = master_password.trim_end_matches(['\r', '\n']).len();
+    master_password.truncate(master_password_length);
+    if master_password.is_empty() {
+        return Err("missing master password".to_string());
+    }
+
+    let params = SitePasswordParams { site, login, counter, algorithm };
+    let result = site_password::derive_site_password(&master_password, &params);
+    master_password.into_bytes().fill(0);
+    println!("{}", result.map_err(|error| error.to_string())?);
+    Ok(())
+}
+
+fn run_mnemonic(pool: &EntropyPool, args: &[String]) -> Result<(), String> {
+    let check = args.first().map(|arg| arg.as_str()) == Some("check");
+    let mut word_count = 24;
+    let mut language = None;
+    let mut print_seed = false;
+
+    let mut args = args[check as usize..].iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("missing value after {}", arg));
+        match arg.as_str() {
+            "--words" if !check => word_count = parse_count(value()?)?,
+            "--language" => {
+                let name = value()?;
+                language = Some(Bip39Language::ALL.into_iter().find(|language| language.name() == name).ok_or(format!("unknown language: {}", name))?);
+            }
+            "--seed" if check => print_seed = true,
+            _ => return Err(format!("unexpected argument: {}", arg)),
+        }
+    }
+
+    if !check {
+        collect_entropy(pool)?;
+        let mnemonic = Mnemonic::generate(pool, word_count, language.unwrap_or(Bip39Language::English)).map_err(|error| error.to_string())?;
+        println!("{}", mnemonic.phrase());
+        return Ok(());
+    }
+
+    let mut lines = io::stdin().lock().lines();
+    let phrase = lines.next().unwrap_or(Ok(String::new())).map_err(|error| format!("could not read stdin: {}", error))?;
+    let result = Mnemonic::parse(&phrase, language);
+    phrase.into_bytes().fill(0);
+    let mnemonic = result.map_err(|error| error.to_string())?;
+    println!("Valid {} phrase of {} words, {} bits of entropy", mnemonic.language().name(), mnemonic.words().len(), mnemonic.entropy().len() * 8);
+
+    if print_seed {
+        let passphrase = lines.next().unwrap_or(Ok(String::new())).map_err(|error| format!("could not read stdin: {}", error))?;
+        let mut seed = mnemonic.to_seed(&passphrase).map_err(|error| error.to_string())?;
+        passphrase.into_bytes().fill(0);
+        println!("{}", encoding::hex_encode(&seed, false));
+        seed.fill(0);
+    }
+    Ok(())
+}
+
+fn run_draw(pool: &EntropyPool, args: &[String]) -> Result<(), String> {
+    let argument = |i: usize, name: &str| args.get(i).cloned().ok_or(format!("missing {}", name));
+    let read_file = |path: &str| fs::read_to_string(path).map_err(|error| format!("could not read {}: {}", path, error));
+
+    match argument(0, "draw step: commit, reveal or verify")?.as_str() {
+        "commit" => {
+            let participants = read_file(&argument(1, "participants file")?)?.lines().map(|line| line.to_string()).collect::<Vec<String>>();
+            let winner_count = parse_count(&argument(2, "number of winners")?)?;
+            let state_path = argument(3, "state file")?;
+
+            collect_entropy(pool)?;
+            let draw = Draw::commit(pool, &participants, winner_count)?;
+            draw.save(Path::new(&state_path)).map_err(|error| format!("could not write {}: {}", state_path, error))?;
+            println!("commitment: {}", encoding::hex_encode(&draw.commitment(), false));
+            println!("participants: {}, winners: {}", draw.participants.len(), draw.winner_count);
+            eprintln!("Keep {} secret until the draw is revealed.", state_path);
+        }
+        "reveal" => {
+            let state = DrawTranscript::parse(&read_file(&argument(1, "state file")?)?)?;
+            if state.draw.commitment() != state.commitment {
+                return Err("the state file does not match its own commitment".to_string());
+            }
+            let beacon = match (args.get(2).map(|arg| arg.as_str()), args.get(3)) {
+                (Some("--beacon"), Some(beacon)) => beacon.clone(),
+                (None, _) => String::new(),
+                _ => return Err("expected --beacon <value>".to_string()),
+            };
+            print!("{}", state.draw.reveal(&beacon).to_text());
+        }
+        "verify" => {
+            let transcript = DrawTranscript::parse(&read_file(&argument(1, "transcript file")?)?)?;
+            transcript.verify()?;
+            println!("OK: the commitment matches and replaying the draw gives the same {} winner(s)", transcript.winners.len());
+        }
+        step => return Err(format!("unknown draw step: {}", step)),
+    }
+    Ok(())
+}
+
+fn run_ceremony(pool: &EntropyPool, args: &[String]) -> Result<(), String> {
+    let argument = |i: usize, name: &str| args.get(i).cloned().ok_or(format!("missing {}", name));
+    let read_file = |path: &str| fs::read_to_string(path).map_err(|error| format!("could not read {}: {}", path, error));
+
+    match argument(0, "ceremony step: contribute, open, reveal or verify")?.as_str() {
+        "contribute" => {
+            let name = argument(1, "participant name")?;
+            collect_entropy(pool)?;
+            let contribution = Contribution::generate(pool, &name)?;
+            print!("{}", contribution.commitment().to_line());
+            print!("{}", contribution.to_line());
+            eprintln!("Hand in the commitment line now. Keep the contribution line secret until everyone has committed.");
+        }
+        "open" => {
+            let commitments = read_file(&argument(1, "commitments file")?)?.lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(Commitment::parse)
+                .collect::<Result<Vec<Commitment>, String>>()?;
+            let state_path = argument(2, "state file")?;
+            let secret_size = match (args.get(3).map(|arg| arg.as_str()), args.get(4)) {
+                (Some("--size"), Some(size)) => parse_count(size)?,
+                (None, _) => 32,
+                _ => return Err("expected --size <bytes>".to_string()),
+            };
+
+            collect_entropy(pool)?;
+            let ceremony = Ceremony::open(pool, &commitments, secret_size)?;
+            ceremony.save(Path::new(&state_path)).map_err(|error| format!("could not write {}: {}", state_path, error))?;
+            println!("public key: {}", encoding::hex_encode(&ceremony.public_key(), false));
+            print!("{}", ceremony.pool_commitment().to_line());
+            println!("participants: {}, secret size: {} bytes", commitments.len(), secret_size);
+            eprintln!("Announce the public key and commitment, and keep {} secret until the reveal.", state_path);
+        }
+        "reveal" => {
+            let ceremony = Ceremony::parse(&read_file(&argument(1, "state file")?)?)?;
+            let contributions = read_file(&argument(2, "contributions file")?)?.lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(Contribution::parse)
+                .collect::<Result<Vec<Contribution>, String>>()?;
+            let (secret_path, reveal_path) = match args.get(3..).unwrap_or_default() {
+                [out, secret_path, reveal, reveal_path] if matches!(out.as_str(), "-o" | "--out") && reveal == "--reveal" => (secret_path, reveal_path),
+                _ => return Err("expected -o <secret-file> --reveal <reveal-file>".to_string()),
+            };
+            if Path::new(reveal_path).exists() {
+                return Err(format!("{} already exists", reveal_path));
+            }
+
+            let (mut secret, transcript, reveal) = ceremony.reveal(&contributions)?;
+            let result = ceremony::save_secret(&secret, Path::new(secret_path));
+            secret.fill(0);
+            result.map_err(|error| format!("could not write {}: {}", secret_path, error))?;
+            reveal.save(Path::new(reveal_path)).map_err(|error| format!("could not write {}: {}", reveal_path, error))?;
+            print!("{}", transcript.to_text());
+            eprintln!("Publish the transcript. Give {} only to those who may learn the secret.", reveal_path);
+        }
+        "verify" => {
+            let transcript = CeremonyTranscript::parse(&read_file(&argument(1, "transcript file")?)?)?;
+            let reveal = args.get(2).map(|path| CeremonyReveal::parse(&read_file(path)?)).transpose()?;
+            transcript.verify(reveal.as_ref())?;
+            match reveal {
+                Some(_) => println!("OK: the signature is valid and all {} contributions went into the secret", transcript.commitments.len()),
+                None => println!("OK: the signature is valid; pass the reveal file to check that every contribution went into the secret"),
+            }
+        }
+        step => return Err(format!("unknown ceremony step: {}", step)),
+    }
+    Ok(())
+}
+
+/// Whether the command generates its output, rather than only checking or reporting. Site
+/// passwords count: the mode decides which algorithms may derive them.
+fn generates(args: &[String]) -> bool {
+    let step = args.get(1).map(|arg| arg.as_str());
+    match args.first().map(|arg| arg.as_str()) {
+        Some("bytes" | "token" | "id" | "random" | "site-password") => true,
+        Some("mnemonic") => step != Some("check"),
+        Some("draw") => step == Some("commit"),
+        Some("ceremony") => step != Some("verify"),
+        Some("analyze") => matches!(parse_analyze_args(&args[1..]), Ok((None, _, _))),
+        _ => false,
+    }
+}
+
+/// Whether the command reads and replaces the seed file. `analyze` throws its streams away
+/// once they are tested, so like the informational commands it leaves the file alone.
+fn uses_seed_file(args: &[String]) -> bool {
+    generates(args) && args[0] != "analyze"
+}
+
+/// Notes on stderr which algorithms produced the output, after commands that generate in
+/// FIPS-approved mode. Standard mode stays quiet.
+fn report_operating_mode(pool: &EntropyPool, args: &[String]) {
+    let operating_mode = pool.operating_mode();
+    if operating_mode == OperatingMode::FipsApproved && generates(args) {
+        eprintln!("P-GEN: generated in {} mode: {}", operating_mode.name(), operating_mode.algorithms());
+    }
+}
+
+fn print_operating_mode(pool: &EntropyPool) {
+    let operating_mode = pool.operating_mode();
+    println!("Operating mode: {}, {}", operating_mode.name(), operating_mode.algorithms());
+}
+
+fn run_health(pool: &EntropyPool) -> Result<(), String> {
+    print_operating_mode(pool);
+    // Give the kernel state collector something to report on.
+    pool.collect_system_entropy(f64::INFINITY, HEALTH_SYSTEM_ENTROPY_DURATION);
+    let report = pool.health_report();
+    for source in &report {
+        println!("{}", source);
+    }
+    match report.iter().filter(|source| source.status == HealthStatus::Degraded).count() {
+        0 => Ok(()),
+        degraded => Err(format!("{} source(s) degraded", degraded)),
+    }
+}
+
+/// The file to analyze, if any, the stream size and the password length.
+fn parse_analyze_args(args: &[String]) -> Result<(Option<String>, u64, usize), String> {
+    let mut path = None;
+    let mut size = ANALYZE_DEFAULT_SIZE;
+    let mut character_count = ANALYZE_DEFAULT_CHARACTERS;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("missing value after {}", arg));
+        match arg.as_str() {
+            "--size" => size = parse_size(value()?)?,
+            "--characters" => character_count = parse_count(value()?)?,
+            _ if path.is_none() => path = Some(arg.clone()),
+            _ => return Err(format!("unexpected argument: {}", arg)),
+        }
+    }
+    Ok((path, size, character_count))
+}
+
+fn run_analyze(pool: &EntropyPool, args: &[String]) -> Result<(), String> {
+    let (path, size, character_count) = parse_analyze_args(args)?;
+    let mut results = Vec::new();
+    match &path {
+        Some(path) => {
+            let data = fs::read(path).map_err(|error| format!("could not read {}: {}", path, error))?;
+            println!("{} ({} bytes):", path, data.len());
+            print_analysis(analysis::analyze_bytes(&data), &mut results);
+        }
+        None => {
+            collect_entropy(pool)?;
+            let data = generator::generate_stream(pool, size as usize).map_err(|error| error.to_string())?;
+            println!("Generated stream ({} bytes):", data.len());
+            print_analysis(analysis::analyze_bytes(&data), &mut results);
+
+            let size = u32::try_from(character_count).map_err(|_| format!("too many characters: {}", character_count))?;
+            let classes: [PasswordClass; 9] = [
+                ("uppercase", |params| params.use_uppercase_chars = true),
+                ("lowercase", |params| params.use_lowercase_chars = true),
+                ("numbers", |params| params.use_numbers = true),
+                ("logograms", |params| params.use_logograms = true),
+                ("punctuation", |params| params.use_punctuation = true),
+                ("quotation marks", |params| params.use_quotation_marks = true),
+                ("dashes and slashes", |params| params.use_dashes_and_slashes = true),
+                ("maths symbols", |params| params.use_maths_symbols = true),
+                ("brackets", |params| params.use_brackets = true),
+            ];
+            let mut every_class = GeneratePasswordParams { size, ..Default::default() };
+            let mut alphabets = Vec::new();
+            for (name, enable) in classes {
+                let mut params = GeneratePasswordParams { size, ..Default::default() };
+                enable(&mut params);
+                enable(&mut every_class);
+                alphabets.push((format!("Characters: {}", name), params));
+            }
+            alphabets.push(("Characters: all classes".to_string(), every_class));
+
+            println!("Generated passwords ({} characters each):", character_count);
+            for (name, params) in alphabets {
+                collect_entropy(pool)?;
+                let password = generator::generate_password(pool, &params).map_err(|error| error.to_string())?;
+                print_analysis(vec![analysis::analyze_characters(&name, &password, &generator::password_characters(&params))], &mut results);
+            }
+        }
+    }
+
+    match results.iter().filter(|result| result.failed()).count() {
+        0 => Ok(()),
+        failed => Err(format!("{} of {} test(s) failed at significance level {}", failed, results.len(), analysis::SIGNIFICANCE_LEVEL)),
+    }
+}
+
+/// A password character class, and how to enable it.
+type PasswordClass = (&'static str, fn(&mut GeneratePasswordParams));
+
+fn print_analysis(new_results: Vec<TestResult>, results: &mut Vec<TestResult>) {
+    for result in new_results {
+        println!("  {}", result);
+        results.push(result);
+    }
+}
+
+fn run_self_test(pool: &EntropyPool) -> Result<(), String> {
+    print_operating_mode(pool);
+    let results = self_test::run();
+    for test in &results {
+        match &test.result {
+            Ok(()) => println!("{}: passed", test.name),
+            Err(reason) => println!("{}: FAILED, {}", test.name, reason),
+        }
+    }
+    match results.iter().filter(|test| test.result.is_err()).count() {
+        0 => Ok(()),
+        failed => Err(format!("{} self-test(s) failed; generation is disabled", failed)),
+    }
+}
+
+fn read_stdin_lines() -> Result<Vec<String>, String> {
+    let lines = io::stdin().lock().lines().collect::<io::Result<Vec<String>>>().map_err(|error| format!("could not read stdin: {}", error))?;
+    Ok(lines.into_iter().map(|line| line.trim().to_string()).filter(|line| !line.is_empty()).collect())
+}
+
+fn parse_count(text: &str) -> Result<usize, String> {
+    text.parse().map_err(|_| format!("invalid number: {}", text))
+}
+
+/// Parses a byte count such as `64`, `512K`, `16M` or `4G`.
+fn parse_size(text: &str) -> Result<u64, String> {
+    let (digits, multiplier) = match text.char_indices().last() {
+        Some((i, 'K' | 'k')) => (&text[..i], 1 << 10),
+        Some((i, 'M' | 'm')) => (&text[..i], 1 << 20),
+        Some((i, 'G' | 'g')) => (&text[..i], 1 << 30),
+        _ => (text, 1),
+    };
+    digits.parse::<u64>().ok()
+        .and_then(|count| count.checked_mul(multiplier))
+        .ok_or_else(|| format!("invalid size: {}", text))
+}
+
+/// Release builds use the Windows GUI subsystem and start without a console, so borrow the
+/// one we were launched from to make stdout and stderr visible.
+#[cfg(windows)]
+fn attach_parent_console() {
+    use windows_sys::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(windows))]
+fn attach_parent_console() {}
\ No newline at end of file
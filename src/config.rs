@@ -0,0 +1,103 @@
+//! The configuration file, for settings that should hold for every run without being
+//! repeated on each command line.
+//!
+//! Each line is `key = value`; blank lines and lines starting with `#` are ignored. An unknown
+//! key or a value that doesn't parse is an error rather than being skipped, so a typo can't
+//! quietly leave a setting at its default. Settings from the command line take precedence
+//! over the environment, which takes precedence over this file.
+//!
+//! ```text
+//! # Generate with FIPS-approved algorithms only.
+//! fips-mode = on
+//! ```
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::pool::OperatingMode;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Config {
+    /// `fips-mode`, if the file sets it.
+    pub operating_mode: Option<OperatingMode>,
+}
+
+impl Config {
+    /// Reads the file at `path`. A missing file is the same as an empty one.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        match fs::read_to_string(path) {
+            Ok(text) => Self::parse(&text).map_err(|reason| format!("{}: {}", path.display(), reason)),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(error) => Err(format!("could not read {}: {}", path.display(), error)),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut config = Self::default();
+        for (line_number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = |reason: &str| format!("line {}: {}", line_number + 1, reason);
+            let (key, value) = line.split_once('=').ok_or_else(|| invalid("expected key = value"))?;
+            match key.trim() {
+                "fips-mode" => {
+                    let operating_mode = OperatingMode::parse_switch(value.trim()).ok_or_else(|| invalid("fips-mode is on or off"))?;
+                    config.operating_mode = Some(operating_mode);
+                }
+                key => return Err(invalid(&format!("unknown setting {}", key))),
+            }
+        }
+        Ok(config)
+    }
+}
+
+/// `%APPDATA%\P-GEN\config` on Windows, `$XDG_CONFIG_HOME/p-gen/config` (by default under
+/// `~/.config`) elsewhere. `None` if no home directory can be found.
+pub fn default_path() -> Option<PathBuf> {
+    if cfg!(windows) {
+        return std::env::var_os("APPDATA").map(|app_data| PathBuf::from(app_data).join("P-GEN").join("config"));
+    }
+    let config_home = match std::env::var_os("XDG_CONFIG_HOME").filter(|path| !path.is_empty()) {
+        Some(config_home) => PathBuf::from(config_home),
+        None => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+    };
+    Some(config_home.join("p-gen").join("config"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entropy::deterministic::scratch_directory;
+
+    #[test]
+    fn fips_mode_is_read() {
+        assert_eq!(Config::parse("").unwrap().operating_mode, None);
+        assert_eq!(Config::parse("# comment\n\n  fips-mode = on  \n").unwrap().operating_mode, Some(OperatingMode::FipsApproved));
+        assert_eq!(Config::parse("fips-mode=TRUE").unwrap().operating_mode, Some(OperatingMode::FipsApproved));
+        assert_eq!(Config::parse("fips-mode = off").unwrap().operating_mode, Some(OperatingMode::Standard));
+        assert_eq!(Config::parse("fips-mode = on\nfips-mode = 0").unwrap().operating_mode, Some(OperatingMode::Standard));
+    }
+
+    #[test]
+    fn mistakes_are_errors() {
+        assert_eq!(Config::parse("fips-mode = maybe").unwrap_err(), "line 1: fips-mode is on or off");
+        assert_eq!(Config::parse("\nfips_mode = on").unwrap_err(), "line 2: unknown setting fips_mode");
+        assert_eq!(Config::parse("fips-mode").unwrap_err(), "line 1: expected key = value");
+    }
+
+    #[test]
+    fn files_are_loaded() {
+        let directory = scratch_directory("config");
+        let path = directory.join("config");
+        assert_eq!(Config::load(&path).unwrap(), Config::default());
+
+        fs::write(&path, "fips-mode = on\n").unwrap();
+        assert_eq!(Config::load(&path).unwrap().operating_mode, Some(OperatingMode::FipsApproved));
+        fs::write(&path, "fips-mode = sometimes\n").unwrap();
+        assert!(Config::load(&path).unwrap_err().ends_with("config: line 1: fips-mode is on or off"));
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
\ No newline at end of file
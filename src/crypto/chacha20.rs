@@ -1,10 +1,286 @@
-use chacha20::ChaCha20;
+//! ChaCha20 and XChaCha20, bare and as Poly1305 AEADs.
+//!
+//! Keys and nonces are fixed-size types, so a wrong length is caught when one is built from
+//! bytes instead of panicking deep inside the cipher. The bare ciphers are malleable and only
+//! fit for keystreams; anything stored or sent should go through an AEAD, and preferably
+//! through `xchacha20_poly1305_seal`, which picks a random 24-byte nonce itself so the caller
+//! can't reuse one.
+
+use std::fmt;
+
+use chacha20::{ChaCha20, XChaCha20};
 use chacha20::cipher::{KeyIvInit, StreamCipher};
+use chacha20poly1305::{ChaCha20Poly1305, KeyInit, XChaCha20Poly1305};
+use chacha20poly1305::aead::{Aead, Payload};
+
+use crate::entropy;
+
+pub const KEY_SIZE: usize = 32;
+pub const NONCE_SIZE: usize = 12;
+pub const EXTENDED_NONCE_SIZE: usize = 24;
+/// Length of the Poly1305 tag the AEADs append to the ciphertext.
+pub const TAG_SIZE: usize = 16;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CryptoError {
+    InvalidKeyLength { length: usize },
+    InvalidNonceLength { expected: usize, length: usize },
+    /// The plaintext is longer than the AEAD can encrypt under one nonce.
+    MessageTooLong,
+    /// The ciphertext, tag, nonce or associated data were tampered with, or the key is wrong.
+    AuthenticationFailed,
+}
+
+impl fmt::Display for CryptoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CryptoError::InvalidKeyLength { length } => write!(f, "keys must be {} bytes, not {}", KEY_SIZE, length),
+            CryptoError::InvalidNonceLength { expected, length } => write!(f, "nonces must be {} bytes, not {}", expected, length),
+            CryptoError::MessageTooLong => write!(f, "message too long to encrypt under one nonce"),
+            CryptoError::AuthenticationFailed => write!(f, "authentication failed: the data was modified or the key is wrong"),
+        }
+    }
+}
+
+impl std::error::Error for CryptoError {}
+
+/// A 256-bit key. It is wiped when dropped and never printed.
+#[derive(Clone)]
+pub struct Key([u8; KEY_SIZE]);
+
+impl Key {
+    pub fn new(bytes: [u8; KEY_SIZE]) -> Self {
+        Self(bytes)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; KEY_SIZE] {
+        &self.0
+    }
+}
+
+impl TryFrom<&[u8]> for Key {
+    type Error = CryptoError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, CryptoError> {
+        Ok(Self(bytes.try_into().map_err(|_| CryptoError::InvalidKeyLength { length: bytes.len() })?))
+    }
+}
+
+impl Drop for Key {
+    fn drop(&mut self) {
+        self.0.fill(0);
+    }
+}
+
+impl fmt::Debug for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Key(..)")
+    }
+}
+
+/// A 96-bit nonce for ChaCha20. Too short to pick at random safely: a counter, or any other
+/// scheme that never repeats under the same key, must produce it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Nonce([u8; NONCE_SIZE]);
+
+impl Nonce {
+    pub fn new(bytes: [u8; NONCE_SIZE]) -> Self {
+        Self(bytes)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; NONCE_SIZE] {
+        &self.0
+    }
+}
+
+impl TryFrom<&[u8]> for Nonce {
+    type Error = CryptoError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, CryptoError> {
+        Ok(Self(bytes.try_into().map_err(|_| CryptoError::InvalidNonceLength { expected: NONCE_SIZE, length: bytes.len() })?))
+    }
+}
+
+/// A 192-bit nonce for XChaCha20, long enough to be picked at random.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ExtendedNonce([u8; EXTENDED_NONCE_SIZE]);
+
+impl ExtendedNonce {
+    pub fn new(bytes: [u8; EXTENDED_NONCE_SIZE]) -> Self {
+        Self(bytes)
+    }
+
+    /// A fresh nonce from the OS RNG.
+    pub fn generate() -> Self {
+        let mut bytes = [0u8; EXTENDED_NONCE_SIZE];
+        bytes.copy_from_slice(&entropy::generate_bytes(EXTENDED_NONCE_SIZE as u32));
+        Self(bytes)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; EXTENDED_NONCE_SIZE] {
+        &self.0
+    }
+}
+
+impl TryFrom<&[u8]> for ExtendedNonce {
+    type Error = CryptoError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, CryptoError> {
+        Ok(Self(bytes.try_into().map_err(|_| CryptoError::InvalidNonceLength { expected: EXTENDED_NONCE_SIZE, length: bytes.len() })?))
+    }
+}
+
+/// XORs `data` with the ChaCha20 keystream, starting at block 0.
+pub fn chacha20_apply_keystream(key: &Key, nonce: &Nonce, data: &mut [u8]) {
+    ChaCha20::new(key.as_bytes().into(), nonce.as_bytes().into()).apply_keystream(data);
+}
+
+/// XORs `data` with the XChaCha20 keystream, starting at block 0.
+pub fn xchacha20_apply_keystream(key: &Key, nonce: &ExtendedNonce, data: &mut [u8]) {
+    XChaCha20::new(key.as_bytes().into(), nonce.as_bytes().into()).apply_keystream(data);
+}
+
+/// RFC 8439 ChaCha20-Poly1305. Returns the ciphertext with the tag appended.
+pub fn chacha20_poly1305_encrypt(key: &Key, nonce: &Nonce, associated_data: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    ChaCha20Poly1305::new(key.as_bytes().into())
+        .encrypt(nonce.as_bytes().into(), Payload { msg: plaintext, aad: associated_data })
+        .map_err(|_| CryptoError::MessageTooLong)
+}
+
+/// Checks and decrypts what `chacha20_poly1305_encrypt` returned.
+pub fn chacha20_poly1305_decrypt(key: &Key, nonce: &Nonce, associated_data: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    ChaCha20Poly1305::new(key.as_bytes().into())
+        .decrypt(nonce.as_bytes().into(), Payload { msg: ciphertext, aad: associated_data })
+        .map_err(|_| CryptoError::AuthenticationFailed)
+}
+
+/// XChaCha20-Poly1305. Returns the ciphertext with the tag appended.
+pub fn xchacha20_poly1305_encrypt(key: &Key, nonce: &ExtendedNonce, associated_data: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    XChaCha20Poly1305::new(key.as_bytes().into())
+        .encrypt(nonce.as_bytes().into(), Payload { msg: plaintext, aad: associated_data })
+        .map_err(|_| CryptoError::MessageTooLong)
+}
+
+/// Checks and decrypts what `xchacha20_poly1305_encrypt` returned.
+pub fn xchacha20_poly1305_decrypt(key: &Key, nonce: &ExtendedNonce, associated_data: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    XChaCha20Poly1305::new(key.as_bytes().into())
+        .decrypt(nonce.as_bytes().into(), Payload { msg: ciphertext, aad: associated_data })
+        .map_err(|_| CryptoError::AuthenticationFailed)
+}
+
+/// Encrypts `plaintext` under a fresh random nonce with XChaCha20-Poly1305 and returns the
+/// nonce, the ciphertext and the tag, in that order.
+pub fn xchacha20_poly1305_seal(key: &Key, associated_data: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    let nonce = ExtendedNonce::generate();
+    let mut sealed = nonce.as_bytes().to_vec();
+    sealed.append(&mut xchacha20_poly1305_encrypt(key, &nonce, associated_data, plaintext)?);
+    Ok(sealed)
+}
+
+/// Checks and decrypts what `xchacha20_poly1305_seal` returned.
+pub fn xchacha20_poly1305_open(key: &Key, associated_data: &[u8], sealed: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    if sealed.len() < EXTENDED_NONCE_SIZE + TAG_SIZE {
+        return Err(CryptoError::AuthenticationFailed);
+    }
+    let (nonce, ciphertext) = sealed.split_at(EXTENDED_NONCE_SIZE);
+    xchacha20_poly1305_decrypt(key, &ExtendedNonce::try_from(nonce)?, associated_data, ciphertext)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding;
+
+    fn key() -> Key {
+        Key::new(std::array::from_fn(|i| 0x80 + i as u8))
+    }
+
+    /// Block `block` of the ChaCha20 keystream.
+    fn keystream_block(key: &Key, nonce: &Nonce, block: usize) -> String {
+        let mut keystream = vec![0u8; 64 * (block + 1)];
+        chacha20_apply_keystream(key, nonce, &mut keystream);
+        encoding::hex_encode(&keystream[64 * block..], false)
+    }
+
+    /// RFC 8439 appendix A.1, test vectors 2, 3 and 5.
+    #[test]
+    fn keystream_matches_rfc_8439() {
+        assert_eq!(keystream_block(&Key::new([0; 32]), &Nonce::new([0; 12]), 1), "9f07e7be5551387a98ba977c732d080dcb0f29a048e3656912c6533e32ee7aed29b721769ce64e43d57133b074d839d531ed1f28510afb45ace10a1f4b794d6f");
+        let mut one = [0; 32];
+        one[31] = 1;
+        assert_eq!(keystream_block(&Key::new(one), &Nonce::new([0; 12]), 1), "3aeb5224ecf849929b9d828db1ced4dd832025e8018b8160b82284f3c949aa5a8eca00bbb4a73bdad192b5c42f73f2fd4e273644c8b36125a64addeb006c13a0");
+        let mut two = [0; 12];
+        two[11] = 2;
+        assert_eq!(keystream_block(&Key::new([0; 32]), &Nonce::new(two), 0), "c2c64d378cd536374ae204b9ef933fcd1a8b2288b3dfa49672ab765b54ee27c78a970e0e955c14f3a88e741b97c286f75f8fc299e8148362fa198a39531bed6d");
+    }
+
+    #[test]
+    fn keystreams_are_their_own_inverse() {
+        let nonce = ExtendedNonce::new([3; 24]);
+        let mut data = b"attack at dawn".to_vec();
+        xchacha20_apply_keystream(&key(), &nonce, &mut data);
+        assert_ne!(data, b"attack at dawn");
+        xchacha20_apply_keystream(&key(), &nonce, &mut data);
+        assert_eq!(data, b"attack at dawn");
+    }
+
+    #[test]
+    fn lengths_are_checked() {
+        assert_eq!(Key::try_from(&[0u8; 31][..]).unwrap_err(), CryptoError::InvalidKeyLength { length: 31 });
+        assert_eq!(Nonce::try_from(&[0u8; 24][..]).unwrap_err(), CryptoError::InvalidNonceLength { expected: NONCE_SIZE, length: 24 });
+        assert_eq!(ExtendedNonce::try_from(&[0u8; 12][..]).unwrap_err(), CryptoError::InvalidNonceLength { expected: EXTENDED_NONCE_SIZE, length: 12 });
+        assert_eq!(Key::try_from(&[7u8; 32][..]).unwrap().as_bytes(), &[7; 32]);
+        assert_eq!(Nonce::try_from(&[7u8; 12][..]).unwrap(), Nonce::new([7; 12]));
+        assert_eq!(ExtendedNonce::try_from(&[7u8; 24][..]).unwrap(), ExtendedNonce::new([7; 24]));
+    }
+
+    #[test]
+    fn keys_are_not_printed() {
+        assert_eq!(format!("{:?}", key()), "Key(..)");
+    }
+
+    #[test]
+    fn tampering_is_detected() {
+        let nonce = Nonce::new([1; 12]);
+        let sealed = chacha20_poly1305_encrypt(&key(), &nonce, b"header", b"message").unwrap();
+        assert_eq!(sealed.len(), 7 + TAG_SIZE);
+        assert_eq!(chacha20_poly1305_decrypt(&key(), &nonce, b"header", &sealed).unwrap(), b"message");
+
+        let failed = Err(CryptoError::AuthenticationFailed);
+        assert_eq!(chacha20_poly1305_decrypt(&key(), &nonce, b"footer", &sealed), failed);
+        assert_eq!(chacha20_poly1305_decrypt(&key(), &Nonce::new([2; 12]), b"header", &sealed), failed);
+        assert_eq!(chacha20_poly1305_decrypt(&Key::new([0; 32]), &nonce, b"header", &sealed), failed);
+        assert_eq!(chacha20_poly1305_decrypt(&key(), &nonce, b"header", &sealed[..sealed.len() - 1]), failed);
+        for position in [0, sealed.len() - 1] {
+            let mut tampered = sealed.clone();
+            tampered[position] ^= 0x80;
+            assert_eq!(chacha20_poly1305_decrypt(&key(), &nonce, b"header", &tampered), failed);
+        }
+    }
+
+    #[test]
+    fn sealing_picks_a_fresh_nonce() {
+        let first = xchacha20_poly1305_seal(&key(), b"header", b"message").unwrap();
+        let second = xchacha20_poly1305_seal(&key(), b"header", b"message").unwrap();
+        assert_eq!(first.len(), EXTENDED_NONCE_SIZE + 7 + TAG_SIZE);
+        assert_ne!(first[..EXTENDED_NONCE_SIZE], second[..EXTENDED_NONCE_SIZE]);
+        assert_ne!(first[EXTENDED_NONCE_SIZE..], second[EXTENDED_NONCE_SIZE..]);
 
-pub fn encrypt_data(key: Vec<u8>, iv: Vec<u8>, data: &mut Vec<u8>) {
-    let key = key.as_slice();
-    let iv = iv.as_slice();
+        let nonce = ExtendedNonce::try_from(&first[..EXTENDED_NONCE_SIZE]).unwrap();
+        assert_eq!(xchacha20_poly1305_encrypt(&key(), &nonce, b"header", b"message").unwrap(), first[EXTENDED_NONCE_SIZE..]);
+        assert_eq!(xchacha20_poly1305_open(&key(), b"header", &first).unwrap(), b"message");
+        assert_eq!(xchacha20_poly1305_open(&key(), b"header", &second).unwrap(), b"message");
+    }
 
-    let mut cipher = ChaCha20::new(key.into(), iv.into());
-    cipher.apply_keystream(data);
+    #[test]
+    fn opening_rejects_bad_input() {
+        let sealed = xchacha20_poly1305_seal(&key(), b"", b"").unwrap();
+        assert_eq!(sealed.len(), EXTENDED_NONCE_SIZE + TAG_SIZE);
+        assert_eq!(xchacha20_poly1305_open(&key(), b"", &sealed).unwrap(), b"");
+        assert_eq!(xchacha20_poly1305_open(&key(), b"", &sealed[1..]), Err(CryptoError::AuthenticationFailed));
+        assert_eq!(xchacha20_poly1305_open(&key(), b"", &[]), Err(CryptoError::AuthenticationFailed));
+        let mut tampered = sealed.clone();
+        tampered[0] ^= 1;
+        assert_eq!(xchacha20_poly1305_open(&key(), b"", &tampered), Err(CryptoError::AuthenticationFailed));
+    }
 }
\ No newline at end of file
@@ -0,0 +1,187 @@
+//! HMAC_DRBG with SHA-512, as specified in NIST SP 800-90A Rev. 1, section 10.1.2.
+//!
+//! This is the generator behind FIPS mode. It supports a security strength of 256 bits, so it
+//! must be instantiated with at least 32 bytes of entropy and a 16-byte nonce. There is no
+//! prediction resistance: callers that want fresh entropy instantiate a new DRBG or reseed.
+
+use std::fmt;
+
+use crate::crypto::hashes;
+
+/// Output length of SHA-512.
+const OUTLEN: usize = 64;
+/// Minimum entropy input for 256-bit security strength, in bytes.
+pub const MIN_ENTROPY_INPUT_SIZE: usize = 32;
+/// Minimum nonce for 256-bit security strength, in bytes.
+pub const MIN_NONCE_SIZE: usize = 16;
+/// Largest request `generate` accepts: 2^19 bits.
+pub const MAX_REQUEST_SIZE: usize = 1 << 16;
+/// Requests allowed before a reseed is required.
+const RESEED_INTERVAL: u64 = 1 << 48;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DrbgError {
+    InsufficientEntropy { size: usize },
+    InsufficientNonce { size: usize },
+    RequestTooLarge { size: usize },
+    ReseedRequired,
+}
+
+impl fmt::Display for DrbgError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DrbgError::InsufficientEntropy { size } => write!(f, "HMAC_DRBG needs at least {} bytes of entropy input, got {}", MIN_ENTROPY_INPUT_SIZE, size),
+            DrbgError::InsufficientNonce { size } => write!(f, "HMAC_DRBG needs a nonce of at least {} bytes, got {}", MIN_NONCE_SIZE, size),
+            DrbgError::RequestTooLarge { size } => write!(f, "HMAC_DRBG can return at most {} bytes per request, not {}", MAX_REQUEST_SIZE, size),
+            DrbgError::ReseedRequired => write!(f, "HMAC_DRBG must be reseeded"),
+        }
+    }
+}
+
+impl std::error::Error for DrbgError {}
+
+pub struct HmacDrbg {
+    key: [u8; OUTLEN],
+    value: [u8; OUTLEN],
+    reseed_counter: u64,
+}
+
+impl HmacDrbg {
+    pub fn instantiate(entropy_input: &[u8], nonce: &[u8], personalization_string: &[u8]) -> Result<Self, DrbgError> {
+        if entropy_input.len() < MIN_ENTROPY_INPUT_SIZE {
+            return Err(DrbgError::InsufficientEntropy { size: entropy_input.len() });
+        }
+        if nonce.len() < MIN_NONCE_SIZE {
+            return Err(DrbgError::InsufficientNonce { size: nonce.len() });
+        }
+        let mut drbg = Self {
+            key: [0; OUTLEN],
+            value: [1; OUTLEN],
+            reseed_counter: 1,
+        };
+        let mut seed_material = [entropy_input, nonce, personalization_string].concat();
+        drbg.update(&seed_material);
+        seed_material.fill(0);
+        Ok(drbg)
+    }
+
+    pub fn reseed(&mut self, entropy_input: &[u8], additional_input: &[u8]) -> Result<(), DrbgError> {
+        if entropy_input.len() < MIN_ENTROPY_INPUT_SIZE {
+            return Err(DrbgError::InsufficientEntropy { size: entropy_input.len() });
+        }
+        let mut seed_material = [entropy_input, additional_input].concat();
+        self.update(&seed_material);
+        seed_material.fill(0);
+        self.reseed_counter = 1;
+        Ok(())
+    }
+
+    /// Fills `output`, which may be at most `MAX_REQUEST_SIZE` bytes.
+    pub fn generate(&mut self, output: &mut [u8], additional_input: &[u8]) -> Result<(), DrbgError> {
+        if output.len() > MAX_REQUEST_SIZE {
+            return Err(DrbgError::RequestTooLarge { size: output.len() });
+        }
+        if self.reseed_counter > RESEED_INTERVAL {
+            return Err(DrbgError::ReseedRequired);
+        }
+        if !additional_input.is_empty() {
+            self.update(additional_input);
+        }
+        for chunk in output.chunks_mut(OUTLEN) {
+            self.value = self.hmac(&[&self.value]);
+            chunk.copy_from_slice(&self.value[..chunk.len()]);
+        }
+        self.update(additional_input);
+        self.reseed_counter += 1;
+        Ok(())
+    }
+
+    /// HMAC_DRBG_Update.
+    fn update(&mut self, provided_data: &[u8]) {
+        self.key = self.hmac(&[&self.value, &[0x00], provided_data]);
+        self.value = self.hmac(&[&self.value]);
+        if provided_data.is_empty() {
+            return;
+        }
+        self.key = self.hmac(&[&self.value, &[0x01], provided_data]);
+        self.value = self.hmac(&[&self.value]);
+    }
+
+    fn hmac(&self, parts: &[&[u8]]) -> [u8; OUTLEN] {
+        let mut data = parts.concat();
+        let mut output = [0u8; OUTLEN];
+        output.copy_from_slice(&hashes::hmac_sha512_compute(&self.key, &data));
+        data.fill(0);
+        output
+    }
+}
+
+impl Drop for HmacDrbg {
+    fn drop(&mut self) {
+        self.key.fill(0);
+        self.value.fill(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn drbg() -> HmacDrbg {
+        HmacDrbg::instantiate(&[1; 32], &[2; 16], b"personalization").unwrap()
+    }
+
+    fn generate(drbg: &mut HmacDrbg, size: usize, additional_input: &[u8]) -> Vec<u8> {
+        let mut output = vec![0u8; size];
+        drbg.generate(&mut output, additional_input).unwrap();
+        output
+    }
+
+    #[test]
+    fn inputs_are_checked() {
+        assert_eq!(HmacDrbg::instantiate(&[0; 31], &[0; 16], &[]).err(), Some(DrbgError::InsufficientEntropy { size: 31 }));
+        assert_eq!(HmacDrbg::instantiate(&[0; 32], &[0; 15], &[]).err(), Some(DrbgError::InsufficientNonce { size: 15 }));
+        assert_eq!(drbg().reseed(&[0; 16], &[]), Err(DrbgError::InsufficientEntropy { size: 16 }));
+        assert_eq!(drbg().generate(&mut vec![0u8; MAX_REQUEST_SIZE + 1], &[]), Err(DrbgError::RequestTooLarge { size: MAX_REQUEST_SIZE + 1 }));
+        assert!(drbg().generate(&mut vec![0u8; MAX_REQUEST_SIZE], &[]).is_ok());
+    }
+
+    #[test]
+    fn reseeding_is_required_after_the_interval() {
+        let mut drbg = drbg();
+        drbg.reseed_counter = RESEED_INTERVAL;
+        generate(&mut drbg, 16, &[]);
+        assert_eq!(drbg.generate(&mut [0u8; 16], &[]), Err(DrbgError::ReseedRequired));
+        drbg.reseed(&[3; 32], &[]).unwrap();
+        assert_eq!(drbg.reseed_counter, 1);
+        generate(&mut drbg, 16, &[]);
+    }
+
+    #[test]
+    fn every_input_changes_the_output() {
+        let reference = generate(&mut drbg(), 64, &[]);
+        assert_eq!(generate(&mut drbg(), 64, &[]), reference);
+        assert_ne!(generate(&mut HmacDrbg::instantiate(&[1; 32], &[2; 16], &[]).unwrap(), 64, &[]), reference);
+        assert_ne!(generate(&mut HmacDrbg::instantiate(&[1; 32], &[3; 16], b"personalization").unwrap(), 64, &[]), reference);
+        assert_ne!(generate(&mut drbg(), 64, b"additional input"), reference);
+
+        let mut reseeded = drbg();
+        reseeded.reseed(&[1; 32], &[]).unwrap();
+        assert_ne!(generate(&mut reseeded, 64, &[]), reference);
+    }
+
+    /// Requests return the start of the blocks a longer request would, and each request moves
+    /// the state on.
+    #[test]
+    fn requests_are_cut_from_whole_blocks() {
+        let long = generate(&mut drbg(), 100, &[]);
+        assert_eq!(generate(&mut drbg(), 70, &[]), long[..70]);
+
+        let mut drbg = drbg();
+        let first = generate(&mut drbg, 64, &[]);
+        let second = generate(&mut drbg, 64, &[]);
+        assert_eq!(first, long[..64]);
+        assert_ne!(second[..36], long[64..]);
+        assert_ne!(first, second);
+    }
+}
\ No newline at end of file
@@ -1,6 +1,36 @@
+use std::fmt;
+use std::io;
+
+use hkdf::Hkdf;
+use hmac::{Hmac, Mac};
+use sha2::{Sha256, Sha512};
 use sha3::{Digest, Sha3_256, Sha3_512};
 use whirlpool::Whirlpool;
 
+/// Longest HKDF-SHA-512 output, 255 blocks of 64 bytes.
+pub const HKDF_SHA512_MAX_OUTPUT_SIZE: usize = 255 * 64;
+/// Shortest pseudorandom key HKDF-SHA-512 expands, one digest.
+pub const HKDF_SHA512_MIN_KEY_SIZE: usize = 64;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HashError {
+    /// HKDF can't expand a key to this many bytes.
+    OutputTooLong { size: usize, max_size: usize },
+    /// HKDF-Expand was given a pseudorandom key shorter than a digest.
+    InvalidKeyLength { length: usize, min_length: usize },
+}
+
+impl fmt::Display for HashError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HashError::OutputTooLong { size, max_size } => write!(f, "cannot derive {} bytes, at most {}", size, max_size),
+            HashError::InvalidKeyLength { length, min_length } => write!(f, "pseudorandom keys must be at least {} bytes, not {}", min_length, length),
+        }
+    }
+}
+
+impl std::error::Error for HashError {}
+
 pub fn sha3_256_compute(data: &[u8]) -> Vec<u8> {
     let mut hasher = Sha3_256::new();
     hasher.update(data);
@@ -63,4 +93,250 @@ pub fn perform_joined_digest(data: Vec<u8>) -> Vec<u8> {
     }
 
     return output_state;
+}
+
+/// SHA-256, for compatibility with formats that use it.
+pub fn sha256_compute(data: &[u8]) -> Vec<u8> {
+    Sha256::digest(data).to_vec()
+}
+
+pub fn sha512_compute(data: &[u8]) -> Vec<u8> {
+    Sha512::digest(data).to_vec()
+}
+
+pub fn hmac_sha3_256_compute(key: &[u8], data: &[u8]) -> Vec<u8> {
+    hmac_compute::<Hmac<Sha3_256>>(key, data)
+}
+
+pub fn hmac_sha3_512_compute(key: &[u8], data: &[u8]) -> Vec<u8> {
+    hmac_compute::<Hmac<Sha3_512>>(key, data)
+}
+
+/// HMAC-SHA-256, for compatibility with formats that use it; P-GEN's own constructions use
+/// SHA-512 or SHA-3.
+pub fn hmac_sha256_compute(key: &[u8], data: &[u8]) -> Vec<u8> {
+    hmac_compute::<Hmac<Sha256>>(key, data)
+}
+
+pub fn hmac_sha512_compute(key: &[u8], data: &[u8]) -> Vec<u8> {
+    hmac_compute::<Hmac<Sha512>>(key, data)
+}
+
+/// Checks an HMAC-SHA3-256 tag in constant time.
+pub fn hmac_sha3_256_verify(key: &[u8], data: &[u8], tag: &[u8]) -> bool {
+    hmac_verify::<Hmac<Sha3_256>>(key, data, tag)
+}
+
+/// Checks an HMAC-SHA3-512 tag in constant time.
+pub fn hmac_sha3_512_verify(key: &[u8], data: &[u8], tag: &[u8]) -> bool {
+    hmac_verify::<Hmac<Sha3_512>>(key, data, tag)
+}
+
+/// Checks an HMAC-SHA-512 tag in constant time.
+pub fn hmac_sha512_verify(key: &[u8], data: &[u8], tag: &[u8]) -> bool {
+    hmac_verify::<Hmac<Sha512>>(key, data, tag)
+}
+
+fn hmac_compute<M: Mac + hmac::digest::KeyInit>(key: &[u8], data: &[u8]) -> Vec<u8> {
+    // HMAC accepts keys of any length.
+    let mut mac = <M as Mac>::new_from_slice(key).unwrap();
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn hmac_verify<M: Mac + hmac::digest::KeyInit>(key: &[u8], data: &[u8], tag: &[u8]) -> bool {
+    let mut mac = <M as Mac>::new_from_slice(key).unwrap();
+    mac.update(data);
+    mac.verify_slice(tag).is_ok()
+}
+
+/// RFC 5869 HKDF-Extract with SHA-512: concentrates the entropy of `input_key_material` into
+/// a 64-byte pseudorandom key. An empty `salt` stands for 64 zero bytes.
+pub fn hkdf_sha512_extract(salt: &[u8], input_key_material: &[u8]) -> Vec<u8> {
+    let (pseudorandom_key, _) = Hkdf::<Sha512>::extract(Some(salt), input_key_material);
+    pseudorandom_key.to_vec()
+}
+
+/// RFC 5869 HKDF-Expand with SHA-512: derives `size` bytes from a pseudorandom key, one
+/// independent output for every distinct `info`.
+pub fn hkdf_sha512_expand(pseudorandom_key: &[u8], info: &[u8], size: usize) -> Result<Vec<u8>, HashError> {
+    let hkdf = Hkdf::<Sha512>::from_prk(pseudorandom_key)
+        .map_err(|_| HashError::InvalidKeyLength { length: pseudorandom_key.len(), min_length: HKDF_SHA512_MIN_KEY_SIZE })?;
+    let mut output = vec![0u8; size];
+    hkdf.expand(info, &mut output).map_err(|_| HashError::OutputTooLong { size, max_size: HKDF_SHA512_MAX_OUTPUT_SIZE })?;
+    Ok(output)
+}
+
+/// BLAKE3 keyed with a 32-byte key, usable as a MAC or a PRF.
+pub fn blake3_keyed_compute(key: &[u8; 32], data: &[u8], size: usize) -> Vec<u8> {
+    let mut output = vec![0u8; size];
+    blake3::Hasher::new_keyed(key).update(data).finalize_xof().fill(&mut output);
+    output
+}
+
+/// BLAKE3's key derivation mode. `context` should be a hardcoded, globally unique string
+/// naming the application and the purpose of the key, such as
+/// `"P-GEN seed file v1 encryption key"`.
+pub fn blake3_derive_key(context: &str, key_material: &[u8]) -> [u8; 32] {
+    blake3::derive_key(context, key_material)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum HashAlgorithm {
+    Sha3_256,
+    Sha3_512,
+    Sha512,
+    Blake3_256,
+    Blake3_512,
+    Whirlpool512,
+}
+
+/// Hashes data fed in pieces, for inputs too large to hold in memory. Also implements
+/// `io::Write`, so a file can be hashed with `io::copy`.
+#[derive(Clone)]
+pub struct IncrementalHasher(HasherState);
+
+#[derive(Clone)]
+enum HasherState {
+    Sha3_256(Sha3_256),
+    Sha3_512(Sha3_512),
+    Sha512(Sha512),
+    Blake3(Box<blake3::Hasher>, usize),
+    Whirlpool(Whirlpool),
+}
+
+impl IncrementalHasher {
+    pub fn new(algorithm: HashAlgorithm) -> Self {
+        Self(match algorithm {
+            HashAlgorithm::Sha3_256     => HasherState::Sha3_256(Sha3_256::new()),
+            HashAlgorithm::Sha3_512     => HasherState::Sha3_512(Sha3_512::new()),
+            HashAlgorithm::Sha512       => HasherState::Sha512(Sha512::new()),
+            HashAlgorithm::Blake3_256   => HasherState::Blake3(Box::new(blake3::Hasher::new()), 32),
+            HashAlgorithm::Blake3_512   => HasherState::Blake3(Box::new(blake3::Hasher::new()), 64),
+            HashAlgorithm::Whirlpool512 => HasherState::Whirlpool(Whirlpool::new()),
+        })
+    }
+
+    /// Keyed BLAKE3 with `size` bytes of output.
+    pub fn new_blake3_keyed(key: &[u8; 32], size: usize) -> Self {
+        Self(HasherState::Blake3(Box::new(blake3::Hasher::new_keyed(key)), size))
+    }
+
+    /// BLAKE3 in key derivation mode, giving the same result as `blake3_derive_key`.
+    pub fn new_blake3_derive_key(context: &str) -> Self {
+        Self(HasherState::Blake3(Box::new(blake3::Hasher::new_derive_key(context)), 32))
+    }
+
+    pub fn update(&mut self, data: &[u8]) -> &mut Self {
+        match &mut self.0 {
+            HasherState::Sha3_256(hasher)  => hasher.update(data),
+            HasherState::Sha3_512(hasher)  => hasher.update(data),
+            HasherState::Sha512(hasher)    => hasher.update(data),
+            HasherState::Blake3(hasher, _) => { hasher.update(data); }
+            HasherState::Whirlpool(hasher) => hasher.update(data),
+        }
+        self
+    }
+
+    pub fn finalize(self) -> Vec<u8> {
+        match self.0 {
+            HasherState::Sha3_256(hasher)  => hasher.finalize().to_vec(),
+            HasherState::Sha3_512(hasher)  => hasher.finalize().to_vec(),
+            HasherState::Sha512(hasher)    => hasher.finalize().to_vec(),
+            HasherState::Whirlpool(hasher) => hasher.finalize().to_vec(),
+            HasherState::Blake3(hasher, size) => {
+                let mut output = vec![0u8; size];
+                hasher.finalize_xof().fill(&mut output);
+                output
+            }
+        }
+    }
+}
+
+impl io::Write for IncrementalHasher {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding;
+
+    fn hex(data: &[u8]) -> String {
+        encoding::hex_encode(data, false)
+    }
+
+    /// One of the Wycheproof HKDF-SHA512 vectors, with a salt and an info string.
+    #[test]
+    fn hkdf_matches_wycheproof() {
+        let input_key_material = encoding::hex_decode("8677dc79233ef3480777c4c601ef4f0b").unwrap();
+        let salt = encoding::hex_decode("ad88db718244e2cb60e35f874d7ad81f").unwrap();
+        let info = encoding::hex_decode("a38f634d947819a9bfa792174b42baa20c9fce15").unwrap();
+        let pseudorandom_key = hkdf_sha512_extract(&salt, &input_key_material);
+        assert_eq!(pseudorandom_key, hmac_sha512_compute(&salt, &input_key_material));
+        assert_eq!(hex(&hkdf_sha512_expand(&pseudorandom_key, &info, 64).unwrap()), "918e9cda37bf7f52506111048a878e64a503f9869d0c2615047b995f1efedc4f713b4dbcc940838e68f6a2bf772ebefae9154e9075da80ea1fd68b9df580ad76");
+    }
+
+    #[test]
+    fn hkdf_rejects_short_keys_and_long_output() {
+        let pseudorandom_key = hkdf_sha512_extract(b"salt", b"input key material");
+        assert_eq!(hkdf_sha512_expand(&pseudorandom_key[..32], b"", 32), Err(HashError::InvalidKeyLength { length: 32, min_length: 64 }));
+        assert_eq!(hkdf_sha512_expand(&pseudorandom_key, b"", HKDF_SHA512_MAX_OUTPUT_SIZE + 1), Err(HashError::OutputTooLong { size: HKDF_SHA512_MAX_OUTPUT_SIZE + 1, max_size: HKDF_SHA512_MAX_OUTPUT_SIZE }));
+
+        let longest = hkdf_sha512_expand(&pseudorandom_key, b"", HKDF_SHA512_MAX_OUTPUT_SIZE).unwrap();
+        assert_eq!(longest[..100], hkdf_sha512_expand(&pseudorandom_key, b"", 100).unwrap());
+        assert_ne!(hkdf_sha512_expand(&pseudorandom_key, b"a", 64), hkdf_sha512_expand(&pseudorandom_key, b"b", 64));
+        assert_eq!(hkdf_sha512_extract(b"", b"input key material"), hkdf_sha512_extract(&[0; 64], b"input key material"));
+    }
+
+    /// RFC 4231 test cases 1 and 6.
+    #[test]
+    fn hmac_matches_rfc_4231() {
+        assert_eq!(hex(&hmac_sha256_compute(&[0x0b; 20], b"Hi There")), "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7");
+        assert_eq!(hex(&hmac_sha512_compute(&[0x0b; 20], b"Hi There")), "87aa7cdea5ef619d4ff0b4241a1d6cb02379f4e2ce4ec2787ad0b30545e17cdedaa833b7d6b8a702038b274eaea3f4e4be9d914eeb61f1702e696c203a126854");
+        assert_eq!(hex(&hmac_sha512_compute(&[0xaa; 131], b"Test Using Larger Than Block-Size Key - Hash Key First")), "80b24263c7c1a3ebb71493c1dd7be8b49b46d1f41b4aeec1121b013783f8f3526b56d037e05f2598bd0fd2215d6a1e5295e64f73f63f0aec8b915a985d786598");
+    }
+
+    #[test]
+    fn hmac_tags_verify() {
+        let tag = hmac_sha512_compute(b"key", b"data");
+        assert!(hmac_sha512_verify(b"key", b"data", &tag));
+        assert!(!hmac_sha512_verify(b"key", b"other data", &tag));
+        assert!(!hmac_sha512_verify(b"key", b"data", &tag[..32]));
+        let tag = hmac_sha3_512_compute(b"key", b"data");
+        assert!(hmac_sha3_512_verify(b"key", b"data", &tag));
+        assert!(!hmac_sha3_512_verify(b"other key", b"data", &tag));
+    }
+
+    #[test]
+    fn sha256_matches_fips_180() {
+        assert_eq!(hex(&sha256_compute(b"abc")), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+    }
+
+    #[test]
+    fn incremental_hashers_match_one_shot_hashes() {
+        let data: Vec<u8> = (0..10_000u32).map(|i| i as u8).collect();
+        let mut hasher = IncrementalHasher::new(HashAlgorithm::Whirlpool512);
+        io::copy(&mut &data[..], &mut hasher).unwrap();
+        assert_eq!(hasher.finalize(), whirlpool_512_compute(&data));
+
+        let mut hasher = IncrementalHasher::new_blake3_derive_key("P-GEN test context");
+        for chunk in data.chunks(333) {
+            hasher.update(chunk);
+        }
+        assert_eq!(hasher.finalize(), blake3_derive_key("P-GEN test context", &data));
+
+        let mut hasher = IncrementalHasher::new_blake3_keyed(&[5; 32], 100);
+        let copy = hasher.clone();
+        hasher.update(&data);
+        assert_eq!(hasher.finalize(), blake3_keyed_compute(&[5; 32], &data, 100));
+        assert_eq!(copy.finalize(), blake3_keyed_compute(&[5; 32], b"", 100));
+    }
 }
\ No newline at end of file
@@ -0,0 +1,129 @@
+//! Password-based key derivation: PBKDF2, scrypt and Argon2id.
+//!
+//! All three are slow on purpose, so that guessing the password behind a derived key costs
+//! an attacker as much per guess as it costs the user once. scrypt and Argon2id also need a
+//! lot of memory per guess, which makes them the better choice against GPUs; PBKDF2 is here
+//! for compatibility and because it is the one NIST approves.
+
+use std::fmt;
+
+use argon2::{Algorithm, Argon2, Version};
+use sha2::{Sha256, Sha512};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum KdfError {
+    /// The cost parameters, salt or output length are outside what the algorithm accepts.
+    InvalidParameters { algorithm: &'static str, reason: String },
+}
+
+impl fmt::Display for KdfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KdfError::InvalidParameters { algorithm, reason } => write!(f, "invalid {} parameters: {}", algorithm, reason),
+        }
+    }
+}
+
+impl std::error::Error for KdfError {}
+
+pub fn pbkdf2_hmac_sha256(password: &[u8], salt: &[u8], iterations: u32, size: usize) -> Result<Vec<u8>, KdfError> {
+    if iterations == 0 {
+        return Err(KdfError::InvalidParameters { algorithm: "PBKDF2", reason: "at least one iteration is needed".to_string() });
+    }
+    let mut output = vec![0u8; size];
+    pbkdf2::pbkdf2_hmac::<Sha256>(password, salt, iterations, &mut output);
+    Ok(output)
+}
+
+pub fn pbkdf2_hmac_sha512(password: &[u8], salt: &[u8], iterations: u32, size: usize) -> Result<Vec<u8>, KdfError> {
+    if iterations == 0 {
+        return Err(KdfError::InvalidParameters { algorithm: "PBKDF2", reason: "at least one iteration is needed".to_string() });
+    }
+    let mut output = vec![0u8; size];
+    pbkdf2::pbkdf2_hmac::<Sha512>(password, salt, iterations, &mut output);
+    Ok(output)
+}
+
+/// scrypt with a cost of `2^log_n`, block size `r` and parallelism `p`. Memory use is
+/// `128 * r * 2^log_n` bytes.
+pub fn scrypt(password: &[u8], salt: &[u8], log_n: u8, r: u32, p: u32, size: usize) -> Result<Vec<u8>, KdfError> {
+    let invalid = |reason: String| KdfError::InvalidParameters { algorithm: "scrypt", reason };
+    let params = scrypt::Params::new(log_n, r, p, size).map_err(|error| invalid(error.to_string()))?;
+    let mut output = vec![0u8; size];
+    scrypt::scrypt(password, salt, &params, &mut output).map_err(|error| invalid(error.to_string()))?;
+    Ok(output)
+}
+
+/// Argon2id, version 1.3, using `memory_kib` KiB of memory over `iterations` passes and
+/// `parallelism` lanes.
+pub fn argon2id(password: &[u8], salt: &[u8], memory_kib: u32, iterations: u32, parallelism: u32, size: usize) -> Result<Vec<u8>, KdfError> {
+    let invalid = |error: argon2::Error| KdfError::InvalidParameters { algorithm: "Argon2id", reason: error.to_string() };
+    let params = argon2::Params::new(memory_kib, iterations, parallelism, Some(size)).map_err(invalid)?;
+    let mut output = vec![0u8; size];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params).hash_password_into(password, salt, &mut output).map_err(invalid)?;
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::hex_encode;
+
+    /// RFC 7914 section 11.
+    #[test]
+    fn pbkdf2_sha256_matches_rfc_7914() {
+        let output = pbkdf2_hmac_sha256(b"passwd", b"salt", 1, 64).unwrap();
+        assert_eq!(hex_encode(&output, false), "55ac046e56e3089fec1691c22544b605f94185216dde0465e68b9d57c20dacbc49ca9cccf179b645991664b39d77ef317c71b845b1e30bd509112041d3a19783");
+        let output = pbkdf2_hmac_sha256(b"Password", b"NaCl", 80000, 64).unwrap();
+        assert_eq!(hex_encode(&output, false), "4ddcd8f60b98be21830cee5ef22701f9641a4418d04c0414aeff08876b34ab56a1d425a1225833549adb841b51c9b3176a272bdebba1d078478f62b397f33c8d");
+    }
+
+    #[test]
+    fn pbkdf2_sha512_matches_known_answers() {
+        let first = pbkdf2_hmac_sha512(b"password", b"salt", 1, 64).unwrap();
+        assert_eq!(hex_encode(&first, false), "867f70cf1ade02cff3752599a3a53dc4af34c7a669815ae5d513554e1c8cf252c02d470a285a0501bad999bfe943c08f050235d7d68b1da55e63f73b60a57fce");
+        let output = pbkdf2_hmac_sha512(b"password", b"salt", 2, 64).unwrap();
+        assert_eq!(hex_encode(&output, false), "e1d9c16aa681708a45f5c7c4e215ceb66e011a2e9f0040713f18aefdb866d53cf76cab2868a39b9f7840edce4fef5a82be67335c77a6068e04112754f27ccf4e");
+        assert_eq!(pbkdf2_hmac_sha512(b"password", b"salt", 1, 20).unwrap(), first[..20]);
+    }
+
+    #[test]
+    fn pbkdf2_needs_an_iteration() {
+        assert!(matches!(pbkdf2_hmac_sha256(b"password", b"salt", 0, 32), Err(KdfError::InvalidParameters { algorithm: "PBKDF2", .. })));
+        assert!(matches!(pbkdf2_hmac_sha512(b"password", b"salt", 0, 32), Err(KdfError::InvalidParameters { algorithm: "PBKDF2", .. })));
+    }
+
+    /// RFC 7914 section 12, the first three vectors.
+    #[test]
+    fn scrypt_matches_rfc_7914() {
+        let output = scrypt(b"", b"", 4, 1, 1, 64).unwrap();
+        assert_eq!(hex_encode(&output, false), "77d6576238657b203b19ca42c18a0497f16b4844e3074ae8dfdffa3fede21442fcd0069ded0948f8326a753a0fc81f17e8d3e0fb2e0d3628cf35e20c38d18906");
+        let output = scrypt(b"password", b"NaCl", 10, 8, 16, 64).unwrap();
+        assert_eq!(hex_encode(&output, false), "fdbabe1c9d3472007856e7190d01e9fe7c6ad7cbc8237830e77376634b3731622eaf30d92e22a3886ff109279d9830dac727afb94a83ee6d8360cbdfa2cc0640");
+        let output = scrypt(b"pleaseletmein", b"SodiumChloride", 14, 8, 1, 64).unwrap();
+        assert_eq!(hex_encode(&output, false), "7023bdcb3afd7348461c06cd81fd38ebfda8fbba904f8e3ea9b543f6545da1f2d5432955613f0fcf62d49705242a9af9e61e85dc0d651e40dfcf017b45575887");
+    }
+
+    #[test]
+    fn scrypt_parameters_are_checked() {
+        let error = scrypt(b"password", b"salt", 4, 0, 1, 64).unwrap_err();
+        assert!(matches!(error, KdfError::InvalidParameters { algorithm: "scrypt", .. }));
+        assert!(error.to_string().starts_with("invalid scrypt parameters: "));
+        assert!(scrypt(b"password", b"salt", 64, 8, 1, 64).is_err());
+    }
+
+    /// The Argon2 reference implementation's Argon2id vector.
+    #[test]
+    fn argon2id_matches_the_reference_implementation() {
+        let output = argon2id(b"password", b"somesalt", 256, 2, 1, 32).unwrap();
+        assert_eq!(hex_encode(&output, false), "9dfeb910e80bad0311fee20f9c0e2b12c17987b4cac90c2ef54d5b3021c68bfe");
+    }
+
+    #[test]
+    fn argon2id_parameters_are_checked() {
+        assert!(matches!(argon2id(b"password", b"somesalt", 1, 2, 1, 32), Err(KdfError::InvalidParameters { algorithm: "Argon2id", .. })));
+        assert!(argon2id(b"password", b"somesalt", 256, 0, 1, 32).is_err());
+        assert!(argon2id(b"password", b"short", 256, 2, 1, 32).is_err());
+        assert!(argon2id(b"password", b"somesalt", 256, 2, 1, 2).is_err());
+    }
+}
\ No newline at end of file
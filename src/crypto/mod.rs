@@ -1,2 +1,4 @@
 pub mod chacha20;
-pub mod hashes;
\ No newline at end of file
+pub mod drbg;
+pub mod hashes;
+pub mod kdf;
\ No newline at end of file
@@ -0,0 +1,189 @@
+//! Text encodings for random bytes. Apart from hex, which transcripts and seed files are
+//! written in, nothing here is meant to be parsed back.
+
+const HEX_LOWER_ALPHABET: &[u8] = b"0123456789abcdef";
+const HEX_UPPER_ALPHABET: &[u8] = b"0123456789ABCDEF";
+const BASE32_ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+const CROCKFORD_BASE32_ALPHABET: &[u8] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+const BASE58_ALPHABET: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+const BASE64_ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const BASE64_URL_ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+const Z85_ALPHABET: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ.-:+=^!/*?&<>()[]{}@%$#";
+
+/// Splits `data` into `bits_per_char`-bit groups, most significant bit first, zero-filling the
+/// last group, and maps each group through `alphabet`. Pads with `=` up to a multiple of
+/// `padded_block` characters when one is given.
+fn encode_bit_groups(data: &[u8], alphabet: &[u8], bits_per_char: u32, padded_block: Option<usize>) -> String {
+    let mut output = String::with_capacity((data.len() * 8).div_ceil(bits_per_char as usize));
+    let mask = (1u32 << bits_per_char) - 1;
+    let mut buffer = 0u32;
+    let mut buffered_bits = 0;
+
+    for &byte in data {
+        buffer = (buffer << 8) | byte as u32;
+        buffered_bits += 8;
+        while buffered_bits >= bits_per_char {
+            buffered_bits -= bits_per_char;
+            output.push(alphabet[((buffer >> buffered_bits) & mask) as usize] as char);
+        }
+    }
+    if buffered_bits > 0 {
+        output.push(alphabet[((buffer << (bits_per_char - buffered_bits)) & mask) as usize] as char);
+    }
+
+    if let Some(block) = padded_block {
+        while !output.len().is_multiple_of(block) {
+            output.push('=');
+        }
+    }
+    output
+}
+
+pub fn hex_encode(data: &[u8], uppercase: bool) -> String {
+    encode_bit_groups(data, if uppercase { HEX_UPPER_ALPHABET } else { HEX_LOWER_ALPHABET }, 4, None)
+}
+
+/// Accepts upper and lower case. Returns `None` on odd length or non-hex characters.
+pub fn hex_decode(text: &str) -> Option<Vec<u8>> {
+    if !text.is_ascii() || !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len()).step_by(2).map(|i| u8::from_str_radix(&text[i..i + 2], 16).ok()).collect()
+}
+
+/// RFC 4648 Base32.
+pub fn base32_encode(data: &[u8], padding: bool) -> String {
+    encode_bit_groups(data, BASE32_ALPHABET, 5, padding.then_some(8))
+}
+
+/// Crockford's Base32, without check symbol.
+pub fn crockford_base32_encode(data: &[u8]) -> String {
+    encode_bit_groups(data, CROCKFORD_BASE32_ALPHABET, 5, None)
+}
+
+/// RFC 4648 Base64 with the standard alphabet.
+pub fn base64_encode(data: &[u8], padding: bool) -> String {
+    encode_bit_groups(data, BASE64_ALPHABET, 6, padding.then_some(4))
+}
+
+/// RFC 4648 Base64 with the URL and filename safe alphabet.
+pub fn base64_url_encode(data: &[u8], padding: bool) -> String {
+    encode_bit_groups(data, BASE64_URL_ALPHABET, 6, padding.then_some(4))
+}
+
+/// Base58 with the Bitcoin alphabet. Every leading zero byte becomes a leading `1`.
+pub fn base58_encode(data: &[u8]) -> String {
+    let leading_zeros = data.iter().take_while(|&&byte| byte == 0).count();
+
+    // Little-endian base-58 digits of the big-endian number in `data`.
+    let mut digits: Vec<u8> = Vec::with_capacity(data.len() * 138 / 100 + 1);
+    for &byte in &data[leading_zeros..] {
+        let mut carry = byte as u32;
+        for digit in digits.iter_mut() {
+            carry += (*digit as u32) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+
+    let mut output = "1".repeat(leading_zeros);
+    output.extend(digits.iter().rev().map(|&digit| BASE58_ALPHABET[digit as usize] as char));
+    output
+}
+
+/// ZeroMQ Z85. Returns `None` unless the length of `data` is a multiple of 4.
+pub fn z85_encode(data: &[u8]) -> Option<String> {
+    if !data.len().is_multiple_of(4) {
+        return None;
+    }
+
+    let mut output = String::with_capacity(data.len() / 4 * 5);
+    for chunk in data.chunks(4) {
+        let mut value = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        let mut characters = [0u8; 5];
+        for character in characters.iter_mut().rev() {
+            *character = Z85_ALPHABET[(value % 85) as usize];
+            value /= 85;
+        }
+        output.extend(characters.iter().map(|&character| character as char));
+    }
+    Some(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The RFC 4648 section 10 inputs.
+    const RFC_4648_INPUTS: [&str; 7] = ["", "f", "fo", "foo", "foob", "fooba", "foobar"];
+
+    #[test]
+    fn base64_matches_rfc_4648() {
+        let expected = ["", "Zg==", "Zm8=", "Zm9v", "Zm9vYg==", "Zm9vYmE=", "Zm9vYmFy"];
+        for (input, expected) in RFC_4648_INPUTS.iter().zip(expected) {
+            assert_eq!(base64_encode(input.as_bytes(), true), expected);
+            assert_eq!(base64_encode(input.as_bytes(), false), expected.trim_end_matches('='));
+        }
+    }
+
+    #[test]
+    fn base64_url_uses_the_safe_alphabet() {
+        assert_eq!(base64_encode(&[0xfb, 0xff, 0xbf], true), "+/+/");
+        assert_eq!(base64_url_encode(&[0xfb, 0xff, 0xbf], true), "-_-_");
+        assert_eq!(base64_url_encode(b"foob", true), "Zm9vYg==");
+        assert_eq!(base64_url_encode(b"foob", false), "Zm9vYg");
+    }
+
+    #[test]
+    fn base32_matches_rfc_4648() {
+        let expected = ["", "MY======", "MZXQ====", "MZXW6===", "MZXW6YQ=", "MZXW6YTB", "MZXW6YTBOI======"];
+        for (input, expected) in RFC_4648_INPUTS.iter().zip(expected) {
+            assert_eq!(base32_encode(input.as_bytes(), true), expected);
+            assert_eq!(base32_encode(input.as_bytes(), false), expected.trim_end_matches('='));
+        }
+    }
+
+    #[test]
+    fn hex_matches_rfc_4648_and_round_trips() {
+        let expected = ["", "66", "666f", "666f6f", "666f6f62", "666f6f6261", "666f6f626172"];
+        for (input, expected) in RFC_4648_INPUTS.iter().zip(expected) {
+            assert_eq!(hex_encode(input.as_bytes(), false), expected);
+            assert_eq!(hex_encode(input.as_bytes(), true), expected.to_uppercase());
+            assert_eq!(hex_decode(expected).unwrap(), input.as_bytes());
+            assert_eq!(hex_decode(&expected.to_uppercase()).unwrap(), input.as_bytes());
+        }
+        assert_eq!(hex_decode("abc"), None);
+        assert_eq!(hex_decode("zz"), None);
+        assert_eq!(hex_decode("é0"), None);
+    }
+
+    #[test]
+    fn crockford_base32_skips_ambiguous_letters() {
+        assert_eq!(crockford_base32_encode(&[0xff; 5]), "ZZZZZZZZ");
+        assert_eq!(crockford_base32_encode(b"foobar"), "CSQPYRK1E8");
+        assert!(!crockford_base32_encode(&(0..=255).collect::<Vec<u8>>()).contains(['I', 'L', 'O', 'U']));
+    }
+
+    #[test]
+    fn base58_matches_the_bitcoin_alphabet() {
+        assert_eq!(base58_encode(b""), "");
+        assert_eq!(base58_encode(b"Hello World!"), "2NEpo7TZRRrLZSi2U");
+        assert_eq!(base58_encode(b"The quick brown fox jumps over the lazy dog."), "USm3fpXnKG5EUBx2ndxBDMPVciP5hGey2Jh4NDv6gmeo1LkMeiKrLJUUBk6Z");
+        assert_eq!(base58_encode(&[0x00, 0x00, 0x28, 0x7f, 0xb4, 0xcd]), "11233QC4");
+        assert_eq!(base58_encode(&[0x00]), "1");
+        assert_eq!(base58_encode(&[0xff]), "5Q");
+    }
+
+    #[test]
+    fn z85_matches_the_spec() {
+        let data = [0x86, 0x4f, 0xd2, 0x6f, 0xb5, 0x59, 0xf7, 0x5b];
+        assert_eq!(z85_encode(&data).unwrap(), "HelloWorld");
+        assert_eq!(z85_encode(&[]).unwrap(), "");
+        assert_eq!(z85_encode(&[0xff; 4]).unwrap(), "%nSc0");
+        assert_eq!(z85_encode(&data[..7]), None);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,245 @@
+//! The background thread that samples the cursor.
+//!
+//! Polling is the only way to follow the cursor across the whole desktop, so the thread keeps
+//! it to when it is wanted: while the window has focus, or during a collection phase the user
+//! started explicitly. Either way it stops once the pool has been credited with the bits it
+//! asked for. The rest of the time the thread sleeps on a condition variable and costs no
+//! wakeups at all.
+
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::pool::EntropyPool;
+
+/// Time between cursor samples while collecting.
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CollectionMode {
+    /// Not sampling, whatever the focus.
+    Paused,
+    /// Sampling only while the window has focus.
+    WhileFocused,
+    /// Sampling even in the background, until the requested bits are in.
+    Collecting,
+}
+
+struct CollectorState {
+    mode: CollectionMode,
+    focused: bool,
+    /// Mouse credit at which the thread stops; see `request_bits`.
+    target_bits: f64,
+    credited_bits: f64,
+    shutdown: bool,
+}
+
+impl CollectorState {
+    fn is_polling(&self) -> bool {
+        let wanted = match self.mode {
+            CollectionMode::Paused       => false,
+            CollectionMode::WhileFocused => self.focused,
+            CollectionMode::Collecting   => true,
+        };
+        wanted && self.credited_bits < self.target_bits && !self.shutdown
+    }
+}
+
+/// Handle to the cursor sampling thread. Dropping it shuts the thread down.
+pub struct MouseCollector {
+    shared: Arc<(Mutex<CollectorState>, Condvar)>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl MouseCollector {
+    /// Starts the thread in `WhileFocused` mode, without focus and with no bits requested, so
+    /// it sleeps until told otherwise. The cursor is read through the pool's entropy source.
+    pub fn spawn(pool: Arc<EntropyPool>) -> Self {
+        let credited_bits = mouse_credited_bits(&pool);
+        let shared = Arc::new((Mutex::new(CollectorState {
+            mode: CollectionMode::WhileFocused,
+            focused: false,
+            target_bits: credited_bits,
+            credited_bits,
+            shutdown: false,
+        }), Condvar::new()));
+
+        let thread_shared = shared.clone();
+        let thread = thread::spawn(move || collector_thread(&thread_shared, &pool));
+        Self { shared, thread: Some(thread) }
+    }
+
+    /// Starts an explicit collection phase: the cursor is sampled even while the window is in
+    /// the background, until the requested bits are in. Then it goes back to `WhileFocused`.
+    pub fn start(&self) {
+        self.update(|state| state.mode = CollectionMode::Collecting);
+    }
+
+    /// Stops sampling until `start` or `resume` is called.
+    pub fn pause(&self) {
+        self.update(|state| state.mode = CollectionMode::Paused);
+    }
+
+    /// Ends a pause or a collection phase and samples only while the window has focus.
+    pub fn resume(&self) {
+        self.update(|state| state.mode = CollectionMode::WhileFocused);
+    }
+
+    /// Tells the collector whether the window has focus.
+    pub fn set_focused(&self, focused: bool) {
+        self.update(|state| state.focused = focused);
+    }
+
+    /// Asks for `bits` more bits of credited mouse entropy from now on. Typically the gap
+    /// between the pool's gathered entropy and its gate threshold, refreshed as it changes.
+    pub fn request_bits(&self, bits: f64) {
+        self.update(|state| state.target_bits = state.credited_bits + bits.max(0.0));
+    }
+
+    pub fn mode(&self) -> CollectionMode {
+        self.lock_state().mode
+    }
+
+    /// Whether the cursor is being sampled right now.
+    pub fn is_polling(&self) -> bool {
+        self.lock_state().is_polling()
+    }
+
+    /// Stops the thread and waits for it to finish.
+    pub fn shutdown(mut self) {
+        self.stop_thread();
+    }
+
+    /// Only wakes the thread if the change means it should start or stop polling, so updating
+    /// the collector every frame costs nothing.
+    fn update(&self, change: impl FnOnce(&mut CollectorState)) {
+        let mut state = self.lock_state();
+        let was_polling = state.is_polling();
+        change(&mut state);
+        if state.is_polling() != was_polling || state.shutdown {
+            self.shared.1.notify_all();
+        }
+    }
+
+    fn lock_state(&self) -> MutexGuard<'_, CollectorState> {
+        self.shared.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn stop_thread(&mut self) {
+        self.update(|state| state.shutdown = true);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for MouseCollector {
+    fn drop(&mut self) {
+        self.stop_thread();
+    }
+}
+
+fn mouse_credited_bits(pool: &EntropyPool) -> f64 {
+    pool.mouse_position_entropy.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).health().credited_bits()
+}
+
+fn collector_thread(shared: &(Mutex<CollectorState>, Condvar), pool: &EntropyPool) {
+    let (state, condvar) = shared;
+    loop {
+        {
+            let mut state = state.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            while !state.is_polling() {
+                if state.shutdown {
+                    return;
+                }
+                state = condvar.wait(state).unwrap_or_else(|poisoned| poisoned.into_inner());
+            }
+        }
+
+        // Nothing to sample on platforms without a cursor, or once a replayed trace runs out.
+        if pool.collect_mouse_position_entropy(1) == 0 {
+            return;
+        }
+        let credited_bits = mouse_credited_bits(pool);
+
+        let mut state = state.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        state.credited_bits = credited_bits;
+        if state.mode == CollectionMode::Collecting && credited_bits >= state.target_bits {
+            state.mode = CollectionMode::WhileFocused;
+        }
+        // Sleeping on the condition variable lets a shutdown cut the interval short.
+        let _ = condvar.wait_timeout(state, POLL_INTERVAL);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entropy::deterministic::seeded_pool;
+    use crate::entropy::replay::MouseTraceReplaySource;
+
+    fn state(mode: CollectionMode, focused: bool, target_bits: f64) -> CollectorState {
+        CollectorState { mode, focused, target_bits, credited_bits: 10.0, shutdown: false }
+    }
+
+    #[test]
+    fn polls_only_when_wanted_and_short_of_the_target() {
+        assert!(!state(CollectionMode::Paused, true, 20.0).is_polling());
+        assert!(!state(CollectionMode::WhileFocused, false, 20.0).is_polling());
+        assert!(state(CollectionMode::WhileFocused, true, 20.0).is_polling());
+        assert!(state(CollectionMode::Collecting, false, 20.0).is_polling());
+        assert!(!state(CollectionMode::Collecting, false, 10.0).is_polling());
+
+        let mut shut_down = state(CollectionMode::Collecting, true, 20.0);
+        shut_down.shutdown = true;
+        assert!(!shut_down.is_polling());
+    }
+
+    #[test]
+    fn handle_tracks_mode_focus_and_requests() {
+        let collector = MouseCollector::spawn(Arc::new(seeded_pool(b"collector")));
+        assert_eq!(collector.mode(), CollectionMode::WhileFocused);
+        assert!(!collector.is_polling());
+
+        collector.set_focused(true);
+        assert!(!collector.is_polling(), "nothing was requested yet");
+        collector.request_bits(64.0);
+        assert!(collector.is_polling());
+
+        collector.pause();
+        assert_eq!(collector.mode(), CollectionMode::Paused);
+        assert!(!collector.is_polling());
+        collector.set_focused(false);
+        collector.start();
+        assert_eq!(collector.mode(), CollectionMode::Collecting);
+        collector.resume();
+        assert!(!collector.is_polling());
+
+        collector.request_bits(-5.0);
+        collector.start();
+        assert!(!collector.is_polling());
+        collector.shutdown();
+    }
+
+    #[test]
+    fn samples_come_from_the_pool_source() {
+        let trace: String = (0..40).map(|i| format!("{} {}\n", i * 7 % 31, i * 13 % 29)).collect();
+        let replayed_pool = |trace: &str| EntropyPool::new(Box::new(MouseTraceReplaySource::from_trace(trace).unwrap()));
+        let expected = replayed_pool(&trace);
+        assert_eq!(expected.collect_mouse_position_entropy(usize::MAX), 40);
+        let expected = expected.mouse_position_entropy.lock().unwrap().data.clone();
+
+        let pool = Arc::new(replayed_pool(&trace));
+        let collector = MouseCollector::spawn(pool.clone());
+        collector.request_bits(1e9);
+        collector.start();
+        let collected = || pool.mouse_position_entropy.lock().unwrap().data == expected;
+        let started = std::time::Instant::now();
+        while !collected() && started.elapsed() < Duration::from_secs(10) {
+            thread::sleep(POLL_INTERVAL);
+        }
+        assert!(collected(), "the trace was not replayed into the pool");
+        // The trace has run out, so the thread has stopped of its own accord.
+        collector.shutdown();
+    }
+}
\ No newline at end of file
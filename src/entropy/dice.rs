@@ -0,0 +1,280 @@
+//! Entropy typed in from physical dice or coins, for machines where neither the OS RNG nor
+//! the cursor should be trusted.
+//!
+//! Rolls of a fair die with `n` sides are combined into one uniformly distributed number in
+//! a mixed radix, and bits are only taken out of it when they are exactly uniform: if the
+//! number is below the largest power of two under its range, its low bits are emitted,
+//! otherwise the same is tried on the excess above that power of two, which is uniform over
+//! what is left of the range. Nothing is carried over to later rolls. Coin flips go through
+//! von Neumann's extractor (heads-tails is 1, tails-heads is 0, equal pairs are dropped),
+//! which removes any bias of the coin. Either way, every credited bit is a full bit.
+//!
+//! The rolls themselves go through the SP 800-90B health tests, assessed at the full entropy
+//! of the die, so a run like `6 6 6 6 6 6 6 6 6` is refused instead of credited.
+
+use crate::entropy::health::HealthTests;
+
+/// Bits are taken out of the mixed-radix accumulator once its range reaches this size, which
+/// wastes less of each roll than extracting after every roll.
+const EXTRACTION_RANGE: u128 = 1 << 16;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DiceEntropyMode {
+    /// Rolls are ignored and generation doesn't wait for them.
+    Disabled,
+    /// Rolls are mixed in with the other sources.
+    Mixed,
+    /// Streams are seeded from rolls alone: no OS randomness, no cursor and no clock.
+    Exclusive,
+}
+
+pub struct DiceEntropy {
+    pub mode: DiceEntropyMode,
+    /// Bits that must be entered before each generation. They are used up by it.
+    pub required_bits: usize,
+    bits: Vec<bool>,
+    sides: u32,
+    accumulator_value: u128,
+    accumulator_range: u128,
+    pending_coin_flip: Option<bool>,
+    rolls_entered: u64,
+    health: HealthTests,
+}
+
+impl Default for DiceEntropy {
+    fn default() -> Self {
+        Self {
+            mode: DiceEntropyMode::Disabled,
+            required_bits: 256,
+            bits: Vec::new(),
+            sides: 6,
+            accumulator_value: 0,
+            accumulator_range: 1,
+            pending_coin_flip: None,
+            rolls_entered: 0,
+            health: HealthTests::new(6f64.log2(), 0),
+        }
+    }
+}
+
+impl DiceEntropy {
+    pub fn available_bits(&self) -> usize {
+        self.bits.len()
+    }
+
+    pub fn rolls_entered(&self) -> u64 {
+        self.rolls_entered
+    }
+
+    pub fn has_required_bits(&self) -> bool {
+        self.bits.len() >= self.required_bits
+    }
+
+    pub fn health(&self) -> &HealthTests {
+        &self.health
+    }
+
+    /// The die that rolls were last entered for; 2 is a coin.
+    pub fn sides(&self) -> u32 {
+        self.sides
+    }
+
+    /// Switches to another die, dropping any partial roll state kept for the previous one.
+    pub fn set_sides(&mut self, sides: u32) {
+        if sides != self.sides {
+            self.sides = sides;
+            self.accumulator_value = 0;
+            self.accumulator_range = 1;
+            self.pending_coin_flip = None;
+            self.health = HealthTests::new((sides as f64).log2(), 0);
+        }
+    }
+
+    /// Adds a roll of a die with `sides` sides showing `value` (1-based). Use 2 sides for a
+    /// coin, with 1 for heads. Fails without crediting anything if the roll trips the health
+    /// tests.
+    pub fn add_roll(&mut self, sides: u32, value: u32) -> Result<(), String> {
+        if sides < 2 || value == 0 || value > sides {
+            return Err(format!("{} is not a valid roll of a {}-sided die", value, sides));
+        }
+        self.set_sides(sides);
+        if !self.health.test(value as u64) {
+            return Err(format!("Rolls refused by the {}; are the dice really being rolled?", self.health.last_failure().unwrap_or("health tests")));
+        }
+        self.rolls_entered += 1;
+
+        let bits_before = self.bits.len();
+        if sides == 2 {
+            let heads = value == 1;
+            match self.pending_coin_flip.take() {
+                Some(first) if first != heads => self.bits.push(first),
+                Some(_) => {}
+                None => self.pending_coin_flip = Some(heads),
+            }
+        } else {
+            self.accumulator_value = self.accumulator_value * sides as u128 + (value - 1) as u128;
+            self.accumulator_range *= sides as u128;
+            if self.accumulator_range >= EXTRACTION_RANGE {
+                self.extract_bits();
+            }
+        }
+        self.health.credit((self.bits.len() - bits_before) as f64);
+        Ok(())
+    }
+
+    /// Parses typed rolls and adds them. Coins accept `H`/`T` or `1`/`0`. Dice accept numbers
+    /// separated by spaces or commas, or a run of digits for dice with fewer than 10 sides;
+    /// a d10 may show 0 for 10. Nothing is added if any roll is invalid or the batch fails the
+    /// health tests. Returns the number of rolls added.
+    pub fn add_typed_rolls(&mut self, sides: u32, text: &str) -> Result<usize, String> {
+        let rolls = parse_rolls(sides, text)?;
+        self.set_sides(sides);
+        let mut health = self.health.clone();
+        if !rolls.iter().all(|&roll| health.test(roll as u64)) {
+            let message = format!("Rolls refused by the {}; are the dice really being rolled?", health.last_failure().unwrap_or("health tests"));
+            self.health = health;
+            return Err(message);
+        }
+        for &roll in &rolls {
+            self.add_roll(sides, roll)?;
+        }
+        Ok(rolls.len())
+    }
+
+    /// Removes and returns every collected bit, packed most significant bit first.
+    pub fn take_bits(&mut self) -> Vec<u8> {
+        let mut packed = vec![0u8; self.bits.len().div_ceil(8)];
+        for (i, &bit) in self.bits.iter().enumerate() {
+            packed[i / 8] |= (bit as u8) << (7 - i % 8);
+        }
+        self.bits.fill(false);
+        self.bits.clear();
+        packed
+    }
+
+    fn extract_bits(&mut self) {
+        while self.accumulator_range > 1 {
+            let bit_count = 127 - self.accumulator_range.leading_zeros();
+            let power_of_two = 1u128 << bit_count;
+            if self.accumulator_value < power_of_two {
+                for i in (0..bit_count).rev() {
+                    self.bits.push((self.accumulator_value >> i) & 1 == 1);
+                }
+                self.accumulator_value = 0;
+                self.accumulator_range = 1;
+            } else {
+                self.accumulator_value -= power_of_two;
+                self.accumulator_range -= power_of_two;
+            }
+        }
+    }
+}
+
+fn parse_rolls(sides: u32, text: &str) -> Result<Vec<u32>, String> {
+    if sides == 2 {
+        return text.chars().filter(|c| !c.is_whitespace() && *c != ',').map(|c| match c.to_ascii_lowercase() {
+            'h' | '1' => Ok(1),
+            't' | '0' => Ok(2),
+            _ => Err(format!("{:?} is not a coin flip; use H/T or 1/0", c)),
+        }).collect();
+    }
+
+    let separated = text.contains(|c: char| c.is_whitespace() || c == ',');
+    let fields: Vec<String> = if sides < 10 && !separated {
+        text.chars().map(|c| c.to_string()).collect()
+    } else {
+        text.split(|c: char| c.is_whitespace() || c == ',').filter(|field| !field.is_empty()).map(|field| field.to_string()).collect()
+    };
+
+    fields.iter().map(|field| {
+        let roll = field.parse::<u32>().map_err(|_| format!("{:?} is not a number", field))?;
+        let roll = if sides == 10 && roll == 0 { 10 } else { roll };
+        if roll == 0 || roll > sides {
+            return Err(format!("{} is not a valid roll of a d{}", roll, sides));
+        }
+        Ok(roll)
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bits_of(dice: &mut DiceEntropy) -> String {
+        let count = dice.available_bits();
+        let packed = dice.take_bits();
+        (0..count).map(|i| if (packed[i / 8] >> (7 - i % 8)) & 1 == 1 { '1' } else { '0' }).collect()
+    }
+
+    #[test]
+    fn von_neumann_drops_equal_pairs() {
+        let mut dice = DiceEntropy::default();
+        assert_eq!(dice.add_typed_rolls(2, "HT TH HH TT HT 10 01 11").unwrap(), 16);
+        assert_eq!(bits_of(&mut dice), "10110");
+        assert_eq!(dice.rolls_entered(), 16);
+        assert_eq!(dice.available_bits(), 0);
+
+        // A lone flip waits for its partner.
+        dice.add_roll(2, 1).unwrap();
+        assert_eq!(dice.available_bits(), 0);
+        dice.add_roll(2, 2).unwrap();
+        assert_eq!(bits_of(&mut dice), "1");
+    }
+
+    #[test]
+    fn mixed_radix_emits_low_bits_below_the_power_of_two() {
+        let mut dice = DiceEntropy::default();
+        // 6^7 = 279936 is the first range past 2^16; the value 1 is below 2^18.
+        dice.add_typed_rolls(6, "111111").unwrap();
+        assert_eq!(dice.available_bits(), 0);
+        dice.add_typed_rolls(6, "2").unwrap();
+        assert_eq!(bits_of(&mut dice), "000000000000000001");
+    }
+
+    #[test]
+    fn mixed_radix_retries_on_the_excess() {
+        let mut dice = DiceEntropy::default();
+        // 279935 is past 2^18, and so is every excess after it down to 127 of 128.
+        dice.add_typed_rolls(6, "6666666").unwrap();
+        assert_eq!(bits_of(&mut dice), "1111111");
+        assert_eq!(dice.accumulator_range, 1);
+        assert_eq!(dice.accumulator_value, 0);
+    }
+
+    #[test]
+    fn typed_rolls_accept_every_notation() {
+        let mut dice = DiceEntropy::default();
+        assert_eq!(dice.add_typed_rolls(6, "123456").unwrap(), 6);
+        assert_eq!(dice.add_typed_rolls(6, "1, 2 3").unwrap(), 3);
+        assert_eq!(dice.add_typed_rolls(10, "0 10 5").unwrap(), 3);
+        assert_eq!(dice.sides(), 10);
+        assert_eq!(dice.add_typed_rolls(20, "20 1 13").unwrap(), 3);
+        for (sides, text) in [(6, "7"), (6, "0"), (6, "x"), (2, "HX"), (20, "21")] {
+            assert!(dice.add_typed_rolls(sides, text).is_err(), "accepted {:?}", text);
+        }
+        assert!(dice.add_roll(1, 1).is_err());
+        assert!(dice.add_roll(6, 0).is_err());
+    }
+
+    #[test]
+    fn stuck_dice_are_refused_without_credit() {
+        let mut dice = DiceEntropy::default();
+        let error = dice.add_typed_rolls(6, "6666666666666666").unwrap_err();
+        assert!(error.contains("refused"), "{}", error);
+        assert_eq!(dice.rolls_entered(), 0);
+        assert_eq!(dice.available_bits(), 0);
+
+        let mut coin = DiceEntropy::default();
+        assert!(coin.add_typed_rolls(2, &"H".repeat(64)).is_err());
+        assert_eq!(coin.rolls_entered(), 0);
+    }
+
+    #[test]
+    fn required_bits_are_used_up() {
+        let mut dice = DiceEntropy { required_bits: 4, ..DiceEntropy::default() };
+        dice.add_typed_rolls(2, "HTHTHTTH").unwrap();
+        assert!(dice.has_required_bits());
+        assert_eq!(dice.take_bits(), [0b1110_0000]);
+        assert!(!dice.has_required_bits());
+    }
+}
\ No newline at end of file
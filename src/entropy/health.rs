@@ -0,0 +1,359 @@
+//! Continuous health tests from NIST SP 800-90B, section 4.4.
+//!
+//! Every noise source runs its raw samples through a `HealthTests` before they are credited.
+//! The repetition count test catches a source that gets stuck on one value, and the adaptive
+//! proportion test catches one value taking over a 512-sample window. Both cutoffs follow
+//! from the entropy per sample claimed for the source, with a false alarm rate of 2^-20.
+//!
+//! A source starts out in `Startup` and is only credited once it has passed its startup
+//! samples. A failure marks it `Degraded`: its samples are still mixed into the pool, which
+//! can never hurt, but nothing is credited until it passes a fresh set of startup samples.
+//! Alongside the tests, a most-common-value estimate (section 6.3.1) keeps track of how much
+//! min-entropy the samples actually seem to carry, and a running total of the bits the
+//! source has been credited with.
+
+use std::fmt;
+
+/// False alarm probability per sample for both tests.
+const ALPHA_EXPONENT: f64 = 20.0;
+/// Window of the adaptive proportion test for non-binary sources.
+const APT_WINDOW: u32 = 512;
+/// Samples the most-common-value estimate waits for before it says anything.
+const MIN_ESTIMATE_SAMPLES: u64 = 256;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EntropySourceKind {
+    Os,
+    Mouse,
+    Jitter,
+    System,
+    Dice,
+    User,
+}
+
+impl EntropySourceKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            EntropySourceKind::Os     => "OS",
+            EntropySourceKind::Mouse  => "Mouse",
+            EntropySourceKind::Jitter => "Jitter",
+            EntropySourceKind::System => "System",
+            EntropySourceKind::Dice   => "Dice",
+            EntropySourceKind::User   => "User",
+        }
+    }
+
+    /// Whether the source's entropy is gathered ahead of time, and so counts towards the
+    /// entropy gate. The OS RNG and CPU jitter are drawn on demand for every stream instead.
+    pub fn is_gathered(&self) -> bool {
+        matches!(self, EntropySourceKind::Mouse | EntropySourceKind::System | EntropySourceKind::Dice | EntropySourceKind::User)
+    }
+
+    /// Whether the source feeds streams in FIPS-approved mode. The cursor buffer, which user
+    /// input also goes into, is conditioned with Whirlpool and so is left out.
+    pub fn is_used_in_fips_mode(&self) -> bool {
+        !matches!(self, EntropySourceKind::Mouse | EntropySourceKind::User)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum HealthStatus {
+    /// Still working through its startup samples; nothing is credited yet.
+    Startup,
+    Healthy,
+    /// Failed a test. Mixed in but not credited until it passes its startup samples again.
+    Degraded,
+}
+
+impl HealthStatus {
+    pub fn name(&self) -> &'static str {
+        match self {
+            HealthStatus::Startup  => "starting",
+            HealthStatus::Healthy  => "healthy",
+            HealthStatus::Degraded => "degraded",
+        }
+    }
+}
+
+/// What `EntropyPool::health_report` says about one source.
+#[derive(Clone, Debug)]
+pub struct SourceHealth {
+    pub kind: EntropySourceKind,
+    pub status: HealthStatus,
+    /// Min-entropy per sample the source was designed to deliver.
+    pub assessed_entropy: f64,
+    /// Min-entropy per sample measured so far, once enough samples have been seen.
+    pub min_entropy_estimate: Option<f64>,
+    pub samples: u64,
+    /// Bits credited to the pool since startup.
+    pub credited_bits: f64,
+    pub failure_count: u64,
+    pub last_failure: Option<String>,
+}
+
+impl fmt::Display for SourceHealth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}, {} samples, {:.2} bits/sample assessed", self.kind.name(), self.status.name(), self.samples, self.assessed_entropy)?;
+        if let Some(estimate) = self.min_entropy_estimate {
+            write!(f, ", {:.2} estimated", estimate)?;
+        }
+        write!(f, ", {:.0} bits credited", self.credited_bits)?;
+        if let Some(failure) = &self.last_failure {
+            write!(f, ", {} failure(s), last: {}", self.failure_count, failure)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+pub struct HealthTests {
+    assessed_entropy: f64,
+    startup_samples: u64,
+    status: HealthStatus,
+    samples_since_reset: u64,
+    rct_cutoff: u32,
+    rct_last_sample: Option<u64>,
+    rct_count: u32,
+    apt_cutoff: u32,
+    apt_reference: u64,
+    apt_count: u32,
+    apt_position: u32,
+    histogram: Vec<u64>,
+    samples: u64,
+    credited_bits: f64,
+    failure_count: u64,
+    last_failure: Option<String>,
+}
+
+impl HealthTests {
+    /// `assessed_entropy` is the min-entropy in bits claimed per sample; `startup_samples` is
+    /// how many samples must pass before the source is credited, after startup or a failure.
+    pub fn new(assessed_entropy: f64, startup_samples: u64) -> Self {
+        Self {
+            assessed_entropy,
+            startup_samples,
+            status: if startup_samples == 0 { HealthStatus::Healthy } else { HealthStatus::Startup },
+            samples_since_reset: 0,
+            rct_cutoff: 1 + (ALPHA_EXPONENT / assessed_entropy).ceil() as u32,
+            rct_last_sample: None,
+            rct_count: 0,
+            apt_cutoff: adaptive_proportion_cutoff(APT_WINDOW, 2f64.powf(-assessed_entropy)),
+            apt_reference: 0,
+            apt_count: 0,
+            apt_position: 0,
+            histogram: vec![0; 256],
+            samples: 0,
+            credited_bits: 0.0,
+            failure_count: 0,
+            last_failure: None,
+        }
+    }
+
+    /// Runs both tests on a raw sample. Returns `true` if the sample may be credited.
+    pub fn test(&mut self, sample: u64) -> bool {
+        self.samples += 1;
+        self.histogram[sample.to_le_bytes().iter().fold(0, |folded, byte| folded ^ byte) as usize] += 1;
+
+        if self.rct_last_sample == Some(sample) {
+            self.rct_count += 1;
+            if self.rct_count >= self.rct_cutoff {
+                self.report_failure(format!("repetition count test: the same sample {} times in a row", self.rct_count));
+                return false;
+            }
+        } else {
+            self.rct_last_sample = Some(sample);
+            self.rct_count = 1;
+        }
+
+        if self.apt_position == 0 {
+            self.apt_reference = sample;
+            self.apt_count = 1;
+        } else if sample == self.apt_reference {
+            self.apt_count += 1;
+            if self.apt_count >= self.apt_cutoff {
+                self.report_failure(format!("adaptive proportion test: one sample {} times in {}", self.apt_count, APT_WINDOW));
+                return false;
+            }
+        }
+        self.apt_position = (self.apt_position + 1) % APT_WINDOW;
+
+        self.samples_since_reset += 1;
+        if self.status != HealthStatus::Healthy && self.samples_since_reset >= self.startup_samples {
+            self.status = HealthStatus::Healthy;
+        }
+        self.status == HealthStatus::Healthy
+    }
+
+    /// Adds to the bits the source has been credited with. Sources decide how much a passing
+    /// sample is worth; `credited_entropy` is the usual answer.
+    pub fn credit(&mut self, bits: f64) {
+        self.credited_bits += bits;
+    }
+
+    pub fn credited_bits(&self) -> f64 {
+        self.credited_bits
+    }
+
+    /// Marks the source degraded for a reason found outside these tests, and restarts them.
+    pub fn report_failure(&mut self, reason: String) {
+        self.failure_count += 1;
+        self.last_failure = Some(reason);
+        self.status = HealthStatus::Degraded;
+        self.samples_since_reset = 0;
+        self.rct_last_sample = None;
+        self.rct_count = 0;
+        self.apt_position = 0;
+    }
+
+    pub fn status(&self) -> HealthStatus {
+        self.status
+    }
+
+    pub fn is_healthy(&self) -> bool {
+        self.status == HealthStatus::Healthy
+    }
+
+    pub fn assessed_entropy(&self) -> f64 {
+        self.assessed_entropy
+    }
+
+    pub fn last_failure(&self) -> Option<&str> {
+        self.last_failure.as_deref()
+    }
+
+    /// Most-common-value estimate over the samples folded to 8 bits, at 99% confidence.
+    /// `None` until enough samples have been seen.
+    pub fn min_entropy_estimate(&self) -> Option<f64> {
+        if self.samples < MIN_ESTIMATE_SAMPLES {
+            return None;
+        }
+        let samples = self.samples as f64;
+        let most_common = *self.histogram.iter().max().unwrap_or(&0) as f64 / samples;
+        let upper_bound = (most_common + 2.576 * (most_common * (1.0 - most_common) / (samples - 1.0)).sqrt()).min(1.0);
+        Some(-upper_bound.log2())
+    }
+
+    /// Bits to credit per sample: the smaller of the assessed and estimated min-entropy, or
+    /// nothing while the source isn't healthy.
+    pub fn credited_entropy(&self) -> f64 {
+        if !self.is_healthy() {
+            return 0.0;
+        }
+        match self.min_entropy_estimate() {
+            Some(estimate) => estimate.min(self.assessed_entropy),
+            None => self.assessed_entropy,
+        }
+    }
+
+    pub fn report(&self, kind: EntropySourceKind) -> SourceHealth {
+        SourceHealth {
+            kind,
+            status: self.status,
+            assessed_entropy: self.assessed_entropy,
+            min_entropy_estimate: self.min_entropy_estimate(),
+            samples: self.samples,
+            credited_bits: self.credited_bits,
+            failure_count: self.failure_count,
+            last_failure: self.last_failure.clone(),
+        }
+    }
+}
+
+/// `1 + CRITBINOM(window, probability, 1 - alpha)`: the smallest count of the reference value
+/// in a window that is less likely than alpha for a source with the assessed entropy.
+fn adaptive_proportion_cutoff(window: u32, probability: f64) -> u32 {
+    if probability >= 1.0 {
+        return window;
+    }
+    let target = 1.0 - 2f64.powf(-ALPHA_EXPONENT);
+    let n = window as f64;
+    let mut log_pmf = n * (1.0 - probability).ln();
+    let mut cdf = 0.0;
+    for k in 0..window {
+        cdf += log_pmf.exp();
+        if cdf >= target {
+            return k + 1;
+        }
+        let k = k as f64;
+        log_pmf += ((n - k) / (k + 1.0)).ln() + (probability / (1.0 - probability)).ln();
+    }
+    window
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cutoffs_match_sp_800_90b() {
+        // Table 2 of SP 800-90B for a window of 512.
+        for (entropy, cutoff) in [(0.5, 410), (1.0, 311), (2.0, 177), (4.0, 62), (8.0, 13)] {
+            assert_eq!(HealthTests::new(entropy, 0).apt_cutoff, cutoff, "H = {}", entropy);
+        }
+        assert_eq!(HealthTests::new(1.0, 0).rct_cutoff, 21);
+        assert_eq!(HealthTests::new(8.0, 0).rct_cutoff, 4);
+    }
+
+    #[test]
+    fn repetition_count_test_catches_a_stuck_source() {
+        let mut health = HealthTests::new(1.0, 0);
+        for _ in 0..20 {
+            assert!(health.test(7));
+        }
+        assert!(!health.test(7));
+        assert_eq!(health.status(), HealthStatus::Degraded);
+        assert!(health.last_failure().unwrap().starts_with("repetition count test"));
+    }
+
+    #[test]
+    fn adaptive_proportion_test_catches_a_dominant_value() {
+        let mut health = HealthTests::new(4.0, 0);
+        let mut failed_at = None;
+        for i in 0..APT_WINDOW {
+            // Never the same twice in a row, so only the proportion test can trip.
+            let sample = if i % 2 == 0 { 0 } else { i as u64 };
+            if !health.test(sample) {
+                failed_at = Some(i);
+                break;
+            }
+        }
+        assert_eq!(failed_at, Some(2 * 61));
+        assert!(health.last_failure().unwrap().starts_with("adaptive proportion test"));
+    }
+
+    #[test]
+    fn sources_recover_after_fresh_startup_samples() {
+        let mut health = HealthTests::new(8.0, 4);
+        assert_eq!(health.status(), HealthStatus::Startup);
+        for sample in 0..3 {
+            assert!(!health.test(sample));
+        }
+        assert!(health.test(3));
+        assert_eq!(health.credited_entropy(), 8.0);
+
+        health.report_failure("test".to_string());
+        assert_eq!(health.credited_entropy(), 0.0);
+        for sample in 10..13 {
+            assert!(!health.test(sample));
+        }
+        assert!(health.test(13));
+        assert!(health.is_healthy());
+        assert_eq!(health.report(EntropySourceKind::Dice).failure_count, 1);
+    }
+
+    #[test]
+    fn estimate_limits_the_credit() {
+        let mut health = HealthTests::new(8.0, 0);
+        assert_eq!(health.min_entropy_estimate(), None);
+        for sample in 0..MIN_ESTIMATE_SAMPLES {
+            health.test(sample % 4);
+        }
+        let estimate = health.min_entropy_estimate().unwrap();
+        assert!(estimate > 1.5 && estimate < 2.0, "{}", estimate);
+        assert_eq!(health.credited_entropy(), estimate);
+
+        health.credit(3.0);
+        health.credit(1.5);
+        assert_eq!(health.credited_bits(), 4.5);
+    }
+}
\ No newline at end of file
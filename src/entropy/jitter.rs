@@ -0,0 +1,204 @@
+//! Entropy from CPU execution-time jitter, after Stephan Müller's jitterentropy.
+//!
+//! Each sample times one pass of a memory-access loop and a hash loop with the finest clock
+//! available. Caches, TLBs, pipelines and interrupts make that time vary in ways nobody
+//! outside the CPU can predict, so the delta between consecutive timings is the noise
+//! source. Deltas are conditioned with SHA3-256 before anything leaves this module.
+//!
+//! As in jitterentropy, a sample is "stuck" if its first, second or third discrete derivative
+//! is zero. Stuck samples are still hashed in but never credited, and a long run of them
+//! degrades the source, as does failing the SP 800-90B tests on the raw deltas. Each credited
+//! sample counts as a third of a bit, well below what jitterentropy assumes on common hardware.
+
+use crate::crypto;
+use crate::entropy::health::HealthTests;
+
+/// Size of the buffer walked by the memory-access loop; larger than L1 on most CPUs.
+const MEMORY_SIZE: usize = 64 * 1024;
+/// Bytes touched per sample. The stride is co-prime with the size so every byte gets visited.
+const MEMORY_ACCESS_LOOPS: usize = 128;
+const MEMORY_STRIDE: usize = 63;
+/// Samples needed to credit one bit of entropy.
+const OVERSAMPLING_RATE: usize = 3;
+/// Samples taken by the startup test before the source is trusted, and again after a failure.
+const STARTUP_SAMPLES: usize = 1024;
+/// Consecutive stuck samples that fail the source, as in jitterentropy's repetition count test.
+const REPETITION_CUTOFF: usize = 30 * OVERSAMPLING_RATE;
+/// Gives up on a request that needed more than this many times the expected samples.
+const MAX_SAMPLE_FACTOR: usize = 10;
+
+pub struct JitterEntropy {
+    memory: Vec<u8>,
+    memory_location: usize,
+    last_time: u64,
+    last_delta: u64,
+    last_delta2: u64,
+    stuck_run: usize,
+    state: Vec<u8>,
+    health: HealthTests,
+}
+
+impl Default for JitterEntropy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl JitterEntropy {
+    /// Runs the startup test. If the timer on this machine is not fit to be used as a noise
+    /// source, the collector comes back degraded and its health tests say why.
+    pub fn new() -> Self {
+        let mut jitter_entropy = Self {
+            memory: vec![0; MEMORY_SIZE],
+            memory_location: 0,
+            last_time: 0,
+            last_delta: 0,
+            last_delta2: 0,
+            stuck_run: 0,
+            state: b"P-GEN JITTER ENTROPY".to_vec(),
+            health: HealthTests::new(1.0 / OVERSAMPLING_RATE as f64, STARTUP_SAMPLES as u64),
+        };
+
+        jitter_entropy.last_time = jitter_entropy.read_clock();
+        let mut stuck_samples = 0;
+        let mut backwards_samples = 0;
+        for _ in 0..STARTUP_SAMPLES {
+            let before = jitter_entropy.last_time;
+            let (delta, stuck) = jitter_entropy.measure();
+            jitter_entropy.health.test(delta);
+            stuck_samples += stuck as usize;
+            backwards_samples += (jitter_entropy.last_time < before) as usize;
+        }
+
+        if backwards_samples > 3 {
+            jitter_entropy.health.report_failure(format!("the timer went backwards {} times during the startup test", backwards_samples));
+        } else if stuck_samples * 10 > STARTUP_SAMPLES * 9 {
+            jitter_entropy.health.report_failure(format!("{} of {} startup samples showed no jitter; the timer is too coarse", stuck_samples, STARTUP_SAMPLES));
+        }
+        jitter_entropy
+    }
+
+    pub fn health(&self) -> &HealthTests {
+        &self.health
+    }
+
+    /// Collects samples until `bits` bits have been credited and returns them conditioned into
+    /// a 32-byte SHA3-256 digest. A degraded source gets the chance to recover along the way;
+    /// if too few samples can be credited, this gives up.
+    pub fn generate(&mut self, bits: usize) -> Result<Vec<u8>, String> {
+        let required_samples = bits * OVERSAMPLING_RATE;
+        let mut credited_samples = 0;
+        let mut samples = 0;
+        let mut deltas = Vec::with_capacity(required_samples * 8);
+        while credited_samples < required_samples {
+            if samples >= required_samples * MAX_SAMPLE_FACTOR {
+                let failure = format!("only {} of {} samples could be credited", credited_samples, samples);
+                if self.health.is_healthy() {
+                    self.health.report_failure(failure.clone());
+                }
+                return Err(failure);
+            }
+
+            let (delta, stuck) = self.measure();
+            let healthy = self.health.test(delta);
+            deltas.extend_from_slice(&delta.to_le_bytes());
+            credited_samples += (healthy && !stuck) as usize;
+            samples += 1;
+        }
+
+        let mut input = self.state.clone();
+        input.append(&mut deltas);
+        self.state = crypto::hashes::sha3_256_compute(&input);
+
+        input.push(1);
+        let output = crypto::hashes::sha3_256_compute(&input);
+        input.fill(0);
+        self.health.credit(bits as f64);
+        Ok(output)
+    }
+
+    /// Times one pass of the noise loops. Returns the delta and whether the sample is stuck.
+    fn measure(&mut self) -> (u64, bool) {
+        self.memory_access_loop();
+        self.hash_loop();
+
+        let time = self.read_clock();
+        let delta = time.wrapping_sub(self.last_time);
+        let delta2 = delta.wrapping_sub(self.last_delta);
+        let delta3 = delta2.wrapping_sub(self.last_delta2);
+        self.last_time = time;
+        self.last_delta = delta;
+        self.last_delta2 = delta2;
+
+        let stuck = delta == 0 || delta2 == 0 || delta3 == 0;
+        if stuck {
+            self.stuck_run += 1;
+            if self.stuck_run == REPETITION_CUTOFF {
+                self.health.report_failure(format!("{} consecutive samples showed no jitter", self.stuck_run));
+            }
+        } else {
+            self.stuck_run = 0;
+        }
+        (delta, stuck)
+    }
+
+    fn memory_access_loop(&mut self) {
+        for _ in 0..MEMORY_ACCESS_LOOPS {
+            let location = self.memory_location;
+            self.memory[location] = std::hint::black_box(self.memory[location].wrapping_add(1));
+            self.memory_location = (location + MEMORY_STRIDE) % MEMORY_SIZE;
+        }
+    }
+
+    /// Hashes the state a varying number of times so the loop length itself is unpredictable.
+    fn hash_loop(&mut self) {
+        let rounds = 1 + (self.last_time & 3);
+        let mut digest = self.state.clone();
+        for _ in 0..rounds {
+            digest = crypto::hashes::sha3_256_compute(&digest);
+        }
+        std::hint::black_box(digest);
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    fn read_clock(&self) -> u64 {
+        // RDTSC is available on every x86-64 CPU.
+        unsafe { std::arch::x86_64::_rdtsc() }
+    }
+
+    #[cfg(not(target_arch = "x86_64"))]
+    fn read_clock(&self) -> u64 {
+        super::get_current_time_ns() as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generates_conditioned_output_and_credits_it() {
+        let mut jitter_entropy = JitterEntropy::new();
+        if !jitter_entropy.health().is_healthy() {
+            // A timer too coarse for jitter must say so rather than be credited.
+            assert!(jitter_entropy.health().last_failure().is_some());
+            return;
+        }
+
+        let first = jitter_entropy.generate(64).unwrap();
+        let second = jitter_entropy.generate(64).unwrap();
+        assert_eq!(first.len(), 32);
+        assert_ne!(first, second);
+        assert_eq!(jitter_entropy.health().credited_bits(), 128.0);
+    }
+
+    #[test]
+    fn memory_loop_visits_every_byte() {
+        let mut jitter_entropy = JitterEntropy::new();
+        jitter_entropy.memory.fill(0);
+        for _ in 0..MEMORY_SIZE / MEMORY_ACCESS_LOOPS {
+            jitter_entropy.memory_access_loop();
+        }
+        assert!(jitter_entropy.memory.iter().all(|&byte| byte == 1));
+    }
+}
\ No newline at end of file
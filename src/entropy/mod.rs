@@ -0,0 +1,208 @@
+use rand::{RngCore, rngs::OsRng};
+use std::{collections::VecDeque, time::{Instant, SystemTime}};
+
+use crate::crypto;
+use crate::entropy::health::HealthTests;
+
+pub mod collector;
+pub mod deterministic;
+pub mod dice;
+pub mod health;
+pub mod jitter;
+pub mod replay;
+pub mod system;
+pub mod user;
+
+/// Everything the generator needs from the outside world: random bytes, the current
+/// time and cursor samples. Swapping the implementation makes the output reproducible.
+pub trait EntropySource: Send {
+    fn fill_bytes(&mut self, data: &mut [u8]);
+    fn get_current_time_ns(&mut self) -> u128;
+
+    /// Returns the next cursor sample, or `None` if this source has no cursor to offer.
+    fn get_cursor_position(&mut self) -> Option<(u16, u16)>;
+
+    fn generate_bytes(&mut self, length: u32) -> Vec<u8> {
+        let mut data = vec![0; length as usize];
+        self.fill_bytes(&mut data);
+        data
+    }
+}
+
+/// The default source backed by `OsRng`, the system clock and the real cursor.
+pub struct OsEntropySource;
+
+impl EntropySource for OsEntropySource {
+    fn fill_bytes(&mut self, data: &mut [u8]) {
+        OsRng.fill_bytes(data);
+    }
+
+    fn get_current_time_ns(&mut self) -> u128 {
+        get_current_time_ns()
+    }
+
+    fn get_cursor_position(&mut self) -> Option<(u16, u16)> {
+        get_cursor_position()
+    }
+}
+
+pub fn generate_bytes(length: u32) -> Vec<u8> {
+    OsEntropySource.generate_bytes(length)
+}
+
+pub fn get_current_time_ns() -> u128 {
+    let duration_since_epoch = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap();
+    duration_since_epoch.as_nanos()
+}
+
+#[cfg(windows)]
+pub fn get_cursor_position() -> Option<(u16, u16)> {
+    use std::ptr::addr_of_mut;
+    use windows_sys::Win32::{Foundation::POINT, UI::WindowsAndMessaging::GetCursorPos};
+
+    let mut point = POINT { x: 0, y: 0 };
+    unsafe {
+        GetCursorPos(addr_of_mut!(point));
+    }
+    Some(((point.x % 0x10000) as u16, (point.y % 0x10000) as u16))
+}
+
+#[cfg(not(windows))]
+pub fn get_cursor_position() -> Option<(u16, u16)> {
+    None
+}
+
+/// Min-entropy claimed per cursor movement, in bits.
+const MOUSE_ASSESSED_ENTROPY: f64 = 1.0;
+/// Movements that must pass the health tests before the cursor is credited.
+const MOUSE_STARTUP_SAMPLES: u64 = 1024;
+/// Positions kept for drawing the cursor trail.
+pub const MOUSE_TRAIL_LENGTH: usize = 256;
+
+/// Ring buffer of recent cursor positions, prefixed with a running Whirlpool digest of
+/// everything that has been written into it so far. The movement between samples is what
+/// the health tests look at, so a cursor stuck on a remote desktop shows up there.
+pub struct MousePositionEntropy {
+    pub data: Vec<u8>,
+    index: usize,
+    last_position: (u16, u16),
+    last_movement: Option<Instant>,
+    trail: VecDeque<(u16, u16)>,
+    health: HealthTests,
+}
+
+impl Default for MousePositionEntropy {
+    fn default() -> Self {
+        let mut data = Vec::new();
+        data.append(&mut crypto::hashes::whirlpool_512_compute(b"P-GEN DEFAULT MOUSE POSITION ENTROPY"));
+        data.append(&mut vec![0; 192]);
+
+        Self {
+            data,
+            index: 0,
+            last_position: (0, 0),
+            last_movement: None,
+            trail: VecDeque::with_capacity(MOUSE_TRAIL_LENGTH),
+            health: HealthTests::new(MOUSE_ASSESSED_ENTROPY, MOUSE_STARTUP_SAMPLES),
+        }
+    }
+}
+
+impl MousePositionEntropy {
+    pub fn health(&self) -> &HealthTests {
+        &self.health
+    }
+
+    /// The most recent positions that were mixed in, oldest first.
+    pub fn trail(&self) -> &VecDeque<(u16, u16)> {
+        &self.trail
+    }
+
+    /// When the cursor last moved, or `None` if it never has.
+    pub fn last_movement(&self) -> Option<Instant> {
+        self.last_movement
+    }
+
+    /// Folds uncredited material, such as a saved seed, into the digest at the head of the
+    /// buffer.
+    pub fn mix_in(&mut self, material: &[u8]) {
+        let mut digest_input = self.data.clone();
+        digest_input.extend_from_slice(material);
+        let entropy_hash = crypto::hashes::whirlpool_512_compute(&digest_input);
+        digest_input.fill(0);
+        self.data[0..64].clone_from_slice(&entropy_hash);
+    }
+
+    /// Mixes a cursor sample into the buffer. Returns `false` if the cursor has not moved
+    /// since the previous sample, in which case nothing is written.
+    pub fn feed(&mut self, position: (u16, u16)) -> bool {
+        if position == self.last_position {
+            return false;
+        }
+        let movement_x = position.0.wrapping_sub(self.last_position.0) as u64;
+        let movement_y = position.1.wrapping_sub(self.last_position.1) as u64;
+        if self.health.test(movement_x << 16 | movement_y) {
+            let credited_entropy = self.health.credited_entropy();
+            self.health.credit(credited_entropy);
+        }
+        self.last_position = position;
+        self.last_movement = Some(Instant::now());
+        if self.trail.len() == MOUSE_TRAIL_LENGTH {
+            self.trail.pop_front();
+        }
+        self.trail.push_back(position);
+        self.index = (self.index + 4) % 192;
+
+        let i = self.index;
+        self.data[i + 64] = (position.0 >> 8) as u8;
+        self.data[i + 65] = (position.0 & 0xff) as u8;
+        self.data[i + 66] = (position.1 >> 8) as u8;
+        self.data[i + 67] = (position.1 & 0xff) as u8;
+
+        if i == 188 {
+            let entropy_hash = crypto::hashes::whirlpool_512_compute(&self.data);
+            self.data[0..64].clone_from_slice(&entropy_hash);
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stationary_cursor_is_not_mixed_in() {
+        let mut mouse_position_entropy = MousePositionEntropy::default();
+        let before = mouse_position_entropy.data.clone();
+        assert!(!mouse_position_entropy.feed((0, 0)));
+        assert_eq!(mouse_position_entropy.data, before);
+        assert_eq!(mouse_position_entropy.last_movement(), None);
+
+        assert!(mouse_position_entropy.feed((300, 2)));
+        assert_ne!(mouse_position_entropy.data, before);
+        assert!(mouse_position_entropy.last_movement().is_some());
+        assert!(!mouse_position_entropy.feed((300, 2)));
+    }
+
+    #[test]
+    fn trail_keeps_the_latest_positions() {
+        let mut mouse_position_entropy = MousePositionEntropy::default();
+        for step in 1..=MOUSE_TRAIL_LENGTH as u16 + 10 {
+            mouse_position_entropy.feed((step, step.wrapping_mul(7)));
+        }
+        let trail = mouse_position_entropy.trail();
+        assert_eq!(trail.len(), MOUSE_TRAIL_LENGTH);
+        assert_eq!(trail.front(), Some(&(11, 77)));
+        assert_eq!(trail.back().unwrap().0, MOUSE_TRAIL_LENGTH as u16 + 10);
+    }
+
+    #[test]
+    fn mixing_in_changes_only_the_digest() {
+        let mut mouse_position_entropy = MousePositionEntropy::default();
+        let before = mouse_position_entropy.data.clone();
+        mouse_position_entropy.mix_in(b"seed");
+        assert_ne!(mouse_position_entropy.data[..64], before[..64]);
+        assert_eq!(mouse_position_entropy.data[64..], before[64..]);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,118 @@
+use std::{fs, io, path::Path};
+
+use crate::crypto;
+use crate::entropy::{deterministic::DeterministicEntropySource, EntropySource};
+
+/// Replays a recorded cursor trace. Each non-empty line of the trace is either `x y` or
+/// `timestamp_ns x y`; lines starting with `#` are ignored. Random bytes come from a
+/// deterministic source seeded with the trace itself, so the same file always produces
+/// the same output.
+pub struct MouseTraceReplaySource {
+    samples: Vec<(Option<u128>, (u16, u16))>,
+    next_sample: usize,
+    fallback: DeterministicEntropySource,
+    last_time_ns: Option<u128>,
+}
+
+impl MouseTraceReplaySource {
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let trace = fs::read_to_string(path)?;
+        Self::from_trace(&trace)
+    }
+
+    pub fn from_trace(trace: &str) -> io::Result<Self> {
+        let mut samples = Vec::new();
+        for (line_number, line) in trace.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let fields: Vec<&str> = line.split(|c: char| c.is_whitespace() || c == ',').filter(|f| !f.is_empty()).collect();
+            let invalid_line = || io::Error::new(io::ErrorKind::InvalidData, format!("invalid cursor sample on line {}: {:?}", line_number + 1, line));
+            let sample = match fields.as_slice() {
+                [x, y] => (None, (x.parse().map_err(|_| invalid_line())?, y.parse().map_err(|_| invalid_line())?)),
+                [t, x, y] => (
+                    Some(t.parse().map_err(|_| invalid_line())?),
+                    (x.parse().map_err(|_| invalid_line())?, y.parse().map_err(|_| invalid_line())?),
+                ),
+                _ => return Err(invalid_line()),
+            };
+            samples.push(sample);
+        }
+
+        let mut seed = b"P-GEN MOUSE TRACE REPLAY".to_vec();
+        seed.append(&mut crypto::hashes::sha3_256_compute(trace.as_bytes()));
+
+        Ok(Self {
+            samples,
+            next_sample: 0,
+            fallback: DeterministicEntropySource::new(&seed),
+            last_time_ns: None,
+        })
+    }
+
+    pub fn remaining_samples(&self) -> usize {
+        self.samples.len() - self.next_sample
+    }
+}
+
+impl EntropySource for MouseTraceReplaySource {
+    fn fill_bytes(&mut self, data: &mut [u8]) {
+        self.fallback.fill_bytes(data);
+    }
+
+    fn get_current_time_ns(&mut self) -> u128 {
+        match self.last_time_ns {
+            Some(time_ns) => time_ns,
+            None => self.fallback.get_current_time_ns(),
+        }
+    }
+
+    fn get_cursor_position(&mut self) -> Option<(u16, u16)> {
+        let (time_ns, position) = *self.samples.get(self.next_sample)?;
+        self.next_sample += 1;
+        if time_ns.is_some() {
+            self.last_time_ns = time_ns;
+        }
+        Some(position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_both_sample_forms() {
+        let mut source = MouseTraceReplaySource::from_trace("# recorded trace\n\n10 20\n1000 30,40\n  50\t60  \n").unwrap();
+        assert_eq!(source.remaining_samples(), 3);
+        assert_eq!(source.get_cursor_position(), Some((10, 20)));
+        assert_eq!(source.get_cursor_position(), Some((30, 40)));
+        assert_eq!(source.get_current_time_ns(), 1000);
+        assert_eq!(source.get_cursor_position(), Some((50, 60)));
+        assert_eq!(source.get_current_time_ns(), 1000);
+        assert_eq!(source.remaining_samples(), 0);
+        assert_eq!(source.get_cursor_position(), None);
+    }
+
+    #[test]
+    fn rejects_malformed_lines() {
+        for trace in ["10", "1 2 3 4", "x 20", "10 -1", "10 65536", "-5 10 20", "10 20\nbad"] {
+            let error = MouseTraceReplaySource::from_trace(trace).err().unwrap_or_else(|| panic!("accepted {:?}", trace));
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        }
+        let error = MouseTraceReplaySource::from_trace("1 2\n\n3 4 5 6").err().unwrap();
+        assert!(error.to_string().contains("line 3"), "{}", error);
+    }
+
+    #[test]
+    fn same_trace_gives_same_bytes() {
+        let mut first = MouseTraceReplaySource::from_trace("1 2\n3 4").unwrap();
+        let mut second = MouseTraceReplaySource::from_trace("1 2\n3 4").unwrap();
+        let mut different = MouseTraceReplaySource::from_trace("1 2\n3 5").unwrap();
+        assert_eq!(first.generate_bytes(64), second.generate_bytes(64));
+        assert_ne!(first.generate_bytes(64), different.generate_bytes(64));
+        assert!(MouseTraceReplaySource::from_trace("").unwrap().get_cursor_position().is_none());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,175 @@
+//! Entropy from fast-changing Linux kernel state, for hosts with no cursor to sample.
+//!
+//! Each round reads the interrupt, disk, CPU, softirq and network counters from `/proc` and
+//! timestamps every read. Counters that changed since the previous round are the noise
+//! source: how many interrupts, context switches, sectors and packets happened in between
+//! depends on everything else the machine is doing. Deltas and timestamps are conditioned
+//! with SHA3-256 before anything leaves this module.
+//!
+//! Much of that activity can be observed or influenced by other users of the same host, so
+//! the credit is low: half a bit for each round in which something changed, and only while
+//! the sum of the deltas passes the SP 800-90B tests. An idle machine whose only moving
+//! counter is the timer tick fails the repetition count test and is not credited at all.
+
+use std::fs;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::crypto;
+use crate::entropy::health::HealthTests;
+
+/// Files read every round, in this order. Missing ones are skipped.
+const SOURCES: [&str; 5] = ["/proc/interrupts", "/proc/softirqs", "/proc/stat", "/proc/diskstats", "/proc/net/dev"];
+/// Min-entropy claimed per round in which at least one counter changed, in bits.
+const SYSTEM_ASSESSED_ENTROPY: f64 = 0.5;
+/// Changed rounds that must pass the health tests before the source is credited.
+const STARTUP_SAMPLES: u64 = 256;
+/// Pause between rounds while harvesting, so the counters have a chance to move.
+const HARVEST_INTERVAL: Duration = Duration::from_millis(1);
+
+pub struct SystemEntropy {
+    counters: Vec<u64>,
+    epoch: Instant,
+    state: Vec<u8>,
+    health: HealthTests,
+}
+
+impl SystemEntropy {
+    /// Takes the first snapshot. Returns `None` if none of the files can be read, as on
+    /// anything but Linux.
+    pub fn new() -> Option<Self> {
+        let mut system_entropy = Self {
+            counters: Vec::new(),
+            epoch: Instant::now(),
+            state: b"P-GEN SYSTEM ENTROPY".to_vec(),
+            health: HealthTests::new(SYSTEM_ASSESSED_ENTROPY, STARTUP_SAMPLES),
+        };
+        let (material, _) = system_entropy.read_round();
+        if system_entropy.counters.is_empty() {
+            return None;
+        }
+        system_entropy.absorb(material);
+        Some(system_entropy)
+    }
+
+    pub fn health(&self) -> &HealthTests {
+        &self.health
+    }
+
+    /// Reads rounds until `bits` bits have been credited or `timeout` has passed, and returns
+    /// the bits credited.
+    pub fn harvest(&mut self, bits: f64, timeout: Duration) -> f64 {
+        let started = Instant::now();
+        let mut credited_bits = 0.0;
+        while credited_bits < bits && started.elapsed() < timeout {
+            thread::sleep(HARVEST_INTERVAL);
+            let (material, delta_sum) = self.read_round();
+            self.absorb(material);
+            if let Some(delta_sum) = delta_sum {
+                if self.health.test(delta_sum) {
+                    let credited_entropy = self.health.credited_entropy();
+                    self.health.credit(credited_entropy);
+                    credited_bits += credited_entropy;
+                }
+            }
+        }
+        credited_bits
+    }
+
+    /// Reads one more round and returns everything harvested so far as a 32-byte digest. The
+    /// round is mixed in but not credited.
+    pub fn generate(&mut self) -> Vec<u8> {
+        let (material, _) = self.read_round();
+        self.absorb(material);
+
+        let mut input = self.state.clone();
+        input.push(1);
+        let output = crypto::hashes::sha3_256_compute(&input);
+        input.fill(0);
+        output
+    }
+
+    fn absorb(&mut self, mut material: Vec<u8>) {
+        let mut input = std::mem::take(&mut self.state);
+        input.append(&mut material);
+        self.state = crypto::hashes::sha3_256_compute(&input);
+        input.fill(0);
+    }
+
+    /// Reads every file once. Returns the material to absorb, and the wrapping sum of the
+    /// counter deltas if any counter changed since the previous round.
+    fn read_round(&mut self) -> (Vec<u8>, Option<u64>) {
+        let mut material = Vec::new();
+        let mut counters = Vec::with_capacity(self.counters.len());
+        for path in SOURCES {
+            material.extend_from_slice(&(self.epoch.elapsed().as_nanos() as u64).to_le_bytes());
+            if let Ok(contents) = fs::read(path) {
+                parse_counters(&contents, &mut counters);
+            }
+        }
+        material.extend_from_slice(&(self.epoch.elapsed().as_nanos() as u64).to_le_bytes());
+
+        let mut delta_sum = None;
+        if counters.len() != self.counters.len() {
+            // A device or interface came or went; everything moved.
+            delta_sum = Some(counters.len() as u64);
+        }
+        for (index, (&counter, &previous)) in counters.iter().zip(&self.counters).enumerate() {
+            if counter != previous {
+                let delta = counter.wrapping_sub(previous);
+                material.extend_from_slice(&(index as u32).to_le_bytes());
+                material.extend_from_slice(&delta.to_le_bytes());
+                delta_sum = Some(delta_sum.unwrap_or(0).wrapping_add(delta));
+            }
+        }
+        self.counters = counters;
+        (material, delta_sum)
+    }
+}
+
+/// Appends every run of decimal digits in `contents` that fits in a `u64`.
+fn parse_counters(contents: &[u8], counters: &mut Vec<u64>) {
+    for token in contents.split(|byte| !byte.is_ascii_digit()).filter(|token| !token.is_empty()) {
+        let counter = token.iter().try_fold(0u64, |counter, digit| counter.checked_mul(10)?.checked_add((digit - b'0') as u64));
+        counters.push(counter.unwrap_or(u64::MAX));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_every_run_of_digits() {
+        let mut counters = Vec::new();
+        parse_counters(b"cpu0 12 345\n  eth0: 6,7 99999999999999999999999 x", &mut counters);
+        assert_eq!(counters, [0, 12, 345, 0, 6, 7, u64::MAX]);
+    }
+
+    #[test]
+    fn deltas_are_summed_over_changed_counters() {
+        let Some(mut system_entropy) = SystemEntropy::new() else { return };
+        assert!(!system_entropy.counters.is_empty());
+
+        // A rewound counter and a vanished one both count as changes.
+        system_entropy.counters[0] = system_entropy.counters[0].wrapping_sub(3);
+        system_entropy.counters.push(0);
+        let (material, delta_sum) = system_entropy.read_round();
+        assert!(delta_sum.is_some());
+        assert!(material.len() > (SOURCES.len() + 1) * 8);
+    }
+
+    #[test]
+    fn output_changes_every_call() {
+        let Some(mut system_entropy) = SystemEntropy::new() else { return };
+        let first = system_entropy.generate();
+        let second = system_entropy.generate();
+        assert_eq!(first.len(), 32);
+        assert_ne!(first, second);
+
+        let started = Instant::now();
+        let credited_bits = system_entropy.harvest(f64::INFINITY, Duration::from_millis(20));
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert_eq!(credited_bits, system_entropy.health().credited_bits());
+    }
+}
\ No newline at end of file
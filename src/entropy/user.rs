@@ -0,0 +1,177 @@
+//! Entropy contributed by the user: dropped files, pasted text and keyboard mashing.
+//!
+//! Content is hashed with BLAKE3, and only its digest and length enter the pool. The credit
+//! is kept well below what the content seems to hold. A photo or a recording may be sitting
+//! in a cloud backup, and pasted text may be on a web page, so content is credited with an
+//! eighth of its most-common-value estimate and never more than `CONTENT_CREDIT_CAP` bits;
+//! the same content twice is only credited once. Keystrokes are credited through their
+//! timing alone, at one bit per key press, with intervals in milliseconds going through the
+//! SP 800-90B health tests so that key repeat or a macro can't pass for a person typing.
+
+use std::collections::HashSet;
+use std::time::Instant;
+
+use crate::crypto;
+use crate::entropy::health::HealthTests;
+
+/// Most bits one file or text can be credited with, however large it is.
+pub const CONTENT_CREDIT_CAP: f64 = 64.0;
+/// Content is credited with its estimated min-entropy divided by this.
+const CONTENT_CREDIT_DIVISOR: f64 = 8.0;
+/// Min-entropy claimed per keystroke interval, in bits.
+const KEYSTROKE_ASSESSED_ENTROPY: f64 = 1.0;
+
+pub struct UserEntropy {
+    /// Refuse to generate unless something new was contributed since the last generation.
+    pub required: bool,
+    contributions: u64,
+    pending_contributions: u64,
+    seen_contents: HashSet<[u8; 32]>,
+    last_keystroke: Option<Instant>,
+    health: HealthTests,
+}
+
+impl Default for UserEntropy {
+    fn default() -> Self {
+        Self {
+            required: false,
+            contributions: 0,
+            pending_contributions: 0,
+            seen_contents: HashSet::new(),
+            last_keystroke: None,
+            health: HealthTests::new(KEYSTROKE_ASSESSED_ENTROPY, 0),
+        }
+    }
+}
+
+impl UserEntropy {
+    pub fn health(&self) -> &HealthTests {
+        &self.health
+    }
+
+    /// Whether anything has been contributed, or contributions are required.
+    pub fn is_used(&self) -> bool {
+        self.required || self.contributions > 0
+    }
+
+    /// Contributions since the last generation.
+    pub fn pending_contributions(&self) -> u64 {
+        self.pending_contributions
+    }
+
+    /// Credits a file or text and returns the material to mix into the pool, along with the
+    /// bits credited.
+    pub fn add_content(&mut self, content: &[u8]) -> (Vec<u8>, f64) {
+        let digest: [u8; 32] = crypto::hashes::blake3_256_compute(content).try_into().unwrap();
+        let credited_bits = if self.seen_contents.insert(digest) {
+            (content_min_entropy(content) / CONTENT_CREDIT_DIVISOR).min(CONTENT_CREDIT_CAP)
+        } else {
+            0.0
+        };
+        self.health.credit(credited_bits);
+        self.count_contribution();
+
+        let mut material = b"P-GEN USER CONTENT".to_vec();
+        material.extend_from_slice(&(content.len() as u64).to_be_bytes());
+        material.extend_from_slice(&digest);
+        (material, credited_bits)
+    }
+
+    /// Credits a key press by its timing and returns the material to mix into the pool, along
+    /// with the bits credited. `key` is whatever identifies the key; it is mixed in but
+    /// not credited.
+    pub fn add_keystroke(&mut self, key: &str, time_ns: u128) -> (Vec<u8>, f64) {
+        let now = Instant::now();
+        let mut credited_bits = 0.0;
+        if let Some(last_keystroke) = self.last_keystroke {
+            let interval = now.duration_since(last_keystroke);
+            if self.health.test(interval.as_millis() as u64) {
+                credited_bits = self.health.credited_entropy();
+                self.health.credit(credited_bits);
+            }
+        }
+        self.last_keystroke = Some(now);
+        self.count_contribution();
+
+        let mut material = b"P-GEN USER KEYSTROKE".to_vec();
+        material.extend_from_slice(&time_ns.to_be_bytes());
+        material.extend_from_slice(key.as_bytes());
+        (material, credited_bits)
+    }
+
+    /// Used up by each generation when contributions are required.
+    pub(crate) fn take_pending_contributions(&mut self) -> u64 {
+        std::mem::take(&mut self.pending_contributions)
+    }
+
+    fn count_contribution(&mut self) {
+        self.contributions += 1;
+        self.pending_contributions += 1;
+    }
+}
+
+/// Most-common-value min-entropy of the bytes of `content`, in bits, at 99% confidence.
+fn content_min_entropy(content: &[u8]) -> f64 {
+    if content.len() < 2 {
+        return 0.0;
+    }
+    let mut histogram = [0u64; 256];
+    for &byte in content {
+        histogram[byte as usize] += 1;
+    }
+    let samples = content.len() as f64;
+    let most_common = *histogram.iter().max().unwrap_or(&0) as f64 / samples;
+    let upper_bound = (most_common + 2.576 * (most_common * (1.0 - most_common) / (samples - 1.0)).sqrt()).min(1.0);
+    -upper_bound.log2() * samples
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entropy::deterministic::seeded_pool;
+    use crate::generator;
+    use crate::pool::GenerationError;
+
+    #[test]
+    fn content_is_credited_once_and_capped() {
+        let mut user_entropy = UserEntropy::default();
+        let text = b"Pack my box with five dozen liquor jugs.";
+        let (material, credited_bits) = user_entropy.add_content(text);
+        assert!(credited_bits > 0.0 && credited_bits < text.len() as f64);
+        assert!(material.starts_with(b"P-GEN USER CONTENT"));
+        assert_eq!(user_entropy.add_content(text).1, 0.0);
+
+        let large: Vec<u8> = (0..100_000u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 24) as u8).collect();
+        assert_eq!(user_entropy.add_content(&large).1, CONTENT_CREDIT_CAP);
+        assert_eq!(user_entropy.health().credited_bits(), credited_bits + CONTENT_CREDIT_CAP);
+    }
+
+    #[test]
+    fn repetitive_content_is_worth_little() {
+        assert_eq!(content_min_entropy(b"a"), 0.0);
+        assert_eq!(content_min_entropy(&[0; 1000]), 0.0);
+        let varied: Vec<u8> = (0..=255).collect();
+        assert!(content_min_entropy(&varied) > content_min_entropy(&[b'a', b'b'].repeat(128)));
+    }
+
+    #[test]
+    fn first_keystroke_has_no_interval_to_credit() {
+        let mut user_entropy = UserEntropy::default();
+        let (material, credited_bits) = user_entropy.add_keystroke("a", 42);
+        assert_eq!(credited_bits, 0.0);
+        assert!(material.ends_with(b"a"));
+        assert_eq!(user_entropy.pending_contributions(), 1);
+        assert!(user_entropy.is_used());
+    }
+
+    #[test]
+    fn required_input_is_used_up_by_each_generation() {
+        let pool = seeded_pool(b"user");
+        pool.user_entropy().required = true;
+        assert_eq!(generator::generate_stream(&pool, 16), Err(GenerationError::MissingUserEntropy));
+
+        pool.add_user_content(b"something only I would type");
+        generator::generate_stream(&pool, 16).unwrap();
+        assert_eq!(generator::generate_stream(&pool, 16), Err(GenerationError::MissingUserEntropy));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,355 @@
+//! BIP39 mnemonics: 128 to 256 bits of entropy written as 12 to 24 words, the last of which
+//! carries a SHA-256 checksum, and the PBKDF2-HMAC-SHA512 seed wallets derive keys from.
+//!
+//! All ten official wordlists are included, unchanged from the BIP, and `power_on` self-tests
+//! check them against their published SHA-256 digests. Phrases are NFKD-normalised before
+//! they are parsed or turned into seeds, as the BIP requires, so accented and full-width
+//! input works however it was typed.
+
+use std::fmt;
+use std::sync::OnceLock;
+
+use unicode_normalization::UnicodeNormalization;
+
+use crate::crypto;
+use crate::generator::stream::StreamGenerator;
+use crate::pool::{EntropyPool, GenerationError};
+use crate::self_test;
+
+/// The word counts BIP39 allows, for 128, 160, 192, 224 and 256 bits of entropy.
+pub const WORD_COUNTS: [usize; 5] = [12, 15, 18, 21, 24];
+const SEED_ITERATIONS: u32 = 2048;
+pub const SEED_SIZE: usize = 64;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Bip39Language {
+    English,
+    ChineseSimplified,
+    ChineseTraditional,
+    Czech,
+    French,
+    Italian,
+    Japanese,
+    Korean,
+    Portuguese,
+    Spanish,
+}
+
+impl Bip39Language {
+    pub const ALL: [Bip39Language; 10] = [
+        Bip39Language::English,
+        Bip39Language::ChineseSimplified,
+        Bip39Language::ChineseTraditional,
+        Bip39Language::Czech,
+        Bip39Language::French,
+        Bip39Language::Italian,
+        Bip39Language::Japanese,
+        Bip39Language::Korean,
+        Bip39Language::Portuguese,
+        Bip39Language::Spanish,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Bip39Language::English            => "english",
+            Bip39Language::ChineseSimplified  => "chinese-simplified",
+            Bip39Language::ChineseTraditional => "chinese-traditional",
+            Bip39Language::Czech              => "czech",
+            Bip39Language::French             => "french",
+            Bip39Language::Italian            => "italian",
+            Bip39Language::Japanese           => "japanese",
+            Bip39Language::Korean             => "korean",
+            Bip39Language::Portuguese         => "portuguese",
+            Bip39Language::Spanish            => "spanish",
+        }
+    }
+
+    /// The wordlist file as published with the BIP.
+    pub(crate) fn wordlist_text(&self) -> &'static str {
+        match self {
+            Bip39Language::English            => include_str!("bip39/english.txt"),
+            Bip39Language::ChineseSimplified  => include_str!("bip39/chinese_simplified.txt"),
+            Bip39Language::ChineseTraditional => include_str!("bip39/chinese_traditional.txt"),
+            Bip39Language::Czech              => include_str!("bip39/czech.txt"),
+            Bip39Language::French             => include_str!("bip39/french.txt"),
+            Bip39Language::Italian            => include_str!("bip39/italian.txt"),
+            Bip39Language::Japanese           => include_str!("bip39/japanese.txt"),
+            Bip39Language::Korean             => include_str!("bip39/korean.txt"),
+            Bip39Language::Portuguese         => include_str!("bip39/portuguese.txt"),
+            Bip39Language::Spanish            => include_str!("bip39/spanish.txt"),
+        }
+    }
+
+    /// The 2048 words, in index order. The published lists are already in NFKD.
+    pub fn words(&self) -> &'static [&'static str] {
+        static WORDLISTS: OnceLock<Vec<Vec<&'static str>>> = OnceLock::new();
+        let wordlists = WORDLISTS.get_or_init(|| Bip39Language::ALL.iter().map(|language| language.wordlist_text().lines().collect()).collect());
+        &wordlists[Bip39Language::ALL.iter().position(|language| language == self).unwrap()]
+    }
+
+    /// Japanese phrases are written with ideographic spaces, everything else with spaces.
+    pub fn separator(&self) -> &'static str {
+        match self {
+            Bip39Language::Japanese => "\u{3000}",
+            _                       => " ",
+        }
+    }
+
+    fn index_of(&self, word: &str) -> Option<usize> {
+        self.words().iter().position(|candidate| *candidate == word)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MnemonicError {
+    /// BIP39 phrases have 12, 15, 18, 21 or 24 words.
+    InvalidWordCount { count: usize },
+    /// Entropy must be 16 to 32 bytes, in steps of 4.
+    InvalidEntropySize { size: usize },
+    UnknownWord { word: String },
+    /// No wordlist holds every word of the phrase.
+    UnknownLanguage,
+    /// The words are valid but the last one doesn't match the checksum: a word is wrong or
+    /// the order is.
+    InvalidChecksum,
+    Generation(GenerationError),
+}
+
+impl fmt::Display for MnemonicError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MnemonicError::InvalidWordCount { count }  => write!(f, "A BIP39 phrase has 12, 15, 18, 21 or 24 words, not {}", count),
+            MnemonicError::InvalidEntropySize { size } => write!(f, "BIP39 entropy must be 16 to 32 bytes in steps of 4, not {}", size),
+            MnemonicError::UnknownWord { word }        => write!(f, "Not in the wordlist: {}", word),
+            MnemonicError::UnknownLanguage             => write!(f, "No BIP39 wordlist holds every word of the phrase"),
+            MnemonicError::InvalidChecksum             => write!(f, "The checksum doesn't match; a word is wrong or out of order"),
+            MnemonicError::Generation(error)           => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for MnemonicError {}
+
+impl From<GenerationError> for MnemonicError {
+    fn from(error: GenerationError) -> Self {
+        MnemonicError::Generation(error)
+    }
+}
+
+/// A mnemonic with a valid checksum. The entropy is wiped when it is dropped.
+pub struct Mnemonic {
+    language: Bip39Language,
+    entropy: Vec<u8>,
+}
+
+impl Mnemonic {
+    /// Draws `word_count / 3 * 32` bits of entropy from a fresh stream.
+    pub fn generate(pool: &EntropyPool, word_count: usize, language: Bip39Language) -> Result<Self, MnemonicError> {
+        if !WORD_COUNTS.contains(&word_count) {
+            return Err(MnemonicError::InvalidWordCount { count: word_count });
+        }
+        let mut entropy = vec![0u8; word_count / 3 * 4];
+        StreamGenerator::new(pool, entropy.len() as u64)?.fill_bytes(&mut entropy);
+        Ok(Self { language, entropy })
+    }
+
+    pub fn from_entropy(entropy: &[u8], language: Bip39Language) -> Result<Self, MnemonicError> {
+        if !(16..=32).contains(&entropy.len()) || !entropy.len().is_multiple_of(4) {
+            return Err(MnemonicError::InvalidEntropySize { size: entropy.len() });
+        }
+        Ok(Self { language, entropy: entropy.to_vec() })
+    }
+
+    /// Checks a phrase and recovers its entropy. Without a language, every wordlist that holds
+    /// all the words is tried, since the Chinese lists share many of them, and the first one
+    /// the checksum matches in is used.
+    pub fn parse(phrase: &str, language: Option<Bip39Language>) -> Result<Self, MnemonicError> {
+        let phrase: String = phrase.nfkd().collect();
+        let words: Vec<&str> = phrase.split_whitespace().collect();
+        if !WORD_COUNTS.contains(&words.len()) {
+            return Err(MnemonicError::InvalidWordCount { count: words.len() });
+        }
+
+        let Some(language) = language else {
+            let mut candidates = Bip39Language::ALL.into_iter().filter(|language| words.iter().all(|word| language.index_of(word).is_some()));
+            let first_candidate = candidates.next().ok_or(MnemonicError::UnknownLanguage)?;
+            return Self::parse(&phrase, Some(first_candidate))
+                .or_else(|error| candidates.find_map(|language| Self::parse(&phrase, Some(language)).ok()).ok_or(error));
+        };
+        let mut indices = Vec::with_capacity(words.len());
+        for word in &words {
+            indices.push(language.index_of(word).ok_or_else(|| MnemonicError::UnknownWord { word: word.to_string() })?);
+        }
+
+        // Every word is 11 bits; the last `words / 3` bits are the checksum.
+        let mut bits = Vec::with_capacity(words.len() * 11);
+        for index in indices {
+            bits.extend((0..11).rev().map(|bit| (index >> bit) & 1 == 1));
+        }
+        let (entropy_bits, checksum_bits) = bits.split_at(words.len() / 3 * 32);
+        let entropy: Vec<u8> = entropy_bits.chunks(8).map(|byte| byte.iter().fold(0u8, |value, &bit| value << 1 | bit as u8)).collect();
+
+        let mnemonic = Self { language, entropy };
+        if mnemonic.checksum_bits() != checksum_bits {
+            return Err(MnemonicError::InvalidChecksum);
+        }
+        Ok(mnemonic)
+    }
+
+    pub fn language(&self) -> Bip39Language {
+        self.language
+    }
+
+    pub fn entropy(&self) -> &[u8] {
+        &self.entropy
+    }
+
+    pub fn words(&self) -> Vec<&'static str> {
+        let wordlist = self.language.words();
+        let mut bits: Vec<bool> = self.entropy.iter().flat_map(|byte| (0..8).rev().map(move |bit| (byte >> bit) & 1 == 1)).collect();
+        bits.extend(self.checksum_bits());
+        bits.chunks(11).map(|word| wordlist[word.iter().fold(0usize, |index, &bit| index << 1 | bit as usize)]).collect()
+    }
+
+    /// The words joined with the language's separator.
+    pub fn phrase(&self) -> String {
+        self.words().join(self.language.separator())
+    }
+
+    /// The BIP39 seed: PBKDF2-HMAC-SHA512 over the NFKD phrase, salted with "mnemonic" and the
+    /// NFKD passphrase, 2048 iterations. Every passphrase gives a valid, different seed.
+    pub fn to_seed(&self, passphrase: &str) -> Result<[u8; SEED_SIZE], MnemonicError> {
+        self_test::check()?;
+        Ok(self.derive_seed(passphrase))
+    }
+
+    /// `to_seed` without the self-test check, for the self-tests themselves.
+    pub(crate) fn derive_seed(&self, passphrase: &str) -> [u8; SEED_SIZE] {
+        let mut phrase: Vec<u8> = self.phrase().nfkd().collect::<String>().into_bytes();
+        let mut salt: Vec<u8> = format!("mnemonic{}", passphrase.nfkd().collect::<String>()).into_bytes();
+        let mut derived = crypto::kdf::pbkdf2_hmac_sha512(&phrase, &salt, SEED_ITERATIONS, SEED_SIZE).expect("the iteration count is not zero");
+        let mut seed = [0u8; SEED_SIZE];
+        seed.copy_from_slice(&derived);
+        phrase.fill(0);
+        salt.fill(0);
+        derived.fill(0);
+        seed
+    }
+
+    /// The first `entropy bits / 32` bits of the entropy's SHA-256 digest.
+    fn checksum_bits(&self) -> Vec<bool> {
+        let digest = crypto::hashes::sha256_compute(&self.entropy);
+        (0..self.entropy.len() * 8 / 32).map(|bit| (digest[bit / 8] >> (7 - bit % 8)) & 1 == 1).collect()
+    }
+}
+
+impl Drop for Mnemonic {
+    fn drop(&mut self) {
+        self.entropy.fill(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::{hex_decode, hex_encode};
+    use crate::entropy::deterministic::seeded_pool;
+
+    /// Trezor's reference vectors, with the passphrase "TREZOR".
+    const TREZOR_VECTORS: [(&str, &str, &str); 7] = [
+        ("00000000000000000000000000000000", "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about", "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04"),
+        ("7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f", "legal winner thank year wave sausage worth useful legal winner thank yellow", "2e8905819b8723fe2c1d161860e5ee1830318dbf49a83bd451cfb8440c28bd6fa457fe1296106559a3c80937a1c1069be3a3a5bd381ee6260e8d9739fce1f607"),
+        ("80808080808080808080808080808080", "letter advice cage absurd amount doctor acoustic avoid letter advice cage above", "d71de856f81a8acc65e6fc851a38d4d7ec216fd0796d0a6827a3ad6ed5511a30fa280f12eb2e47ed2ac03b5c462a0358d18d69fe4f985ec81778c1b370b652a8"),
+        ("ffffffffffffffffffffffffffffffff", "zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo wrong", "ac27495480225222079d7be181583751e86f571027b0497b5b5d11218e0a8a13332572917f0f8e5a589620c6f15b11c61dee327651a14c34e18231052e48c069"),
+        ("9e885d952ad362caeb4efe34a8e91bd2", "ozone drill grab fiber curtain grace pudding thank cruise elder eight picnic", "274ddc525802f7c828d8ef7ddbcdc5304e87ac3535913611fbbfa986d0c9e5476c91689f9c8a54fd55bd38606aa6a8595ad213d4c9c9f9aca3fb217069a41028"),
+        ("0000000000000000000000000000000000000000000000000000000000000000", "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon art", "bda85446c68413707090a52022edd26a1c9462295029f2e60cd7c4f2bbd3097170af7a4d73245cafa9c3cca8d561a7c3de6f5d4a10be8ed2a5e608d68f92fcc8"),
+        ("68a79eaca2324873eacc50cb9c6eca8cc68ea5d936f98787c60c7ebc74e6ce7c", "hamster diagram private dutch cause delay private meat slide toddler razor book happy fancy gospel tennis maple dilemma loan word shrug inflict delay length", "64c87cde7e12ecf6704ab95bb1408bef047c22db4cc7491c4271d170a1b213d20b385bc1588d9c7b38f1b39d415665b8a9030c9ec653d75e65f847d8fc1fc440"),
+    ];
+
+    #[test]
+    fn trezor_vectors_round_trip() {
+        for (entropy, phrase, seed) in TREZOR_VECTORS {
+            let mnemonic = Mnemonic::from_entropy(&hex_decode(entropy).unwrap(), Bip39Language::English).unwrap();
+            assert_eq!(mnemonic.phrase(), phrase);
+            assert_eq!(hex_encode(&mnemonic.to_seed("TREZOR").unwrap(), false), seed);
+
+            let parsed = Mnemonic::parse(phrase, Some(Bip39Language::English)).unwrap();
+            assert_eq!(hex_encode(parsed.entropy(), false), entropy);
+        }
+    }
+
+    #[test]
+    fn a_wrong_last_word_fails_the_checksum() {
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon";
+        assert_eq!(Mnemonic::parse(phrase, Some(Bip39Language::English)).err(), Some(MnemonicError::InvalidChecksum));
+        let swapped = "winner legal thank year wave sausage worth useful legal winner thank yellow";
+        assert_eq!(Mnemonic::parse(swapped, None).err(), Some(MnemonicError::InvalidChecksum));
+    }
+
+    #[test]
+    fn word_counts_are_checked() {
+        let eleven = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        assert_eq!(Mnemonic::parse(eleven, None).err(), Some(MnemonicError::InvalidWordCount { count: 11 }));
+        assert_eq!(Mnemonic::parse("", None).err(), Some(MnemonicError::InvalidWordCount { count: 0 }));
+
+        let pool = seeded_pool(b"bip39 tests");
+        assert_eq!(Mnemonic::generate(&pool, 13, Bip39Language::English).err(), Some(MnemonicError::InvalidWordCount { count: 13 }));
+    }
+
+    #[test]
+    fn entropy_sizes_are_checked() {
+        for size in [0, 12, 15, 17, 36] {
+            assert_eq!(Mnemonic::from_entropy(&vec![0; size], Bip39Language::English).err(), Some(MnemonicError::InvalidEntropySize { size }));
+        }
+    }
+
+    #[test]
+    fn unknown_words_are_reported() {
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon aboot";
+        assert_eq!(Mnemonic::parse(phrase, Some(Bip39Language::English)).err(), Some(MnemonicError::UnknownWord { word: "aboot".to_string() }));
+        assert_eq!(Mnemonic::parse(phrase, None).err(), Some(MnemonicError::UnknownLanguage));
+    }
+
+    #[test]
+    fn the_language_is_detected() {
+        let french = "abaisser abaisser abaisser abaisser abaisser abaisser abaisser abaisser abaisser abaisser abaisser abeille";
+        assert_eq!(Mnemonic::parse(french, None).unwrap().language(), Bip39Language::French);
+
+        let japanese = Mnemonic::from_entropy(&[0; 16], Bip39Language::Japanese).unwrap().phrase();
+        assert!(japanese.contains('\u{3000}'));
+        let parsed = Mnemonic::parse(&japanese, None).unwrap();
+        assert_eq!(parsed.language(), Bip39Language::Japanese);
+        assert_eq!(parsed.entropy(), [0; 16]);
+
+        // The Chinese lists share characters at the same positions, so a phrase of shared ones
+        // reads the same in both and goes to the first; one they don't share settles it.
+        let shared = Mnemonic::from_entropy(&[0; 16], Bip39Language::ChineseTraditional).unwrap().phrase();
+        assert_eq!(Mnemonic::parse(&shared, None).unwrap().language(), Bip39Language::ChineseSimplified);
+        let mut entropy = [0u8; 16];
+        entropy[..2].copy_from_slice(&[0x01, 0x20]);
+        let traditional = Mnemonic::from_entropy(&entropy, Bip39Language::ChineseTraditional).unwrap().phrase();
+        assert_eq!(Mnemonic::parse(&traditional, None).unwrap().language(), Bip39Language::ChineseTraditional);
+    }
+
+    #[test]
+    fn every_language_round_trips() {
+        let pool = seeded_pool(b"bip39 tests");
+        for language in Bip39Language::ALL {
+            assert_eq!(language.words().len(), 2048, "{}", language.name());
+            for word_count in WORD_COUNTS {
+                let mnemonic = Mnemonic::generate(&pool, word_count, language).unwrap();
+                assert_eq!(mnemonic.words().len(), word_count);
+                let parsed = Mnemonic::parse(&mnemonic.phrase(), Some(language)).unwrap();
+                assert_eq!(parsed.entropy(), mnemonic.entropy());
+            }
+        }
+    }
+
+    #[test]
+    fn passphrases_change_the_seed() {
+        let mnemonic = Mnemonic::from_entropy(&[0; 16], Bip39Language::English).unwrap();
+        assert_ne!(mnemonic.to_seed("").unwrap(), mnemonic.to_seed("TREZOR").unwrap());
+        // The passphrase is normalized, so composed and decomposed forms agree.
+        assert_eq!(mnemonic.to_seed("caf\u{e9}").unwrap(), mnemonic.to_seed("cafe\u{301}").unwrap());
+    }
+}
\ No newline at end of file
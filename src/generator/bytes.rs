@@ -0,0 +1,155 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::generator::stream::StreamGenerator;
+use crate::pool::EntropyPool;
+
+/// Size of the buffer random bytes are generated into before being written out.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Writes `size` random bytes to `writer`. `progress` is called after every chunk with the
+/// number of bytes written so far and the total. Setting `cancel` stops the write after the
+/// current chunk with an `Interrupted` error.
+pub fn write_random_bytes<W: Write>(pool: &EntropyPool, writer: &mut W, size: u64, progress: &mut dyn FnMut(u64, u64), cancel: &AtomicBool) -> io::Result<()> {
+    let mut stream = StreamGenerator::new(pool, size).map_err(io::Error::other)?;
+    let mut buffer = vec![0u8; CHUNK_SIZE];
+    let mut written = 0;
+
+    while written < size {
+        if cancel.load(Ordering::Relaxed) {
+            buffer.fill(0);
+            return Err(io::Error::new(io::ErrorKind::Interrupted, "random byte output cancelled"));
+        }
+
+        let chunk_size = (size - written).min(CHUNK_SIZE as u64) as usize;
+        stream.fill_bytes(&mut buffer[..chunk_size]);
+        writer.write_all(&buffer[..chunk_size])?;
+
+        written += chunk_size as u64;
+        progress(written, size);
+    }
+
+    buffer.fill(0);
+    writer.flush()
+}
+
+/// Writes `size` random bytes to a new file at `path`. Refuses to overwrite an existing file.
+/// The bytes go to a temporary file next to the target first, which only appears under its
+/// final name once it is complete and synced, so a cancelled or failed write never leaves a
+/// truncated key file behind. On Unix the file is created with mode 0600; on Windows it gets
+/// the ACL of its directory.
+pub fn write_random_bytes_to_file(pool: &EntropyPool, path: &Path, size: u64, progress: &mut dyn FnMut(u64, u64), cancel: &AtomicBool) -> io::Result<()> {
+    if path.exists() {
+        return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("{} already exists", path.display())));
+    }
+
+    let temporary_path = temporary_path_for(path);
+    let mut file = create_new_private_file(&temporary_path)?;
+
+    let result = write_random_bytes(pool, &mut file, size, progress, cancel)
+        .and_then(|_| file.sync_all())
+        .and_then(|_| move_into_place(&temporary_path, path));
+
+    drop(file);
+    let _ = fs::remove_file(&temporary_path);
+    result
+}
+
+/// Gives the finished temporary file its final name without replacing anything. Linking
+/// fails if the target appeared in the meantime, unlike a rename, but FAT32, exFAT and some
+/// network shares have no hard links; there the target is checked and the file renamed,
+/// which can only lose a race with a file created in between.
+fn move_into_place(temporary_path: &Path, path: &Path) -> io::Result<()> {
+    match fs::hard_link(temporary_path, path) {
+        Ok(()) => Ok(()),
+        Err(error) if error.kind() == io::ErrorKind::AlreadyExists => Err(error),
+        Err(_) if path.exists() => Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("{} already exists", path.display()))),
+        Err(_) => fs::rename(temporary_path, path),
+    }
+}
+
+pub(crate) fn temporary_path_for(path: &Path) -> PathBuf {
+    let file_name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    path.with_file_name(format!(".{}.{}.pgen-tmp", file_name, std::process::id()))
+}
+
+/// Creates `path`, failing if it already exists. On Unix only the owner may read it.
+pub(crate) fn create_new_private_file(path: &Path) -> io::Result<File> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    options.open(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entropy::deterministic::{scratch_directory, seeded_pool};
+
+    #[test]
+    fn writes_the_requested_size_with_progress() {
+        let mut output = Vec::new();
+        let mut reports = Vec::new();
+        let size = CHUNK_SIZE as u64 * 2 + 5;
+        write_random_bytes(&seeded_pool(b"bytes"), &mut output, size, &mut |written, total| reports.push((written, total)), &AtomicBool::new(false)).unwrap();
+        assert_eq!(output.len() as u64, size);
+        assert_eq!(reports, [(CHUNK_SIZE as u64, size), (CHUNK_SIZE as u64 * 2, size), (size, size)]);
+    }
+
+    #[test]
+    fn cancelled_writes_are_interrupted() {
+        let mut output = Vec::new();
+        let error = write_random_bytes(&seeded_pool(b"bytes"), &mut output, 10, &mut |_, _| {}, &AtomicBool::new(true)).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::Interrupted);
+        assert!(output.is_empty());
+    }
+
+    #[test]
+    fn file_appears_complete_and_leaves_no_temporary_file() {
+        let directory = scratch_directory("bytes-complete");
+        let path = directory.join("key.bin");
+        write_random_bytes_to_file(&seeded_pool(b"bytes"), &path, 1000, &mut |_, _| {}, &AtomicBool::new(false)).unwrap();
+        assert_eq!(fs::metadata(&path).unwrap().len(), 1000);
+        assert_eq!(fs::read_dir(&directory).unwrap().count(), 1);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn existing_files_are_never_replaced() {
+        let directory = scratch_directory("bytes-existing");
+        let path = directory.join("key.bin");
+        fs::write(&path, b"keep").unwrap();
+        let error = write_random_bytes_to_file(&seeded_pool(b"bytes"), &path, 10, &mut |_, _| {}, &AtomicBool::new(false)).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::AlreadyExists);
+
+        let temporary_path = directory.join("temporary");
+        fs::write(&temporary_path, b"new").unwrap();
+        assert_eq!(move_into_place(&temporary_path, &path).unwrap_err().kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(fs::read(&path).unwrap(), b"keep");
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn cancelled_files_leave_nothing_behind() {
+        let directory = scratch_directory("bytes-cancelled");
+        let path = directory.join("key.bin");
+        let error = write_random_bytes_to_file(&seeded_pool(b"bytes"), &path, 10, &mut |_, _| {}, &AtomicBool::new(true)).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::Interrupted);
+        assert_eq!(fs::read_dir(&directory).unwrap().count(), 0);
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
\ No newline at end of file
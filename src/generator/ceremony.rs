@@ -0,0 +1,559 @@
+//! Key ceremonies: several people generate a shared secret, such as an HSM admin PIN or a
+//! backup key, without any one of them controlling it.
+//!
+//! Every participant makes a random contribution and hands in only its commitment. The
+//! coordinator opens the ceremony with those commitments plus a contribution of its own
+//! pool, and announces the ceremony's public key. Then everyone reveals; the secret is
+//! derived from all contributions, so anyone whose contribution is random and was committed
+//! before seeing the others makes the result unpredictable. Nobody can change their mind
+//! after seeing the other reveals, since a contribution must match its commitment.
+//!
+//! The transcript lists every commitment, the coordinator's contribution and a check value of
+//! the secret, signed with the ceremony key. It does not contain the secret. Each participant
+//! can find their own commitment in it. The participants' revealed contributions go into a
+//! separate reveal file instead, since together with the transcript they give the secret
+//! away. Anyone holding both can check every contribution against its commitment and
+//! recompute the check value, which proves that every contribution went into the secret.
+
+use std::io::{self, Write};
+use std::path::Path;
+
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+
+use crate::crypto;
+use crate::encoding;
+use crate::generator::bytes::create_new_private_file;
+use crate::generator::stream::StreamGenerator;
+use crate::pool::EntropyPool;
+
+const STATE_HEADER: &str = "P-GEN ceremony state v1";
+const TRANSCRIPT_HEADER: &str = "P-GEN ceremony transcript v1";
+const REVEAL_HEADER: &str = "P-GEN ceremony reveal v1";
+const COMMITMENT_DOMAIN: &[u8] = b"P-GEN CEREMONY COMMITMENT V1";
+const SECRET_KEY_DOMAIN: &[u8] = b"P-GEN CEREMONY SECRET KEY V1";
+const CHECK_VALUE_DOMAIN: &[u8] = b"P-GEN CEREMONY CHECK VALUE V1";
+/// The name the coordinator's own contribution goes under.
+pub const POOL_PARTICIPANT: &str = "P-GEN pool";
+/// Shorter contributions could be found from their commitments by brute force.
+pub const MIN_CONTRIBUTION_SIZE: usize = 16;
+pub const MIN_SECRET_SIZE: usize = 16;
+
+/// A participant's commitment, as handed to the coordinator.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Commitment {
+    pub name: String,
+    pub commitment: [u8; 32],
+}
+
+/// A participant's contribution, revealed once every commitment is in.
+#[derive(Clone)]
+pub struct Contribution {
+    pub name: String,
+    pub bytes: Vec<u8>,
+}
+
+/// An opened ceremony, as kept by the coordinator until the reveal. Keep it secret: it holds
+/// the signing key and the pool's contribution.
+pub struct Ceremony {
+    pub signing_key: [u8; 32],
+    pub secret_size: usize,
+    /// The participants' commitments followed by the pool's.
+    pub commitments: Vec<Commitment>,
+    pub pool_contribution: [u8; 32],
+}
+
+/// The signed record of a finished ceremony.
+pub struct CeremonyTranscript {
+    pub public_key: [u8; 32],
+    pub secret_size: usize,
+    pub commitments: Vec<Commitment>,
+    pub pool_contribution: [u8; 32],
+    pub check_value: [u8; 32],
+    pub signature: [u8; 64],
+}
+
+/// The participants' revealed contributions, in commitment order, without the pool's, which
+/// is in the transcript. Verifying that every contribution went into the secret needs it.
+/// Keep it as secret as the secret itself: with the transcript, it is enough to derive it.
+pub struct CeremonyReveal {
+    pub contributions: Vec<Contribution>,
+}
+
+impl Commitment {
+    /// Lines look like `commitment: <hex> <name>`; the field name may be left out.
+    pub fn parse(value: &str) -> Result<Commitment, String> {
+        let (commitment, name) = parse_named_value(value.strip_prefix("commitment: ").unwrap_or(value))?;
+        Ok(Commitment { name, commitment: parse_hash(commitment)? })
+    }
+
+    pub fn to_line(&self) -> String {
+        format!("commitment: {} {}\n", encoding::hex_encode(&self.commitment, false), self.name)
+    }
+}
+
+impl Contribution {
+    /// Makes a random contribution for `name` from the pool.
+    pub fn generate(pool: &EntropyPool, name: &str) -> Result<Contribution, String> {
+        let name = name.trim().to_string();
+        validate_name(&name)?;
+        let mut bytes = vec![0u8; 32];
+        StreamGenerator::new(pool, 32).map_err(|error| error.to_string())?.fill_bytes(&mut bytes);
+        Ok(Contribution { name, bytes })
+    }
+
+    /// SHA3-256 over the length-prefixed name and the contribution.
+    pub fn commitment(&self) -> Commitment {
+        let mut data = COMMITMENT_DOMAIN.to_vec();
+        data.extend_from_slice(&(self.name.len() as u64).to_be_bytes());
+        data.extend_from_slice(self.name.as_bytes());
+        data.extend_from_slice(&self.bytes);
+
+        let mut commitment = [0u8; 32];
+        commitment.copy_from_slice(&crypto::hashes::sha3_256_compute(&data));
+        data.fill(0);
+        Commitment { name: self.name.clone(), commitment }
+    }
+
+    /// Lines look like `contribution: <hex> <name>`; the field name may be left out.
+    pub fn parse(value: &str) -> Result<Contribution, String> {
+        let (bytes, name) = parse_named_value(value.strip_prefix("contribution: ").unwrap_or(value))?;
+        let bytes = encoding::hex_decode(bytes).ok_or(format!("invalid hex contribution from {}", name))?;
+        Ok(Contribution { name, bytes })
+    }
+
+    pub fn to_line(&self) -> String {
+        format!("contribution: {} {}\n", encoding::hex_encode(&self.bytes, false), self.name)
+    }
+}
+
+impl Ceremony {
+    /// Adds a contribution and a signing key from the pool to the participants' commitments.
+    /// Refused in FIPS-approved mode, since the secret is expanded with ChaCha20.
+    pub fn open(pool: &EntropyPool, commitments: &[Commitment], secret_size: usize) -> Result<Ceremony, String> {
+        pool.require_standard_mode("key ceremonies").map_err(|error| error.to_string())?;
+        if secret_size < MIN_SECRET_SIZE {
+            return Err(format!("the secret must be at least {} bytes", MIN_SECRET_SIZE));
+        }
+        if commitments.is_empty() {
+            return Err("a ceremony needs at least one participant besides P-GEN".to_string());
+        }
+
+        let mut random = [0u8; 64];
+        StreamGenerator::new(pool, 64).map_err(|error| error.to_string())?.fill_bytes(&mut random);
+        let mut signing_key = [0u8; 32];
+        let mut pool_contribution = [0u8; 32];
+        signing_key.copy_from_slice(&random[..32]);
+        pool_contribution.copy_from_slice(&random[32..]);
+        random.fill(0);
+
+        let mut commitments = commitments.to_vec();
+        commitments.push(Contribution { name: POOL_PARTICIPANT.to_string(), bytes: pool_contribution.to_vec() }.commitment());
+        validate_commitments(&commitments)?;
+        Ok(Ceremony { signing_key, secret_size, commitments, pool_contribution })
+    }
+
+    /// The key the transcript will be signed with. Announce it when the ceremony opens.
+    pub fn public_key(&self) -> [u8; 32] {
+        SigningKey::from_bytes(&self.signing_key).verifying_key().to_bytes()
+    }
+
+    /// The pool's commitment, to announce along with the public key.
+    pub fn pool_commitment(&self) -> &Commitment {
+        self.commitments.last().expect("an opened ceremony always has the pool's commitment")
+    }
+
+    /// Checks every participant's contribution against their commitment, derives the secret
+    /// and signs the transcript. Returns the secret, the transcript and the reveal.
+    pub fn reveal(&self, contributions: &[Contribution]) -> Result<(Vec<u8>, CeremonyTranscript, CeremonyReveal), String> {
+        let mut contributions = contributions.to_vec();
+        contributions.push(Contribution { name: POOL_PARTICIPANT.to_string(), bytes: self.pool_contribution.to_vec() });
+        let secret = derive_secret(&self.commitments, &contributions, self.secret_size)?;
+        let reveal = CeremonyReveal {
+            contributions: self.commitments[..self.commitments.len() - 1].iter()
+                .filter_map(|commitment| contributions.iter().find(|contribution| contribution.name == commitment.name).cloned())
+                .collect(),
+        };
+
+        let mut transcript = CeremonyTranscript {
+            public_key: self.public_key(),
+            secret_size: self.secret_size,
+            commitments: self.commitments.clone(),
+            pool_contribution: self.pool_contribution,
+            check_value: check_value(&secret),
+            signature: [0u8; 64],
+        };
+        transcript.signature = SigningKey::from_bytes(&self.signing_key).sign(transcript.signed_text().as_bytes()).to_bytes();
+        Ok((secret, transcript, reveal))
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = format!("{}\nsigning key: {}\nsecret size: {}\npool contribution: {}\n", STATE_HEADER, encoding::hex_encode(&self.signing_key, false), self.secret_size, encoding::hex_encode(&self.pool_contribution, false));
+        for commitment in &self.commitments {
+            text += &commitment.to_line();
+        }
+        text
+    }
+
+    /// Writes the state file to a new file only the owner can read. Never overwrites.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut file = create_new_private_file(path)?;
+        file.write_all(self.to_text().as_bytes())?;
+        file.sync_all()
+    }
+
+    pub fn parse(text: &str) -> Result<Ceremony, String> {
+        let mut signing_key = None;
+        let mut secret_size = None;
+        let mut pool_contribution = None;
+        let mut commitments = Vec::new();
+        for (field, value) in parse_fields(text, STATE_HEADER)? {
+            match field {
+                "signing key" => signing_key = Some(parse_hash(value)?),
+                "secret size" => secret_size = Some(parse_secret_size(value)?),
+                "pool contribution" => pool_contribution = Some(parse_hash(value)?),
+                "commitment" => commitments.push(Commitment::parse(value)?),
+                _ => return Err(format!("unknown field: {:?}", field)),
+            }
+        }
+
+        let ceremony = Ceremony {
+            signing_key: signing_key.ok_or("the state file has no signing key")?,
+            secret_size: secret_size.ok_or("the state file has no secret size")?,
+            commitments,
+            pool_contribution: pool_contribution.ok_or("the state file has no pool contribution")?,
+        };
+        validate_commitments(&ceremony.commitments)?;
+        if ceremony.commitments.last().map(|commitment| commitment.name.as_str()) != Some(POOL_PARTICIPANT) {
+            return Err("the state file does not end with the pool's commitment".to_string());
+        }
+        Ok(ceremony)
+    }
+}
+
+impl CeremonyTranscript {
+    /// Everything but the signature line, which is what gets signed.
+    fn signed_text(&self) -> String {
+        let mut text = format!("{}\npublic key: {}\nsecret size: {}\n", TRANSCRIPT_HEADER, encoding::hex_encode(&self.public_key, false), self.secret_size);
+        for commitment in &self.commitments {
+            text += &commitment.to_line();
+        }
+        text += &format!("pool contribution: {}\ncheck value: {}\n", encoding::hex_encode(&self.pool_contribution, false), encoding::hex_encode(&self.check_value, false));
+        text
+    }
+
+    pub fn to_text(&self) -> String {
+        format!("{}signature: {}\n", self.signed_text(), encoding::hex_encode(&self.signature, false))
+    }
+
+    pub fn parse(text: &str) -> Result<CeremonyTranscript, String> {
+        let mut public_key = None;
+        let mut secret_size = None;
+        let mut commitments = Vec::new();
+        let mut pool_contribution = None;
+        let mut check_value = None;
+        let mut signature = None;
+        for (field, value) in parse_fields(text, TRANSCRIPT_HEADER)? {
+            match field {
+                "public key" => public_key = Some(parse_hash(value)?),
+                "secret size" => secret_size = Some(parse_secret_size(value)?),
+                "commitment" => commitments.push(Commitment::parse(value)?),
+                "pool contribution" => pool_contribution = Some(parse_hash(value)?),
+                "check value" => check_value = Some(parse_hash(value)?),
+                "signature" => {
+                    signature = Some(encoding::hex_decode(value.trim())
+                        .and_then(|bytes| bytes.try_into().ok())
+                        .ok_or(format!("invalid 64-byte hex signature: {:?}", value))?)
+                }
+                _ => return Err(format!("unknown field: {:?}", field)),
+            }
+        }
+
+        Ok(CeremonyTranscript {
+            public_key: public_key.ok_or("the transcript has no public key")?,
+            secret_size: secret_size.ok_or("the transcript has no secret size")?,
+            commitments,
+            pool_contribution: pool_contribution.ok_or("the transcript has no pool contribution")?,
+            check_value: check_value.ok_or("the transcript has no check value")?,
+            signature: signature.ok_or("the transcript has no signature")?,
+        })
+    }
+
+    /// Checks the signature and the pool's commitment. With the reveal, also checks each
+    /// participant's contribution against its commitment and recomputes the check value.
+    pub fn verify(&self, reveal: Option<&CeremonyReveal>) -> Result<(), String> {
+        validate_commitments(&self.commitments)?;
+        let public_key = VerifyingKey::from_bytes(&self.public_key).map_err(|_| "the public key is not a valid Ed25519 key".to_string())?;
+        public_key.verify(self.signed_text().as_bytes(), &Signature::from_bytes(&self.signature))
+            .map_err(|_| "the signature does not match the transcript".to_string())?;
+
+        let pool_contribution = Contribution { name: POOL_PARTICIPANT.to_string(), bytes: self.pool_contribution.to_vec() };
+        if self.commitments.last() != Some(&pool_contribution.commitment()) {
+            return Err("the pool's contribution does not match its commitment".to_string());
+        }
+
+        if let Some(reveal) = reveal {
+            let mut contributions = reveal.contributions.clone();
+            contributions.push(pool_contribution);
+            let mut secret = derive_secret(&self.commitments, &contributions, self.secret_size)?;
+            let matches = check_value(&secret) == self.check_value;
+            secret.fill(0);
+            if !matches {
+                return Err("the contributions do not give the secret the transcript was made for".to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+impl CeremonyReveal {
+    pub fn to_text(&self) -> String {
+        let mut text = format!("{}\n", REVEAL_HEADER);
+        for contribution in &self.contributions {
+            text += &contribution.to_line();
+        }
+        text
+    }
+
+    /// Writes the reveal to a new file only the owner can read. Never overwrites.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut file = create_new_private_file(path)?;
+        file.write_all(self.to_text().as_bytes())?;
+        file.sync_all()
+    }
+
+    pub fn parse(text: &str) -> Result<CeremonyReveal, String> {
+        let mut contributions = Vec::new();
+        for (field, value) in parse_fields(text, REVEAL_HEADER)? {
+            match field {
+                "contribution" => contributions.push(Contribution::parse(value)?),
+                _ => return Err(format!("unknown field: {:?}", field)),
+            }
+        }
+        Ok(CeremonyReveal { contributions })
+    }
+}
+
+/// Writes the shared secret to a new file only the owner can read. Never overwrites.
+pub fn save_secret(secret: &[u8], path: &Path) -> io::Result<()> {
+    let mut file = create_new_private_file(path)?;
+    file.write_all(secret)?;
+    file.sync_all()
+}
+
+/// SHA3-256 over every contribution, in commitment order, keys a stream that produces the
+/// secret. Fails unless there is exactly one matching contribution per commitment.
+fn derive_secret(commitments: &[Commitment], contributions: &[Contribution], secret_size: usize) -> Result<Vec<u8>, String> {
+    let mut data = SECRET_KEY_DOMAIN.to_vec();
+    data.extend_from_slice(&(secret_size as u64).to_be_bytes());
+    data.extend_from_slice(&(commitments.len() as u64).to_be_bytes());
+    for commitment in commitments {
+        let mut matching = contributions.iter().filter(|contribution| contribution.name == commitment.name);
+        let contribution = match (matching.next(), matching.next()) {
+            (Some(contribution), None) => contribution,
+            (None, _) => return Err(format!("{} has not revealed their contribution", commitment.name)),
+            (Some(_), Some(_)) => return Err(format!("{} revealed more than one contribution", commitment.name)),
+        };
+        if contribution.bytes.len() < MIN_CONTRIBUTION_SIZE {
+            return Err(format!("the contribution from {} is shorter than {} bytes", commitment.name, MIN_CONTRIBUTION_SIZE));
+        }
+        if contribution.commitment() != *commitment {
+            return Err(format!("the contribution from {} does not match their commitment", commitment.name));
+        }
+        data.extend_from_slice(&(contribution.name.len() as u64).to_be_bytes());
+        data.extend_from_slice(contribution.name.as_bytes());
+        data.extend_from_slice(&(contribution.bytes.len() as u64).to_be_bytes());
+        data.extend_from_slice(&contribution.bytes);
+    }
+    if let Some(stranger) = contributions.iter().find(|contribution| !commitments.iter().any(|commitment| commitment.name == contribution.name)) {
+        return Err(format!("{} contributed without a commitment", stranger.name));
+    }
+
+    let key = crypto::hashes::sha3_256_compute(&data);
+    data.fill(0);
+    let mut secret = vec![0u8; secret_size];
+    StreamGenerator::from_key(key.as_slice().try_into().expect("SHA3-256 digests are 32 bytes"), &[0u8; 12]).fill_bytes(&mut secret);
+    Ok(secret)
+}
+
+fn check_value(secret: &[u8]) -> [u8; 32] {
+    let mut data = CHECK_VALUE_DOMAIN.to_vec();
+    data.extend_from_slice(secret);
+    let mut check_value = [0u8; 32];
+    check_value.copy_from_slice(&crypto::hashes::sha3_256_compute(&data));
+    data.fill(0);
+    check_value
+}
+
+fn validate_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.contains('\n') || name.trim() != name {
+        return Err("participant names must be non-empty single lines without surrounding whitespace".to_string());
+    }
+    Ok(())
+}
+
+fn validate_commitments(commitments: &[Commitment]) -> Result<(), String> {
+    for commitment in commitments {
+        validate_name(&commitment.name)?;
+    }
+    let mut names: Vec<&str> = commitments.iter().map(|commitment| commitment.name.as_str()).collect();
+    if names[..names.len().saturating_sub(1)].contains(&POOL_PARTICIPANT) {
+        return Err(format!("{:?} is reserved for P-GEN's own contribution", POOL_PARTICIPANT));
+    }
+    names.sort();
+    if names.windows(2).any(|pair| pair[0] == pair[1]) {
+        return Err("every participant must commit exactly once".to_string());
+    }
+    Ok(())
+}
+
+/// Checks the header and splits the remaining non-empty lines into fields and values.
+fn parse_fields<'a>(text: &'a str, header: &str) -> Result<Vec<(&'a str, &'a str)>, String> {
+    let mut lines = text.lines();
+    if lines.next().map(|line| line.trim_end()) != Some(header) {
+        return Err(format!("the first line must be {:?}", header));
+    }
+    lines.filter(|line| !line.trim().is_empty())
+        .map(|line| line.split_once(": ").ok_or(format!("malformed line: {:?}", line)))
+        .collect()
+}
+
+fn parse_named_value(value: &str) -> Result<(&str, String), String> {
+    let (hex, name) = value.trim().split_once(' ').ok_or(format!("expected <hex> <name>: {:?}", value))?;
+    let name = name.trim().to_string();
+    validate_name(&name)?;
+    Ok((hex, name))
+}
+
+fn parse_secret_size(text: &str) -> Result<usize, String> {
+    text.trim().parse().ok().filter(|&size| size >= MIN_SECRET_SIZE).ok_or(format!("invalid secret size: {:?}", text))
+}
+
+fn parse_hash(text: &str) -> Result<[u8; 32], String> {
+    encoding::hex_decode(text.trim())
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or(format!("invalid 32-byte hex value: {:?}", text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entropy::deterministic::seeded_pool;
+    use crate::pool::OperatingMode;
+
+    fn contributions() -> Vec<Contribution> {
+        ["alice", "bob"].iter().map(|name| Contribution { name: name.to_string(), bytes: vec![name.len() as u8; 32] }).collect()
+    }
+
+    fn revealed() -> (Vec<u8>, CeremonyTranscript, CeremonyReveal) {
+        let commitments: Vec<Commitment> = contributions().iter().map(Contribution::commitment).collect();
+        Ceremony::open(&seeded_pool(b"ceremony"), &commitments, 32).unwrap().reveal(&contributions()).unwrap()
+    }
+
+    #[test]
+    fn revealed_ceremonies_verify_and_round_trip() {
+        let (secret, transcript, reveal) = revealed();
+        assert_eq!(secret.len(), 32);
+        assert_eq!(transcript.commitments.len(), 3);
+        assert!(!transcript.to_text().contains(&encoding::hex_encode(&contributions()[0].bytes, false)));
+        transcript.verify(None).unwrap();
+        transcript.verify(Some(&reveal)).unwrap();
+
+        let parsed = CeremonyTranscript::parse(&transcript.to_text()).unwrap();
+        assert_eq!(parsed.to_text(), transcript.to_text());
+        let parsed_reveal = CeremonyReveal::parse(&reveal.to_text()).unwrap();
+        assert_eq!(parsed_reveal.to_text(), reveal.to_text());
+        parsed.verify(Some(&parsed_reveal)).unwrap();
+    }
+
+    #[test]
+    fn state_files_round_trip() {
+        let commitments: Vec<Commitment> = contributions().iter().map(Contribution::commitment).collect();
+        let ceremony = Ceremony::open(&seeded_pool(b"ceremony"), &commitments, 24).unwrap();
+        let parsed = Ceremony::parse(&ceremony.to_text()).unwrap();
+        assert_eq!(parsed.to_text(), ceremony.to_text());
+        assert_eq!(parsed.public_key(), ceremony.public_key());
+        assert_eq!(parsed.reveal(&contributions()).unwrap().0, ceremony.reveal(&contributions()).unwrap().0);
+    }
+
+    #[test]
+    fn tampered_transcripts_fail() {
+        let (_, transcript, reveal) = revealed();
+        let text = transcript.to_text();
+        let verify = |text: &str| CeremonyTranscript::parse(text).and_then(|transcript| transcript.verify(Some(&reveal)));
+
+        let alice = transcript.commitments[0].to_line();
+        let forged = Contribution { name: "alice".to_string(), bytes: vec![9; 32] }.commitment().to_line();
+        assert!(verify(&text.replace(&alice, &forged)).is_err());
+        assert!(verify(&text.replace("secret size: 32", "secret size: 33")).is_err());
+
+        let mut check_value = CeremonyTranscript::parse(&text).unwrap();
+        check_value.check_value[0] ^= 1;
+        assert!(check_value.verify(None).is_err());
+        let mut signature = CeremonyTranscript::parse(&text).unwrap();
+        signature.signature[0] ^= 1;
+        assert!(signature.verify(None).is_err());
+        let mut pool_contribution = CeremonyTranscript::parse(&text).unwrap();
+        pool_contribution.pool_contribution[0] ^= 1;
+        assert!(pool_contribution.verify(None).is_err());
+    }
+
+    #[test]
+    fn tampered_reveals_fail() {
+        let (_, transcript, reveal) = revealed();
+        let mut changed = CeremonyReveal::parse(&reveal.to_text()).unwrap();
+        changed.contributions[1].bytes[0] ^= 1;
+        assert!(transcript.verify(Some(&changed)).is_err());
+
+        let mut missing = CeremonyReveal::parse(&reveal.to_text()).unwrap();
+        missing.contributions.pop();
+        assert!(transcript.verify(Some(&missing)).is_err());
+
+        let mut extra = CeremonyReveal::parse(&reveal.to_text()).unwrap();
+        extra.contributions.push(Contribution { name: "mallory".to_string(), bytes: vec![7; 32] });
+        assert!(transcript.verify(Some(&extra)).is_err());
+    }
+
+    #[test]
+    fn reveals_need_every_matching_contribution() {
+        let commitments: Vec<Commitment> = contributions().iter().map(Contribution::commitment).collect();
+        let ceremony = Ceremony::open(&seeded_pool(b"ceremony"), &commitments, 32).unwrap();
+        assert!(ceremony.reveal(&contributions()[..1]).is_err());
+
+        let mut wrong = contributions();
+        wrong[0].bytes[0] ^= 1;
+        assert!(ceremony.reveal(&wrong).is_err());
+
+        let mut twice = contributions();
+        twice.push(contributions()[0].clone());
+        assert!(ceremony.reveal(&twice).is_err());
+    }
+
+    #[test]
+    fn malformed_files_are_rejected() {
+        let (_, transcript, reveal) = revealed();
+        let text = transcript.to_text();
+        assert!(CeremonyTranscript::parse(&text.replacen(TRANSCRIPT_HEADER, "P-GEN ceremony transcript v2", 1)).is_err());
+        assert!(CeremonyTranscript::parse(&format!("{}colour: blue\n", text)).is_err());
+        assert!(CeremonyTranscript::parse(&text.replace("signature: ", "signature: 00")).is_err());
+        assert!(CeremonyTranscript::parse(&text.replace("secret size: 32", "secret size: 8")).is_err());
+        assert!(CeremonyTranscript::parse(TRANSCRIPT_HEADER).is_err());
+        assert!(CeremonyReveal::parse(&reveal.to_text().replacen(REVEAL_HEADER, TRANSCRIPT_HEADER, 1)).is_err());
+        assert!(CeremonyReveal::parse(&format!("{}nonsense\n", reveal.to_text())).is_err());
+        assert!(Commitment::parse("commitment: 00 alice").is_err());
+        assert!(Contribution::parse("contribution: zz alice").is_err());
+    }
+
+    #[test]
+    fn invalid_ceremonies_are_refused() {
+        let pool = seeded_pool(b"ceremony");
+        let commitments: Vec<Commitment> = contributions().iter().map(Contribution::commitment).collect();
+        assert!(Ceremony::open(&pool, &[], 32).is_err());
+        assert!(Ceremony::open(&pool, &commitments, MIN_SECRET_SIZE - 1).is_err());
+        let reserved = Contribution { name: POOL_PARTICIPANT.to_string(), bytes: vec![1; 32] }.commitment();
+        assert!(Ceremony::open(&pool, &[reserved], 32).is_err());
+        assert!(Ceremony::open(&pool, &[commitments[0].clone(), commitments[0].clone()], 32).is_err());
+
+        pool.set_operating_mode(OperatingMode::FipsApproved);
+        assert!(Ceremony::open(&pool, &commitments, 32).is_err());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,267 @@
+//! Verifiable draws. The organiser first publishes a commitment to a secret seed, the
+//! participant list and the number of winners. After the draw the seed is revealed in a
+//! transcript, and anyone can check it against the commitment and replay the draw.
+//!
+//! An optional beacon, a public value nobody could predict at commitment time such as a
+//! later lottery result, is mixed into the draw but not the commitment, so the organiser
+//! can't grind seeds for a favourable outcome either.
+
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::crypto;
+use crate::encoding;
+use crate::generator::bytes::create_new_private_file;
+use crate::generator::stream::StreamGenerator;
+use crate::generator::utilities;
+use crate::pool::EntropyPool;
+
+const TRANSCRIPT_HEADER: &str = "P-GEN draw transcript v1";
+const COMMITMENT_DOMAIN: &[u8] = b"P-GEN DRAW COMMITMENT V1";
+const DRAW_KEY_DOMAIN: &[u8] = b"P-GEN DRAW KEY V1";
+
+/// A committed but not yet performed draw. Keep it secret until the reveal: anyone holding
+/// the seed can compute the winners.
+pub struct Draw {
+    pub seed: [u8; 32],
+    pub participants: Vec<String>,
+    pub winner_count: usize,
+}
+
+/// Everything needed to check a draw after the fact.
+pub struct DrawTranscript {
+    pub draw: Draw,
+    pub commitment: [u8; 32],
+    pub beacon: String,
+    pub winners: Vec<String>,
+}
+
+impl Draw {
+    /// Draws a fresh seed from the pool. Participants are trimmed and must be unique. Refused
+    /// in FIPS-approved mode, since winners are drawn from a ChaCha20 keystream.
+    pub fn commit(pool: &EntropyPool, participants: &[String], winner_count: usize) -> Result<Draw, String> {
+        pool.require_standard_mode("verifiable draws").map_err(|error| error.to_string())?;
+        let participants: Vec<String> = participants.iter().map(|participant| participant.trim().to_string()).filter(|participant| !participant.is_empty()).collect();
+        validate(&participants, winner_count)?;
+
+        let mut seed = [0u8; 32];
+        StreamGenerator::new(pool, 32).map_err(|error| error.to_string())?.fill_bytes(&mut seed);
+        Ok(Draw { seed, participants, winner_count })
+    }
+
+    /// SHA3-256 over the seed, the winner count and the length-prefixed participants.
+    pub fn commitment(&self) -> [u8; 32] {
+        let mut data = COMMITMENT_DOMAIN.to_vec();
+        data.extend_from_slice(&self.seed);
+        data.append(&mut self.encode_parameters());
+
+        let mut commitment = [0u8; 32];
+        commitment.copy_from_slice(&crypto::hashes::sha3_256_compute(&data));
+        commitment
+    }
+
+    /// Performs the draw. The winners only depend on the seed, the participants, the winner
+    /// count and the beacon.
+    pub fn winners(&self, beacon: &str) -> Vec<String> {
+        let mut data = DRAW_KEY_DOMAIN.to_vec();
+        data.extend_from_slice(&self.seed);
+        data.append(&mut self.encode_parameters());
+        data.extend_from_slice(&(beacon.len() as u64).to_be_bytes());
+        data.extend_from_slice(beacon.as_bytes());
+        let key = crypto::hashes::sha3_256_compute(&data);
+
+        let mut stream = StreamGenerator::from_key(key.as_slice().try_into().expect("SHA3-256 digests are 32 bytes"), &[0u8; 12]);
+        utilities::sample(&mut stream, &self.participants, self.winner_count).unwrap_or_default()
+    }
+
+    pub fn reveal(self, beacon: &str) -> DrawTranscript {
+        DrawTranscript {
+            commitment: self.commitment(),
+            beacon: beacon.to_string(),
+            winners: self.winners(beacon),
+            draw: self,
+        }
+    }
+
+    /// The state file written between commitment and reveal: a transcript without winners.
+    pub fn to_text(&self) -> String {
+        let mut text = format!("{}\ncommitment: {}\nseed: {}\nwinner count: {}\n", TRANSCRIPT_HEADER, encoding::hex_encode(&self.commitment(), false), encoding::hex_encode(&self.seed, false), self.winner_count);
+        for participant in &self.participants {
+            text += &format!("participant: {}\n", participant);
+        }
+        text
+    }
+
+    /// Writes the state file to a new file only the owner can read. Never overwrites.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut file = create_new_private_file(path)?;
+        file.write_all(self.to_text().as_bytes())?;
+        file.sync_all()
+    }
+
+    fn encode_parameters(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&(self.winner_count as u64).to_be_bytes());
+        data.extend_from_slice(&(self.participants.len() as u64).to_be_bytes());
+        for participant in &self.participants {
+            data.extend_from_slice(&(participant.len() as u64).to_be_bytes());
+            data.extend_from_slice(participant.as_bytes());
+        }
+        data
+    }
+}
+
+impl DrawTranscript {
+    pub fn to_text(&self) -> String {
+        let mut text = self.draw.to_text();
+        text += &format!("beacon: {}\n", self.beacon);
+        for winner in &self.winners {
+            text += &format!("winner: {}\n", winner);
+        }
+        text
+    }
+
+    /// Parses a transcript, or a state file written by `Draw::to_text` (which has no beacon or
+    /// winners).
+    pub fn parse(text: &str) -> Result<DrawTranscript, String> {
+        let mut lines = text.lines();
+        if lines.next().map(|line| line.trim_end()) != Some(TRANSCRIPT_HEADER) {
+            return Err(format!("not a transcript: the first line must be {:?}", TRANSCRIPT_HEADER));
+        }
+
+        let mut commitment = None;
+        let mut seed = None;
+        let mut winner_count = None;
+        let mut participants = Vec::new();
+        let mut beacon = String::new();
+        let mut winners = Vec::new();
+        for line in lines {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let (field, value) = line.split_once(": ").ok_or(format!("malformed line: {:?}", line))?;
+            match field {
+                "commitment" => commitment = Some(parse_hash(value)?),
+                "seed" => seed = Some(parse_hash(value)?),
+                "winner count" => winner_count = Some(value.parse().map_err(|_| format!("invalid winner count: {:?}", value))?),
+                "participant" => participants.push(value.to_string()),
+                "beacon" => beacon = value.to_string(),
+                "winner" => winners.push(value.to_string()),
+                _ => return Err(format!("unknown field: {:?}", field)),
+            }
+        }
+
+        Ok(DrawTranscript {
+            draw: Draw {
+                seed: seed.ok_or("the transcript has no seed")?,
+                participants,
+                winner_count: winner_count.ok_or("the transcript has no winner count")?,
+            },
+            commitment: commitment.ok_or("the transcript has no commitment")?,
+            beacon,
+            winners,
+        })
+    }
+
+    /// Checks the revealed seed against the commitment and replays the draw.
+    pub fn verify(&self) -> Result<(), String> {
+        validate(&self.draw.participants, self.draw.winner_count)?;
+        if self.draw.commitment() != self.commitment {
+            return Err("the seed, participants or winner count do not match the commitment".to_string());
+        }
+        if self.draw.winners(&self.beacon) != self.winners {
+            return Err("replaying the draw gives different winners".to_string());
+        }
+        Ok(())
+    }
+}
+
+fn validate(participants: &[String], winner_count: usize) -> Result<(), String> {
+    if participants.iter().any(|participant| participant.contains('\n') || participant.trim() != participant || participant.is_empty()) {
+        return Err("participants must be non-empty single lines without surrounding whitespace".to_string());
+    }
+    let mut sorted = participants.to_vec();
+    sorted.sort();
+    if sorted.windows(2).any(|pair| pair[0] == pair[1]) {
+        return Err("every participant must be listed exactly once".to_string());
+    }
+    if winner_count > participants.len() {
+        return Err(format!("cannot draw {} winners from {} participants", winner_count, participants.len()));
+    }
+    Ok(())
+}
+
+fn parse_hash(text: &str) -> Result<[u8; 32], String> {
+    encoding::hex_decode(text.trim())
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or(format!("invalid 32-byte hex value: {:?}", text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entropy::deterministic::seeded_pool;
+    use crate::pool::OperatingMode;
+
+    fn participants() -> Vec<String> {
+        ["alice", " bob ", "carol", "", "dave", "erin"].iter().map(|participant| participant.to_string()).collect()
+    }
+
+    #[test]
+    fn revealed_draws_verify_and_round_trip() {
+        let draw = Draw::commit(&seeded_pool(b"draw"), &participants(), 2).unwrap();
+        assert_eq!(draw.participants, ["alice", "bob", "carol", "dave", "erin"]);
+        let commitment = draw.commitment();
+
+        let transcript = draw.reveal("beacon 42");
+        assert_eq!(transcript.commitment, commitment);
+        assert_eq!(transcript.winners.len(), 2);
+        transcript.verify().unwrap();
+
+        let parsed = DrawTranscript::parse(&transcript.to_text()).unwrap();
+        assert_eq!(parsed.to_text(), transcript.to_text());
+        parsed.verify().unwrap();
+    }
+
+    #[test]
+    fn winners_depend_on_the_beacon() {
+        let draw = Draw { seed: [1; 32], participants: (0..100).map(|number| number.to_string()).collect(), winner_count: 3 };
+        assert_eq!(draw.winners("a"), draw.winners("a"));
+        assert_ne!(draw.winners("a"), draw.winners("b"));
+    }
+
+    #[test]
+    fn tampered_transcripts_fail() {
+        let text = Draw::commit(&seeded_pool(b"draw"), &participants(), 2).unwrap().reveal("beacon").to_text();
+        let verify = |text: &str| DrawTranscript::parse(text).and_then(|transcript| transcript.verify());
+
+        let mut winners = DrawTranscript::parse(&text).unwrap();
+        winners.winners.reverse();
+        assert!(winners.verify().is_err());
+        assert!(verify(&text.replace("participant: erin", "participant: mallory")).is_err());
+        assert!(verify(&text.replace("winner count: 2", "winner count: 1")).is_err());
+        assert!(verify(&text.replace("beacon: beacon", "beacon: other")).is_err());
+        assert!(verify(&text.replace("participant: erin\n", "participant: erin\nparticipant: erin\n")).is_err());
+    }
+
+    #[test]
+    fn malformed_transcripts_are_rejected() {
+        let text = Draw::commit(&seeded_pool(b"draw"), &participants(), 1).unwrap().to_text();
+        assert!(DrawTranscript::parse(&text).is_ok());
+        assert!(DrawTranscript::parse(&text.replacen(TRANSCRIPT_HEADER, "P-GEN draw transcript v2", 1)).is_err());
+        assert!(DrawTranscript::parse(&format!("{}nonsense\n", text)).is_err());
+        assert!(DrawTranscript::parse(&format!("{}colour: blue\n", text)).is_err());
+        assert!(DrawTranscript::parse(&text.replace("seed: ", "seed: 00")).is_err());
+        assert!(DrawTranscript::parse(TRANSCRIPT_HEADER).is_err());
+    }
+
+    #[test]
+    fn invalid_draws_are_refused() {
+        let pool = seeded_pool(b"draw");
+        assert!(Draw::commit(&pool, &participants(), 6).is_err());
+        assert!(Draw::commit(&pool, &["alice".to_string(), "alice".to_string()], 1).is_err());
+
+        pool.set_operating_mode(OperatingMode::FipsApproved);
+        assert!(Draw::commit(&pool, &participants(), 1).is_err());
+    }
+}
\ No newline at end of file
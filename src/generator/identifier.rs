@@ -0,0 +1,342 @@
+use std::fmt;
+
+use crate::encoding;
+use crate::generator::stream::StreamGenerator;
+use crate::pool::{EntropyPool, GenerationError};
+
+/// The URL-safe alphabet used by the reference NanoID implementation.
+pub const NANOID_DEFAULT_ALPHABET: &str = "useandom-26T198340PX75pxJACKVERYMINDBUSHWOLF_GQZbfghjklqvwyzrict";
+pub const NANOID_DEFAULT_SIZE: usize = 21;
+
+const ULID_ALPHABET: &[u8] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum IdentifierKind {
+    UuidV4,
+    UuidV7,
+    Ulid,
+    NanoId,
+}
+
+impl IdentifierKind {
+    pub const ALL: [IdentifierKind; 4] = [IdentifierKind::UuidV4, IdentifierKind::UuidV7, IdentifierKind::Ulid, IdentifierKind::NanoId];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            IdentifierKind::UuidV4 => "uuid4",
+            IdentifierKind::UuidV7 => "uuid7",
+            IdentifierKind::Ulid   => "ulid",
+            IdentifierKind::NanoId => "nanoid",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<IdentifierKind> {
+        IdentifierKind::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+}
+
+/// Why a batch of identifiers could not be produced.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum IdentifierError {
+    InvalidAlphabet,
+    UlidOverflow,
+    /// The batch is too large to size its stream.
+    TooMany { count: usize },
+    Generation(GenerationError),
+}
+
+impl fmt::Display for IdentifierError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IdentifierError::InvalidAlphabet   => write!(f, "The alphabet must hold between 2 and 256 distinct characters"),
+            IdentifierError::UlidOverflow      => write!(f, "Too many ULIDs within one millisecond"),
+            IdentifierError::TooMany { count } => write!(f, "Can't generate {} identifiers at once", count),
+            IdentifierError::Generation(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for IdentifierError {}
+
+impl From<GenerationError> for IdentifierError {
+    fn from(error: GenerationError) -> Self {
+        IdentifierError::Generation(error)
+    }
+}
+
+pub struct GenerateIdentifierParams {
+    pub kind: IdentifierKind,
+    /// NanoID only.
+    pub nanoid_size: usize,
+    /// NanoID only. Must contain between 2 and 256 distinct characters.
+    pub nanoid_alphabet: String,
+}
+
+impl Default for GenerateIdentifierParams {
+    fn default() -> Self {
+        Self {
+            kind: IdentifierKind::UuidV4,
+            nanoid_size: NANOID_DEFAULT_SIZE,
+            nanoid_alphabet: NANOID_DEFAULT_ALPHABET.to_string(),
+        }
+    }
+}
+
+/// Generates `count` identifiers from a single stream. ULIDs in a batch are monotonic.
+pub fn generate_identifiers(pool: &EntropyPool, params: &GenerateIdentifierParams, count: usize) -> Result<Vec<String>, IdentifierError> {
+    let alphabet: Vec<char> = params.nanoid_alphabet.chars().collect();
+    if params.kind == IdentifierKind::NanoId && !is_valid_nanoid_alphabet(&alphabet) {
+        return Err(IdentifierError::InvalidAlphabet);
+    }
+
+    let stream_size = count.checked_mul(16).ok_or(IdentifierError::TooMany { count })?;
+    let mut stream = StreamGenerator::new(pool, stream_size as u64)?;
+    let mut ulid_generator = UlidGenerator::new(true);
+    let mut identifiers = Vec::with_capacity(count);
+    for _ in 0..count {
+        let time_ns = pool.source().get_current_time_ns();
+        let identifier = match params.kind {
+            IdentifierKind::UuidV4 => uuid_v4_from(&mut stream),
+            IdentifierKind::UuidV7 => uuid_v7_from(&mut stream, time_ns),
+            IdentifierKind::Ulid   => ulid_generator.next_from(&mut stream, time_ns).ok_or(IdentifierError::UlidOverflow)?,
+            IdentifierKind::NanoId => nanoid_from(&mut stream, &alphabet, params.nanoid_size),
+        };
+        identifiers.push(identifier);
+    }
+    Ok(identifiers)
+}
+
+/// RFC 9562 version 4: 122 random bits.
+pub fn generate_uuid_v4(pool: &EntropyPool) -> Result<String, GenerationError> {
+    Ok(uuid_v4_from(&mut StreamGenerator::new(pool, 16)?))
+}
+
+/// RFC 9562 version 7: 48-bit Unix millisecond timestamp, then the sub-millisecond fraction
+/// in the 12 `rand_a` bits (method 3 of section 6.2), then 62 random bits.
+pub fn generate_uuid_v7(pool: &EntropyPool) -> Result<String, GenerationError> {
+    let time_ns = pool.source().get_current_time_ns();
+    Ok(uuid_v7_from(&mut StreamGenerator::new(pool, 16)?, time_ns))
+}
+
+/// A single non-monotonic ULID.
+pub fn generate_ulid(pool: &EntropyPool) -> Result<String, GenerationError> {
+    let time_ns = pool.source().get_current_time_ns();
+    Ok(UlidGenerator::new(false).next_from(&mut StreamGenerator::new(pool, 10)?, time_ns).unwrap_or_default())
+}
+
+/// Fails if the alphabet doesn't hold between 2 and 256 distinct characters.
+pub fn generate_nanoid(pool: &EntropyPool, alphabet: &str, size: usize) -> Result<String, IdentifierError> {
+    let alphabet: Vec<char> = alphabet.chars().collect();
+    if !is_valid_nanoid_alphabet(&alphabet) {
+        return Err(IdentifierError::InvalidAlphabet);
+    }
+    Ok(nanoid_from(&mut StreamGenerator::new(pool, size as u64)?, &alphabet, size))
+}
+
+/// Keeps the state needed to make ULIDs generated within the same millisecond sort in
+/// generation order, as described in the ULID specification.
+pub struct UlidGenerator {
+    monotonic: bool,
+    last_timestamp_ms: u64,
+    last_random: u128,
+}
+
+impl UlidGenerator {
+    pub fn new(monotonic: bool) -> Self {
+        Self {
+            monotonic,
+            last_timestamp_ms: 0,
+            last_random: 0,
+        }
+    }
+
+    /// Fails once the 80 random bits overflow within one millisecond.
+    pub fn generate(&mut self, pool: &EntropyPool) -> Result<String, IdentifierError> {
+        let time_ns = pool.source().get_current_time_ns();
+        self.next_from(&mut StreamGenerator::new(pool, 10)?, time_ns).ok_or(IdentifierError::UlidOverflow)
+    }
+
+    fn next_from(&mut self, stream: &mut StreamGenerator, time_ns: u128) -> Option<String> {
+        let timestamp_ms = ((time_ns / 1_000_000) as u64) & 0xffff_ffff_ffff;
+
+        let random = if self.monotonic && timestamp_ms <= self.last_timestamp_ms && self.last_timestamp_ms != 0 {
+            let incremented = self.last_random + 1;
+            if incremented >> 80 != 0 {
+                return None;
+            }
+            incremented
+        } else {
+            let mut random_bytes = [0u8; 16];
+            stream.fill_bytes(&mut random_bytes[6..]);
+            u128::from_be_bytes(random_bytes)
+        };
+
+        // A clock that went backwards keeps the last timestamp so ordering still holds.
+        if !self.monotonic || timestamp_ms > self.last_timestamp_ms {
+            self.last_timestamp_ms = timestamp_ms;
+        }
+        self.last_random = random;
+
+        let value = ((self.last_timestamp_ms as u128) << 80) | random;
+        Some((0..26).rev().map(|i| ULID_ALPHABET[((value >> (i * 5)) & 0x1f) as usize] as char).collect())
+    }
+}
+
+fn uuid_v4_from(stream: &mut StreamGenerator) -> String {
+    let mut bytes = [0u8; 16];
+    stream.fill_bytes(&mut bytes);
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    format_uuid(&bytes)
+}
+
+fn uuid_v7_from(stream: &mut StreamGenerator, time_ns: u128) -> String {
+    let timestamp_ms = (time_ns / 1_000_000) as u64;
+    let sub_millisecond_fraction = ((time_ns % 1_000_000) * 4096 / 1_000_000) as u16;
+
+    let mut bytes = [0u8; 16];
+    stream.fill_bytes(&mut bytes[8..]);
+    bytes[..6].copy_from_slice(&timestamp_ms.to_be_bytes()[2..]);
+    bytes[6] = 0x70 | (sub_millisecond_fraction >> 8) as u8;
+    bytes[7] = (sub_millisecond_fraction & 0xff) as u8;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    format_uuid(&bytes)
+}
+
+fn format_uuid(bytes: &[u8; 16]) -> String {
+    format!(
+        "{}-{}-{}-{}-{}",
+        encoding::hex_encode(&bytes[0..4], false),
+        encoding::hex_encode(&bytes[4..6], false),
+        encoding::hex_encode(&bytes[6..8], false),
+        encoding::hex_encode(&bytes[8..10], false),
+        encoding::hex_encode(&bytes[10..16], false)
+    )
+}
+
+fn is_valid_nanoid_alphabet(alphabet: &[char]) -> bool {
+    let mut sorted = alphabet.to_vec();
+    sorted.sort_unstable();
+    sorted.dedup();
+    sorted.len() == alphabet.len() && (2..=256).contains(&alphabet.len())
+}
+
+/// Masks each byte down to the next power of two above the alphabet size and rejects
+/// values past its end, so every character is equally likely.
+fn nanoid_from(stream: &mut StreamGenerator, alphabet: &[char], size: usize) -> String {
+    let mask = alphabet.len().next_power_of_two() - 1;
+    let mut nanoid = String::with_capacity(size);
+    let mut nanoid_length = 0;
+    let mut buffer = [0u8; 64];
+
+    while nanoid_length < size {
+        stream.fill_bytes(&mut buffer);
+        for &byte in &buffer {
+            let index = byte as usize & mask;
+            if index < alphabet.len() && nanoid_length < size {
+                nanoid.push(alphabet[index]);
+                nanoid_length += 1;
+            }
+        }
+    }
+    nanoid
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entropy::deterministic::seeded_pool;
+
+    fn test_stream() -> StreamGenerator {
+        StreamGenerator::from_key(&[1; 32], &[2; 12])
+    }
+
+    fn uuid_bytes(uuid: &str) -> Vec<u8> {
+        let groups: Vec<&str> = uuid.split('-').collect();
+        assert_eq!(groups.iter().map(|group| group.len()).collect::<Vec<_>>(), [8, 4, 4, 4, 12], "{}", uuid);
+        encoding::hex_decode(&groups.concat()).unwrap()
+    }
+
+    #[test]
+    fn uuid_v4_sets_version_and_variant() {
+        for uuid in generate_identifiers(&seeded_pool(b"identifiers"), &GenerateIdentifierParams::default(), 200).unwrap() {
+            let bytes = uuid_bytes(&uuid);
+            assert_eq!(bytes[6] >> 4, 4);
+            assert_eq!(bytes[8] >> 6, 0b10);
+        }
+    }
+
+    #[test]
+    fn uuid_v7_carries_the_timestamp() {
+        let time_ns = 1_700_000_000_123_500_000u128;
+        let bytes = uuid_bytes(&uuid_v7_from(&mut test_stream(), time_ns));
+        assert_eq!(bytes[6] >> 4, 7);
+        assert_eq!(bytes[8] >> 6, 0b10);
+        assert_eq!(u64::from_be_bytes([0, 0, bytes[0], bytes[1], bytes[2], bytes[3], bytes[4], bytes[5]]), 1_700_000_000_123);
+        // Half a millisecond is half of the 12-bit fraction.
+        assert_eq!(u16::from_be_bytes([bytes[6] & 0x0f, bytes[7]]), 2048);
+    }
+
+    #[test]
+    fn ulid_uses_crockford_base32() {
+        let mut generator = UlidGenerator::new(false);
+        let ulid = generator.next_from(&mut test_stream(), 1_469_918_176_385 * 1_000_000).unwrap();
+        assert_eq!(ulid.len(), 26);
+        // The timestamp from the ULID specification's example.
+        assert_eq!(&ulid[..10], "01ARYZ6S41");
+        assert!(ulid[..1] <= *"7");
+        assert!(ulid.bytes().all(|character| ULID_ALPHABET.contains(&character)));
+    }
+
+    #[test]
+    fn monotonic_ulids_increment_within_a_millisecond() {
+        let mut generator = UlidGenerator::new(true);
+        let mut stream = test_stream();
+        let time_ns = 1_469_918_176_385 * 1_000_000;
+        let first = generator.next_from(&mut stream, time_ns).unwrap();
+        let first_random = generator.last_random;
+        let second = generator.next_from(&mut stream, time_ns + 999_999).unwrap();
+        assert_eq!(generator.last_random, first_random + 1);
+        assert!(second > first);
+
+        // A clock that goes backwards still increments.
+        let third = generator.next_from(&mut stream, time_ns - 5_000_000).unwrap();
+        assert!(third > second);
+        assert_eq!(third[..10], first[..10]);
+
+        generator.last_random = (1 << 80) - 1;
+        assert_eq!(generator.next_from(&mut stream, time_ns), None);
+        assert!(generator.next_from(&mut stream, time_ns + 1_000_000).is_some());
+    }
+
+    #[test]
+    fn nanoid_uses_the_alphabet_and_size() {
+        let pool = seeded_pool(b"identifiers");
+        let nanoid = generate_nanoid(&pool, NANOID_DEFAULT_ALPHABET, NANOID_DEFAULT_SIZE).unwrap();
+        assert_eq!(nanoid.chars().count(), NANOID_DEFAULT_SIZE);
+        assert!(nanoid.chars().all(|character| NANOID_DEFAULT_ALPHABET.contains(character)));
+
+        let nanoid = generate_nanoid(&pool, "abc", 300).unwrap();
+        assert_eq!(nanoid.len(), 300);
+        for character in ['a', 'b', 'c'] {
+            assert!(nanoid.contains(character));
+        }
+        assert!(nanoid.chars().all(|character| "abc".contains(character)));
+        assert_eq!(generate_nanoid(&pool, "ĀāĂ", 5).unwrap().chars().count(), 5);
+    }
+
+    #[test]
+    fn nanoid_rejects_bad_alphabets() {
+        let pool = seeded_pool(b"identifiers");
+        assert_eq!(generate_nanoid(&pool, "a", 5), Err(IdentifierError::InvalidAlphabet));
+        assert_eq!(generate_nanoid(&pool, "abca", 5), Err(IdentifierError::InvalidAlphabet));
+        let too_long: String = (0..257).map(|code| char::from_u32(0x100 + code).unwrap()).collect();
+        assert_eq!(generate_nanoid(&pool, &too_long, 5), Err(IdentifierError::InvalidAlphabet));
+    }
+
+    #[test]
+    fn oversized_batches_are_refused() {
+        let error = generate_identifiers(&seeded_pool(b"identifiers"), &GenerateIdentifierParams::default(), usize::MAX).unwrap_err();
+        assert_eq!(error, IdentifierError::TooMany { count: usize::MAX });
+    }
+}
\ No newline at end of file
@@ -0,0 +1,151 @@
+use crate::pool::{EntropyPool, GenerationError};
+
+pub mod bip39;
+pub mod bytes;
+pub mod ceremony;
+pub mod draw;
+pub mod identifier;
+pub mod site_password;
+pub mod stream;
+pub mod token;
+pub mod utilities;
+
+use stream::StreamGenerator;
+
+pub(crate) static SALT_1: [u8; 32] = [
+    0x40, 0xf0, 0xe4, 0xd9, 0x81, 0x80, 0x8d, 0x5a, 0xe6, 0x1a, 0x7c, 0xef, 0x10, 0xee, 0x8f, 0xd9,
+    0x86, 0xc1, 0x2b, 0x1c, 0x3d, 0x02, 0x76, 0x46, 0x02, 0x3c, 0x2f, 0x3a, 0x6a, 0xf8, 0x54, 0x1a
+];
+pub(crate) static SALT_2: [u8; 32] = [
+    0x25, 0x02, 0x13, 0xbb, 0x51, 0xb7, 0x9f, 0xe2, 0xb3, 0xaa, 0x41, 0xdf, 0xbf, 0x53, 0x5e, 0xdc,
+    0x48, 0x4d, 0x48, 0x85, 0xa5, 0xe1, 0xe4, 0x6e, 0x34, 0x14, 0x14, 0xd1, 0x91, 0x1e, 0xb4, 0xab
+];
+pub(crate) static SALT_3: [u8; 32] = [
+    0xbb, 0x9c, 0xe0, 0x46, 0x9a, 0x34, 0x29, 0x3f, 0x6f, 0x90, 0x7f, 0xb1, 0x6e, 0x5e, 0x2f, 0x1f,
+    0xfb, 0xad, 0xfc, 0xff, 0xc4, 0xbc, 0xca, 0xdf, 0xc3, 0x02, 0x1a, 0x4e, 0x96, 0x61, 0x2b, 0x3c
+];
+pub(crate) static SALT_4: [u8; 32] = [
+    0x3b, 0x20, 0x03, 0x89, 0x90, 0x8f, 0xc6, 0x47, 0x97, 0x4c, 0xe9, 0xf0, 0x72, 0xad, 0x9b, 0x57,
+    0xd9, 0x76, 0x2f, 0x36, 0x31, 0x46, 0x08, 0xff, 0x3a, 0xff, 0xee, 0x4f, 0xa2, 0x92, 0x93, 0x8b
+];
+pub(crate) static SALT_5: [u8; 32] = [
+    0x02, 0xc6, 0x88, 0x12, 0xcb, 0xf2, 0xeb, 0x86, 0x60, 0x32, 0xbb, 0x06, 0xe5, 0x4a, 0x05, 0x80,
+    0x93, 0xe7, 0xb6, 0x78, 0x3f, 0xa8, 0x19, 0xbf, 0x52, 0x69, 0x89, 0x15, 0xf0, 0xb0, 0xe2, 0x5a
+];
+
+pub fn generate_stream(pool: &EntropyPool, size: usize) -> Result<Vec<u8>, GenerationError> {
+    let mut output_stream = vec![0; size];
+    StreamGenerator::new(pool, size as u64)?.fill_bytes(&mut output_stream);
+    Ok(output_stream)
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct GeneratePasswordParams {
+    pub size: u32,
+    pub use_uppercase_chars: bool,
+    pub use_lowercase_chars: bool,
+    pub use_numbers: bool,
+    pub use_logograms: bool,
+    pub use_punctuation: bool,
+    pub use_quotation_marks: bool,
+    pub use_dashes_and_slashes: bool,
+    pub use_maths_symbols: bool,
+    pub use_brackets: bool
+}
+
+/// The characters a password with these parameters is drawn from.
+pub fn password_characters(params: &GeneratePasswordParams) -> String {
+    let mut characters = String::new();
+    if params.use_uppercase_chars    { characters += "ABCDEFGHIJKLMNOPQRSTUVWXYZ"; }
+    if params.use_lowercase_chars    { characters += "abcdefghijklmnopqrstuvwxyz"; }
+    if params.use_numbers            { characters += "0123456789";                 }
+    if params.use_logograms          { characters += "#$%&@^`~";                   }
+    if params.use_punctuation        { characters += ".,:;";                       }
+    if params.use_quotation_marks    { characters += "\"'";                        }
+    if params.use_dashes_and_slashes { characters += "\\/|_-";                     }
+    if params.use_maths_symbols      { characters += "<>*+!?=";                    }
+    if params.use_brackets           { characters += "()[]{}";                     }
+    characters
+}
+
+/// Every character is drawn uniformly from `password_characters`, without modulo bias.
+pub fn generate_password(pool: &EntropyPool, params: &GeneratePasswordParams) -> Result<String, GenerationError> {
+    if params.size == 0 {
+        return Ok(String::new());
+    }
+
+    let characters = password_characters(params);
+    if characters.is_empty() {
+        return Ok(String::new());
+    }
+
+    let mut stream = StreamGenerator::new(pool, params.size as u64)?;
+    Ok(password_from(&mut stream, characters.as_bytes(), params.size))
+}
+
+/// Draws `size` characters uniformly from `characters`, which must not be empty.
+pub(crate) fn password_from(stream: &mut StreamGenerator, characters: &[u8], size: u32) -> String {
+    let mut password = String::new();
+    for _ in 0..size {
+        password.push(characters[utilities::uniform_below(stream, characters.len() as u128) as usize] as char);
+    }
+    password
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entropy::deterministic::seeded_pool;
+
+    fn all_characters(size: u32) -> GeneratePasswordParams {
+        GeneratePasswordParams {
+            size,
+            use_uppercase_chars: true,
+            use_lowercase_chars: true,
+            use_numbers: true,
+            use_logograms: true,
+            use_punctuation: true,
+            use_quotation_marks: true,
+            use_dashes_and_slashes: true,
+            use_maths_symbols: true,
+            use_brackets: true,
+        }
+    }
+
+    #[test]
+    fn seeded_passwords_are_pinned() {
+        let pool = seeded_pool(b"P-GEN test seed");
+        assert_eq!(generate_password(&pool, &all_characters(24)).unwrap(), "7HbWP~hCo9$A.47\":WqTO\"zF");
+        assert_eq!(generate_password(&pool, &all_characters(24)).unwrap(), "JExdhT-~4YKm|?{XH(EZ]PlI");
+    }
+
+    #[test]
+    fn seeded_stream_is_pinned() {
+        let pool = seeded_pool(b"P-GEN test seed");
+        let stream = generate_stream(&pool, 48).unwrap();
+        assert_eq!(crate::encoding::hex_encode(&stream, false), "4f2ba7c24d5efaa1dc43e4877fdc15fe506674d9a8d3d7413210fa6a96980e346170f80d48a5ec4ad4e9685c19182e2c");
+    }
+
+    #[test]
+    fn pools_with_the_same_seed_agree() {
+        let first = seeded_pool(b"same seed");
+        let second = seeded_pool(b"same seed");
+        for size in [1, 32, 1000] {
+            assert_eq!(generate_stream(&first, size).unwrap(), generate_stream(&second, size).unwrap());
+        }
+        assert_eq!(generate_password(&first, &all_characters(32)).unwrap(), generate_password(&second, &all_characters(32)).unwrap());
+
+        let other = seeded_pool(b"other seed");
+        assert_ne!(generate_stream(&seeded_pool(b"same seed"), 32).unwrap(), generate_stream(&other, 32).unwrap());
+    }
+
+    #[test]
+    fn passwords_use_only_the_selected_characters() {
+        let pool = seeded_pool(b"P-GEN test seed");
+        let params = GeneratePasswordParams { size: 200, use_numbers: true, ..Default::default() };
+        let password = generate_password(&pool, &params).unwrap();
+        assert_eq!(password.len(), 200);
+        assert!(password.bytes().all(|character| character.is_ascii_digit()));
+        assert_eq!(generate_password(&pool, &GeneratePasswordParams { size: 10, ..Default::default() }).unwrap(), "");
+    }
+}
\ No newline at end of file
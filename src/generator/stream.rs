@@ -0,0 +1,329 @@
+use std::io;
+
+use chacha20::ChaCha20;
+use chacha20::cipher::{KeyIvInit, StreamCipher};
+
+use crate::crypto;
+use crate::crypto::chacha20::{Key, Nonce};
+use crate::crypto::drbg::{self, HmacDrbg};
+use crate::generator::{SALT_1, SALT_2, SALT_3, SALT_4, SALT_5};
+use crate::entropy::dice::DiceEntropyMode;
+use crate::pool::{EntropyPool, GenerationError, OperatingMode, JITTER_ENTROPY_BITS};
+use crate::self_test;
+
+/// Number of output bytes produced under one key before the generator rekeys itself.
+pub const REKEY_INTERVAL: usize = 1 << 20;
+/// Bytes of OS entropy input a FIPS-mode stream is instantiated with.
+const FIPS_ENTROPY_INPUT_SIZE: usize = 48;
+const FIPS_PERSONALIZATION: &[u8] = b"P-GEN FIPS STREAM";
+
+/// Streaming random generator. The pool is only touched once, when the stream is seeded;
+/// after that output is a ChaCha20 keystream that replaces its own key with keystream
+/// bytes every `REKEY_INTERVAL` bytes, so earlier output can't be recovered from a later
+/// state. Memory use is constant and cost is linear in the number of bytes produced.
+///
+/// In FIPS-approved mode the stream is an HMAC_DRBG instead, whose state is updated after
+/// every request with the same effect.
+pub struct StreamGenerator {
+    core: StreamCore,
+}
+
+enum StreamCore {
+    ChaCha20 { cipher: ChaCha20, bytes_until_rekey: usize },
+    Drbg(HmacDrbg),
+}
+
+impl StreamGenerator {
+    /// Seeds a new stream from the pool. `size` is the number of bytes the caller intends to
+    /// read (or 0 if unknown) and is only used for domain separation. Fails if the pool is
+    /// waiting for dice rolls or user input, depending on its entropy gate, for more
+    /// gathered entropy, or if a self-test has failed.
+    pub fn new(pool: &EntropyPool, size: u64) -> Result<Self, GenerationError> {
+        self_test::check()?;
+        pool.check_entropy_gate()?;
+        let stream = match pool.operating_mode() {
+            OperatingMode::Standard     => Self::seed(pool, size)?,
+            OperatingMode::FipsApproved => Self::seed_fips(pool, size)?,
+        };
+        pool.reset_entropy_credits();
+        Ok(stream)
+    }
+
+    fn seed(pool: &EntropyPool, size: u64) -> Result<Self, GenerationError> {
+        let mut dice_entropy = pool.dice_entropy();
+        let mut dice_bits = Vec::new();
+        if dice_entropy.mode != DiceEntropyMode::Disabled && !dice_entropy.has_required_bits() {
+            return Err(GenerationError::InsufficientDiceEntropy { available_bits: dice_entropy.available_bits(), required_bits: dice_entropy.required_bits });
+        }
+
+        // The dice-only path ignores user input like everything else.
+        let mut user_entropy = pool.user_entropy();
+        if dice_entropy.mode != DiceEntropyMode::Exclusive && user_entropy.required && user_entropy.take_pending_contributions() == 0 {
+            return Err(GenerationError::MissingUserEntropy);
+        }
+        drop(user_entropy);
+
+        if dice_entropy.mode != DiceEntropyMode::Disabled {
+            dice_bits = dice_entropy.take_bits();
+        }
+
+        let mut entropy_vec: Vec<u8> = Vec::new();
+        entropy_vec.append(&mut size.to_be_bytes().to_vec());
+        entropy_vec.append(&mut pool.next_stream_index().to_be_bytes().to_vec());
+
+        if dice_entropy.mode == DiceEntropyMode::Exclusive {
+            entropy_vec.append(&mut dice_bits);
+            return Self::from_entropy_vec(pool, entropy_vec);
+        }
+        drop(dice_entropy);
+
+        let mut source = pool.source();
+        entropy_vec.append(&mut pool.start_time.to_be_bytes().to_vec());
+        entropy_vec.append(&mut source.get_current_time_ns().to_be_bytes().to_vec());
+
+        let mouse_position_entropy = pool.mouse_position_entropy.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        entropy_vec.push(1);
+        entropy_vec.append(&mut crypto::hashes::whirlpool_512_compute(&mouse_position_entropy.data));
+        drop(mouse_position_entropy);
+
+        match pool.jitter_entropy().as_mut().map(|jitter_entropy| jitter_entropy.generate(JITTER_ENTROPY_BITS)) {
+            Some(Ok(mut jitter_bytes)) => {
+                pool.continuous_test("Jitter continuous", &jitter_bytes)?;
+                entropy_vec.push(1);
+                entropy_vec.append(&mut jitter_bytes);
+            }
+            _ => entropy_vec.push(0),
+        }
+
+        match pool.system_entropy().as_mut().map(|system_entropy| system_entropy.generate()) {
+            Some(mut system_bytes) => {
+                pool.continuous_test("System continuous", &system_bytes)?;
+                entropy_vec.push(1);
+                entropy_vec.append(&mut system_bytes);
+            }
+            None => entropy_vec.push(0),
+        }
+
+        let mut last_used_entropy = pool.last_used_entropy.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        if !last_used_entropy.is_empty() {
+            entropy_vec.push(1);
+            entropy_vec.append(&mut last_used_entropy.clone());
+        } else {
+            entropy_vec.push(0);
+        }
+
+        entropy_vec.push(!dice_bits.is_empty() as u8);
+        entropy_vec.append(&mut dice_bits);
+
+        let mut os_bytes = source.generate_bytes(40);
+        let mut key = [0u8; crypto::chacha20::KEY_SIZE];
+        let mut iv = [0u8; crypto::chacha20::NONCE_SIZE];
+        source.fill_bytes(&mut key);
+        source.fill_bytes(&mut iv);
+        pool.test_os_bytes(&os_bytes);
+        pool.test_os_bytes(&key);
+        pool.test_os_bytes(&iv);
+        pool.continuous_test("OS continuous", &[os_bytes.as_slice(), &key, &iv].concat())?;
+        entropy_vec.append(&mut os_bytes);
+        crypto::chacha20::chacha20_apply_keystream(&Key::new(key), &Nonce::new(iv), &mut entropy_vec);
+        key.fill(0);
+
+        let mut last_used_entropy_vec = entropy_vec.clone();
+        last_used_entropy_vec.append(&mut SALT_5.to_vec());
+        *last_used_entropy = crypto::hashes::whirlpool_512_compute(&last_used_entropy_vec);
+
+        Self::from_entropy_vec(pool, entropy_vec)
+    }
+
+    /// Instantiates an HMAC_DRBG from the OS RNG. The personalization string holds the clock,
+    /// the stream's size and index, and jitter, kernel state and dice conditioned with SHA-3
+    /// and SHA-512; the cursor, user input and the chaining value are left out.
+    fn seed_fips(pool: &EntropyPool, size: u64) -> Result<Self, GenerationError> {
+        let mut dice_entropy = pool.dice_entropy();
+        if dice_entropy.mode == DiceEntropyMode::Exclusive {
+            return Err(GenerationError::UnavailableInFipsMode { feature: "seeding from dice alone" });
+        }
+        if dice_entropy.mode != DiceEntropyMode::Disabled && !dice_entropy.has_required_bits() {
+            return Err(GenerationError::InsufficientDiceEntropy { available_bits: dice_entropy.available_bits(), required_bits: dice_entropy.required_bits });
+        }
+        if pool.user_entropy().required {
+            return Err(GenerationError::UnavailableInFipsMode { feature: "required user input" });
+        }
+
+        let mut supplementary_input = Vec::new();
+        match pool.jitter_entropy().as_mut().map(|jitter_entropy| jitter_entropy.generate(JITTER_ENTROPY_BITS)) {
+            Some(Ok(jitter_bytes)) => {
+                pool.continuous_test("Jitter continuous", &jitter_bytes)?;
+                supplementary_input.push(1);
+                supplementary_input.append(&mut crypto::hashes::sha3_512_compute(&jitter_bytes));
+            }
+            _ => supplementary_input.push(0),
+        }
+        match pool.system_entropy().as_mut().map(|system_entropy| system_entropy.generate()) {
+            Some(system_bytes) => {
+                pool.continuous_test("System continuous", &system_bytes)?;
+                supplementary_input.push(1);
+                supplementary_input.append(&mut crypto::hashes::sha3_512_compute(&system_bytes));
+            }
+            None => supplementary_input.push(0),
+        }
+        if dice_entropy.mode != DiceEntropyMode::Disabled {
+            let mut dice_bits = dice_entropy.take_bits();
+            supplementary_input.push(1);
+            supplementary_input.append(&mut crypto::hashes::sha3_512_compute(&dice_bits));
+            dice_bits.fill(0);
+        } else {
+            supplementary_input.push(0);
+        }
+        drop(dice_entropy);
+
+        let mut source = pool.source();
+        let mut personalization = FIPS_PERSONALIZATION.to_vec();
+        personalization.extend_from_slice(&size.to_be_bytes());
+        personalization.extend_from_slice(&pool.next_stream_index().to_be_bytes());
+        personalization.extend_from_slice(&pool.start_time.to_be_bytes());
+        personalization.extend_from_slice(&source.get_current_time_ns().to_be_bytes());
+        personalization.append(&mut crypto::hashes::sha512_compute(&supplementary_input));
+        supplementary_input.fill(0);
+
+        let mut entropy_input = [0u8; FIPS_ENTROPY_INPUT_SIZE];
+        let mut nonce = [0u8; drbg::MIN_NONCE_SIZE];
+        source.fill_bytes(&mut entropy_input);
+        source.fill_bytes(&mut nonce);
+        drop(source);
+        pool.test_os_bytes(&entropy_input);
+        pool.test_os_bytes(&nonce);
+        pool.continuous_test("OS continuous", &[entropy_input.as_slice(), &nonce].concat())?;
+
+        let drbg = HmacDrbg::instantiate(&entropy_input, &nonce, &personalization).expect("entropy input and nonce have approved sizes");
+        entropy_input.fill(0);
+        personalization.fill(0);
+        Ok(Self { core: StreamCore::Drbg(drbg) })
+    }
+
+    /// Derives the stream's key and nonce, and runs the continuous test on the key.
+    fn from_entropy_vec(pool: &EntropyPool, mut entropy_vec: Vec<u8>) -> Result<Self, GenerationError> {
+        let mut key_material = SALT_1.to_vec();
+        key_material.append(&mut entropy_vec.clone());
+        let key = crypto::hashes::perform_joined_digest(key_material);
+
+        let mut nonce_material = SALT_2.to_vec();
+        nonce_material.append(&mut entropy_vec);
+        let nonce = crypto::hashes::blake3_256_compute(&nonce_material);
+        nonce_material.fill(0);
+
+        pool.continuous_test("Stream key continuous", &key)?;
+        Ok(Self::from_key(key.as_slice().try_into().expect("the joined digest is 32 bytes"), nonce[..12].try_into().expect("the nonce is cut to 12 bytes")))
+    }
+
+    /// Starts a stream directly from a 32-byte key and 12-byte nonce, bypassing the pool.
+    pub fn from_key(key: &[u8; 32], nonce: &[u8; 12]) -> Self {
+        Self {
+            core: StreamCore::ChaCha20 {
+                cipher: ChaCha20::new(key.into(), nonce.into()),
+                bytes_until_rekey: REKEY_INTERVAL,
+            },
+        }
+    }
+
+    pub fn fill_bytes(&mut self, output: &mut [u8]) {
+        match &mut self.core {
+            StreamCore::ChaCha20 { cipher, bytes_until_rekey } => Self::fill_bytes_chacha20(cipher, bytes_until_rekey, output),
+            StreamCore::Drbg(drbg) => {
+                for chunk in output.chunks_mut(drbg::MAX_REQUEST_SIZE) {
+                    // 2^48 requests of 64 KiB: a stream won't come near the reseed interval.
+                    drbg.generate(chunk, &[]).expect("requests are within HMAC_DRBG limits");
+                }
+            }
+        }
+    }
+
+    fn fill_bytes_chacha20(cipher: &mut ChaCha20, bytes_until_rekey: &mut usize, mut output: &mut [u8]) {
+        while !output.is_empty() {
+            if *bytes_until_rekey == 0 {
+                *cipher = Self::rekey(cipher);
+                *bytes_until_rekey = REKEY_INTERVAL;
+            }
+
+            let chunk_size = output.len().min(*bytes_until_rekey);
+            let (chunk, rest) = output.split_at_mut(chunk_size);
+            chunk.fill(0);
+            cipher.apply_keystream(chunk);
+
+            *bytes_until_rekey -= chunk_size;
+            output = rest;
+        }
+    }
+
+    fn rekey(cipher: &mut ChaCha20) -> ChaCha20 {
+        let mut keystream = [0u8; 32];
+        cipher.apply_keystream(&mut keystream);
+
+        let mut key_material = SALT_3.to_vec();
+        key_material.extend_from_slice(&keystream);
+        let key = crypto::hashes::blake3_256_compute(&key_material);
+
+        let mut nonce_material = SALT_4.to_vec();
+        nonce_material.extend_from_slice(&keystream);
+        let nonce = crypto::hashes::blake3_256_compute(&nonce_material);
+        keystream.fill(0);
+
+        ChaCha20::new(key.as_slice().into(), nonce[..12].into())
+    }
+}
+
+impl io::Read for StreamGenerator {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.fill_bytes(buf);
+        Ok(buf.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    const KEY: [u8; 32] = [7; 32];
+    const NONCE: [u8; 12] = [9; 12];
+
+    #[test]
+    fn first_interval_is_the_plain_keystream() {
+        let mut output = vec![0u8; 4096];
+        StreamGenerator::from_key(&KEY, &NONCE).fill_bytes(&mut output);
+
+        let mut keystream = vec![0u8; 4096];
+        ChaCha20::new((&KEY).into(), (&NONCE).into()).apply_keystream(&mut keystream);
+        assert_eq!(output, keystream);
+    }
+
+    #[test]
+    fn rekeys_after_the_interval() {
+        let mut output = vec![0u8; REKEY_INTERVAL + 64];
+        StreamGenerator::from_key(&KEY, &NONCE).fill_bytes(&mut output);
+
+        let mut keystream = vec![0u8; REKEY_INTERVAL + 64];
+        ChaCha20::new((&KEY).into(), (&NONCE).into()).apply_keystream(&mut keystream);
+        assert_eq!(output[..REKEY_INTERVAL], keystream[..REKEY_INTERVAL]);
+        assert_ne!(output[REKEY_INTERVAL..], keystream[REKEY_INTERVAL..]);
+    }
+
+    #[test]
+    fn output_does_not_depend_on_read_sizes() {
+        let size = REKEY_INTERVAL * 2 + 1000;
+        let mut whole = vec![0u8; size];
+        StreamGenerator::from_key(&KEY, &NONCE).fill_bytes(&mut whole);
+
+        let mut pieces = Vec::with_capacity(size);
+        let mut stream = StreamGenerator::from_key(&KEY, &NONCE);
+        let mut piece_size = 1;
+        while pieces.len() < size {
+            let mut piece = vec![0u8; piece_size.min(size - pieces.len())];
+            stream.read_exact(&mut piece).unwrap();
+            pieces.extend_from_slice(&piece);
+            piece_size = piece_size * 3 + 1;
+        }
+        assert_eq!(whole, pieces);
+    }
+}
\ No newline at end of file
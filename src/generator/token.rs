@@ -0,0 +1,156 @@
+use std::fmt;
+
+use crate::encoding;
+use crate::generator::generate_stream;
+use crate::pool::{EntropyPool, GenerationError};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TokenEncoding {
+    HexLower,
+    HexUpper,
+    Base32,
+    CrockfordBase32,
+    Base58,
+    Base64,
+    Base64Url,
+    Z85,
+}
+
+impl TokenEncoding {
+    pub const ALL: [TokenEncoding; 8] = [
+        TokenEncoding::HexLower,
+        TokenEncoding::HexUpper,
+        TokenEncoding::Base32,
+        TokenEncoding::CrockfordBase32,
+        TokenEncoding::Base58,
+        TokenEncoding::Base64,
+        TokenEncoding::Base64Url,
+        TokenEncoding::Z85,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TokenEncoding::HexLower        => "hex",
+            TokenEncoding::HexUpper        => "HEX",
+            TokenEncoding::Base32          => "base32",
+            TokenEncoding::CrockfordBase32 => "crockford",
+            TokenEncoding::Base58          => "base58",
+            TokenEncoding::Base64          => "base64",
+            TokenEncoding::Base64Url       => "base64url",
+            TokenEncoding::Z85             => "z85",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<TokenEncoding> {
+        TokenEncoding::ALL.into_iter().find(|encoding| encoding.name() == name)
+    }
+
+    /// Whether `GenerateTokenParams::padding` has any effect on this encoding.
+    pub fn supports_padding(&self) -> bool {
+        matches!(self, TokenEncoding::Base32 | TokenEncoding::Base64 | TokenEncoding::Base64Url)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TokenError {
+    /// Z85 encodes 4 bytes at a time and can't encode a partial group.
+    InvalidLength { encoding: TokenEncoding, size: usize },
+}
+
+impl fmt::Display for TokenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenError::InvalidLength { encoding, size } => write!(f, "{} can't encode {} bytes, only a multiple of 4", encoding.name(), size),
+        }
+    }
+}
+
+impl std::error::Error for TokenError {}
+
+pub struct GenerateTokenParams {
+    /// Number of random bytes in the token. Z85 rounds this up to a multiple of 4.
+    pub size: usize,
+    pub encoding: TokenEncoding,
+    pub padding: bool,
+}
+
+impl GenerateTokenParams {
+    /// Parameters for a token carrying at least `bits` bits of randomness.
+    pub fn from_bits(bits: usize, encoding: TokenEncoding, padding: bool) -> Self {
+        Self {
+            size: bits.div_ceil(8),
+            encoding,
+            padding,
+        }
+    }
+}
+
+pub fn generate_token(pool: &EntropyPool, params: &GenerateTokenParams) -> Result<String, GenerationError> {
+    let mut size = params.size;
+    if params.encoding == TokenEncoding::Z85 {
+        size = size.div_ceil(4) * 4;
+    }
+    if size == 0 {
+        return Ok(String::new());
+    }
+
+    let mut stream = generate_stream(pool, size)?;
+    let token = encode_token(&stream, params.encoding, params.padding).expect("Z85 sizes are rounded up to a multiple of 4");
+    stream.fill(0);
+    Ok(token)
+}
+
+/// Fails only for Z85 when the length of `data` is not a multiple of 4.
+pub fn encode_token(data: &[u8], encoding: TokenEncoding, padding: bool) -> Result<String, TokenError> {
+    Ok(match encoding {
+        TokenEncoding::HexLower        => encoding::hex_encode(data, false),
+        TokenEncoding::HexUpper        => encoding::hex_encode(data, true),
+        TokenEncoding::Base32          => encoding::base32_encode(data, padding),
+        TokenEncoding::CrockfordBase32 => encoding::crockford_base32_encode(data),
+        TokenEncoding::Base58          => encoding::base58_encode(data),
+        TokenEncoding::Base64          => encoding::base64_encode(data, padding),
+        TokenEncoding::Base64Url       => encoding::base64_url_encode(data, padding),
+        TokenEncoding::Z85             => encoding::z85_encode(data).ok_or(TokenError::InvalidLength { encoding, size: data.len() })?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entropy::deterministic::seeded_pool;
+
+    #[test]
+    fn z85_rejects_partial_groups() {
+        assert_eq!(encode_token(&[1, 2, 3], TokenEncoding::Z85, false), Err(TokenError::InvalidLength { encoding: TokenEncoding::Z85, size: 3 }));
+        assert_eq!(encode_token(&[0x86, 0x4f, 0xd2, 0x6f], TokenEncoding::Z85, false).unwrap(), "Hello");
+    }
+
+    #[test]
+    fn encodings_round_trip_through_their_names() {
+        for encoding in TokenEncoding::ALL {
+            assert_eq!(TokenEncoding::from_name(encoding.name()), Some(encoding));
+        }
+        assert_eq!(TokenEncoding::from_name("base16"), None);
+    }
+
+    #[test]
+    fn token_lengths_match_their_sizes() {
+        let pool = seeded_pool(b"token");
+        let cases = [
+            (TokenEncoding::HexLower, false, 64),
+            (TokenEncoding::Base32, true, 56),
+            (TokenEncoding::Base32, false, 52),
+            (TokenEncoding::Base64, true, 44),
+            (TokenEncoding::Base64Url, false, 43),
+            (TokenEncoding::Z85, false, 40),
+        ];
+        for (encoding, padding, length) in cases {
+            let token = generate_token(&pool, &GenerateTokenParams::from_bits(256, encoding, padding)).unwrap();
+            assert_eq!(token.len(), length, "{}", encoding.name());
+        }
+
+        let z85 = generate_token(&pool, &GenerateTokenParams { size: 5, encoding: TokenEncoding::Z85, padding: false }).unwrap();
+        assert_eq!(z85.len(), 10);
+        assert_eq!(generate_token(&pool, &GenerateTokenParams { size: 0, encoding: TokenEncoding::Base58, padding: false }).unwrap(), "");
+    }
+}
\ No newline at end of file
@@ -0,0 +1,316 @@
+//! Uniform sampling from a stream: integers, big integers and floats in a range, shuffles,
+//! samples without replacement and dice rolls. Integer draws reject and redraw the values
+//! that would bias them, so every outcome is exactly as likely as the next.
+
+use num_bigint::{BigInt, BigUint};
+
+use crate::generator::stream::StreamGenerator;
+
+/// Dice notation is capped so a typo can't ask for a billion rolls.
+pub const MAX_DICE_COUNT: u64 = 1000;
+
+pub fn next_u64(stream: &mut StreamGenerator) -> u64 {
+    let mut bytes = [0u8; 8];
+    stream.fill_bytes(&mut bytes);
+    u64::from_le_bytes(bytes)
+}
+
+fn next_u128(stream: &mut StreamGenerator) -> u128 {
+    let mut bytes = [0u8; 16];
+    stream.fill_bytes(&mut bytes);
+    u128::from_le_bytes(bytes)
+}
+
+/// Uniform integer in `0..bound`. Values from the top partial copy of `0..bound` are
+/// rejected and redrawn, so there is no modulo bias. `bound` must not be 0.
+pub fn uniform_below(stream: &mut StreamGenerator, bound: u128) -> u128 {
+    assert!(bound != 0, "uniform_below needs a non-zero bound");
+    let rejection_threshold = bound.wrapping_neg() % bound;
+    loop {
+        let value = next_u128(stream);
+        if value >= rejection_threshold {
+            return value % bound;
+        }
+    }
+}
+
+/// Uniform integer in `min..=max`. Returns `None` if `min > max`.
+pub fn uniform_int(stream: &mut StreamGenerator, min: i64, max: i64) -> Option<i64> {
+    if min > max {
+        return None;
+    }
+    let span = (max as i128 - min as i128 + 1) as u128;
+    Some((min as i128 + uniform_below(stream, span) as i128) as i64)
+}
+
+/// Uniform integer in `min..=max` for ranges of any size. Candidates are drawn with exactly
+/// as many bits as the span needs and rejected if they fall outside it.
+pub fn uniform_bigint(stream: &mut StreamGenerator, min: &BigInt, max: &BigInt) -> Option<BigInt> {
+    if min > max {
+        return None;
+    }
+    let span = (max - min + 1u32).to_biguint()?;
+    let bits = span.bits();
+    let mut bytes = vec![0u8; bits.div_ceil(8) as usize];
+    let top_byte_mask = match bits % 8 {
+        0 => 0xff,
+        remaining_bits => (1u8 << remaining_bits) - 1,
+    };
+
+    loop {
+        stream.fill_bytes(&mut bytes);
+        bytes[0] &= top_byte_mask;
+        let candidate = BigUint::from_bytes_be(&bytes);
+        if candidate < span {
+            bytes.fill(0);
+            return Some(min + BigInt::from(candidate));
+        }
+    }
+}
+
+/// Uniform float in `[0, 1)` with 53 random bits, every representable multiple of 2^-53
+/// being equally likely.
+pub fn uniform_unit_f64(stream: &mut StreamGenerator) -> f64 {
+    (next_u64(stream) >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+}
+
+/// Uniform float in `[min, max)`. Returns `None` for an empty or non-finite range.
+pub fn uniform_f64(stream: &mut StreamGenerator, min: f64, max: f64) -> Option<f64> {
+    if !min.is_finite() || !max.is_finite() || min >= max {
+        return None;
+    }
+    loop {
+        let value = min + (max - min) * uniform_unit_f64(stream);
+        // Rounding can land exactly on `max` for wide ranges.
+        if value < max {
+            return Some(value);
+        }
+    }
+}
+
+/// In-place Fisher–Yates shuffle.
+pub fn shuffle<T>(stream: &mut StreamGenerator, items: &mut [T]) {
+    for i in (1..items.len()).rev() {
+        let j = uniform_below(stream, i as u128 + 1) as usize;
+        items.swap(i, j);
+    }
+}
+
+/// Picks `count` distinct items without replacement, in the order they were drawn. Returns
+/// `None` if `count` is larger than the number of items.
+pub fn sample<T: Clone>(stream: &mut StreamGenerator, items: &[T], count: usize) -> Option<Vec<T>> {
+    if count > items.len() {
+        return None;
+    }
+    // The first `count` steps of a Fisher–Yates shuffle, run on indices.
+    let mut indices: Vec<usize> = (0..items.len()).collect();
+    for i in 0..count {
+        let j = i + uniform_below(stream, (items.len() - i) as u128) as usize;
+        indices.swap(i, j);
+    }
+    Some(indices[..count].iter().map(|&i| items[i].clone()).collect())
+}
+
+/// Splits pasted text into list items, one per line or comma, ignoring blanks.
+pub fn parse_list(text: &str) -> Vec<String> {
+    text.split(['\n', ','])
+        .map(|item| item.trim())
+        .filter(|item| !item.is_empty())
+        .map(|item| item.to_string())
+        .collect()
+}
+
+/// A roll in dice notation. `parse` only accepts rolls whose highest possible total,
+/// modifier included, fits in an `i64`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct DiceRoll {
+    pub count: u64,
+    pub sides: u64,
+    pub modifier: i64,
+}
+
+pub struct DiceResult {
+    pub rolls: Vec<u64>,
+    pub total: i64,
+}
+
+impl DiceRoll {
+    /// Parses standard dice notation: `[count]d<sides>[+|-modifier]`, for example `d20`,
+    /// `3d6+2` or `2d10-1`. `d%` is a d100.
+    pub fn parse(notation: &str) -> Result<DiceRoll, String> {
+        let notation: String = notation.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_lowercase();
+        let invalid = || format!("invalid dice notation: {:?}", notation);
+
+        let (count, rest) = notation.split_once('d').ok_or_else(invalid)?;
+        let count = if count.is_empty() { 1 } else { count.parse::<u64>().map_err(|_| invalid())? };
+
+        let (sides, modifier) = match rest.find(['+', '-']) {
+            Some(i) => (&rest[..i], rest[i..].parse::<i64>().map_err(|_| invalid())?),
+            None => (rest, 0),
+        };
+        let sides = if sides == "%" { 100 } else { sides.parse::<u64>().map_err(|_| invalid())? };
+
+        if count == 0 || count > MAX_DICE_COUNT || sides == 0 {
+            return Err(format!("dice notation needs 1 to {} dice with at least one side", MAX_DICE_COUNT));
+        }
+        if count as u128 * sides as u128 + modifier.max(0) as u128 > i64::MAX as u128 {
+            return Err(format!("{} can total more than {}", notation, i64::MAX));
+        }
+        Ok(DiceRoll { count, sides, modifier })
+    }
+
+    pub fn roll(&self, stream: &mut StreamGenerator) -> DiceResult {
+        let rolls: Vec<u64> = (0..self.count).map(|_| uniform_below(stream, self.sides as u128) as u64 + 1).collect();
+        let total = rolls.iter().map(|&roll| roll as i64).sum::<i64>() + self.modifier;
+        DiceResult { rolls, total }
+    }
+
+    /// Formats a result as `4 + 1 + 6 + 2 = 13`, or just the total for a single plain die.
+    pub fn describe(&self, result: &DiceResult) -> String {
+        if result.rolls.len() == 1 && self.modifier == 0 {
+            return result.total.to_string();
+        }
+        let mut description = result.rolls.iter().map(|roll| roll.to_string()).collect::<Vec<String>>().join(" + ");
+        if self.modifier > 0 {
+            description += &format!(" + {}", self.modifier);
+        } else if self.modifier < 0 {
+            description += &format!(" - {}", self.modifier.unsigned_abs());
+        }
+        format!("{} = {}", description, result.total)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_stream() -> StreamGenerator {
+        StreamGenerator::from_key(&[4; 32], &[8; 12])
+    }
+
+    #[test]
+    fn uniform_below_stays_in_bounds() {
+        let mut stream = test_stream();
+        assert_eq!(uniform_below(&mut stream, 1), 0);
+        for bound in [2, 3, 7, 1000, u64::MAX as u128 + 1, u128::MAX] {
+            for _ in 0..100 {
+                assert!(uniform_below(&mut stream, bound) < bound);
+            }
+        }
+
+        let mut seen = [false; 6];
+        for _ in 0..200 {
+            seen[uniform_below(&mut stream, 6) as usize] = true;
+        }
+        assert!(seen.iter().all(|&value| value));
+    }
+
+    #[test]
+    #[should_panic(expected = "non-zero bound")]
+    fn uniform_below_refuses_zero() {
+        uniform_below(&mut test_stream(), 0);
+    }
+
+    #[test]
+    fn uniform_int_covers_the_whole_range() {
+        let mut stream = test_stream();
+        assert_eq!(uniform_int(&mut stream, 5, 4), None);
+        assert_eq!(uniform_int(&mut stream, -3, -3), Some(-3));
+        assert_eq!(uniform_int(&mut stream, i64::MAX, i64::MAX), Some(i64::MAX));
+        for _ in 0..100 {
+            let value = uniform_int(&mut stream, -2, 2).unwrap();
+            assert!((-2..=2).contains(&value));
+        }
+        // The full range has a span of 2^64, one more than a u64 holds.
+        let values: Vec<i64> = (0..100).map(|_| uniform_int(&mut stream, i64::MIN, i64::MAX).unwrap()).collect();
+        assert!(values.iter().any(|&value| value < 0) && values.iter().any(|&value| value > 0));
+    }
+
+    #[test]
+    fn uniform_bigint_and_f64_stay_in_bounds() {
+        let mut stream = test_stream();
+        let min = BigInt::from(-1) << 200;
+        let max = BigInt::from(1) << 200;
+        for _ in 0..50 {
+            let value = uniform_bigint(&mut stream, &min, &max).unwrap();
+            assert!(value >= min && value <= max);
+        }
+        assert_eq!(uniform_bigint(&mut stream, &max, &min), None);
+
+        for _ in 0..100 {
+            let value = uniform_f64(&mut stream, -1.5, 2.5).unwrap();
+            assert!((-1.5..2.5).contains(&value));
+        }
+        assert_eq!(uniform_f64(&mut stream, 1.0, 1.0), None);
+        assert_eq!(uniform_f64(&mut stream, 0.0, f64::INFINITY), None);
+    }
+
+    #[test]
+    fn sample_draws_distinct_items() {
+        let mut stream = test_stream();
+        let items: Vec<u32> = (0..20).collect();
+        let mut drawn = sample(&mut stream, &items, 20).unwrap();
+        drawn.sort_unstable();
+        assert_eq!(drawn, items);
+
+        let drawn = sample(&mut stream, &items, 5).unwrap();
+        assert_eq!(drawn.len(), 5);
+        assert!(drawn.iter().all(|item| drawn.iter().filter(|other| *other == item).count() == 1));
+        assert_eq!(sample(&mut stream, &items, 0).unwrap(), Vec::<u32>::new());
+        assert_eq!(sample(&mut stream, &items, 21), None);
+    }
+
+    #[test]
+    fn shuffle_keeps_every_item() {
+        let mut items: Vec<u32> = (0..50).collect();
+        shuffle(&mut test_stream(), &mut items);
+        assert_ne!(items, (0..50).collect::<Vec<u32>>());
+        items.sort_unstable();
+        assert_eq!(items, (0..50).collect::<Vec<u32>>());
+        assert_eq!(parse_list(" a, b\n\nc ,, "), ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn parses_dice_notation() {
+        assert_eq!(DiceRoll::parse("d20"), Ok(DiceRoll { count: 1, sides: 20, modifier: 0 }));
+        assert_eq!(DiceRoll::parse("3D6 + 2"), Ok(DiceRoll { count: 3, sides: 6, modifier: 2 }));
+        assert_eq!(DiceRoll::parse("2d10-1"), Ok(DiceRoll { count: 2, sides: 10, modifier: -1 }));
+        assert_eq!(DiceRoll::parse("d%"), Ok(DiceRoll { count: 1, sides: 100, modifier: 0 }));
+        for notation in ["", "20", "d", "0d6", "1001d6", "2d0", "2dx", "2d6+", "2d6*3", "-1d6"] {
+            assert!(DiceRoll::parse(notation).is_err(), "accepted {:?}", notation);
+        }
+    }
+
+    #[test]
+    fn dice_totals_fit_in_an_i64() {
+        assert!(DiceRoll::parse("1000d18446744073709551615").is_err());
+        assert!(DiceRoll::parse("d9223372036854775807+1").is_err());
+        assert!(DiceRoll::parse("d9223372036854775806+1").is_ok());
+
+        // A negative modifier only lowers the highest total, and the lowest is a die above i64::MIN.
+        let dice = DiceRoll::parse("d9223372036854775807-9223372036854775808").unwrap();
+        let result = dice.roll(&mut test_stream());
+        assert_eq!(result.total, result.rolls[0] as i64 + i64::MIN);
+        assert!(DiceRoll::parse("d9223372036854775807-1").is_ok());
+
+        let dice = DiceRoll::parse("1000d9223372036854775").unwrap();
+        let result = dice.roll(&mut test_stream());
+        assert_eq!(result.rolls.len(), 1000);
+        assert_eq!(result.total, result.rolls.iter().map(|&roll| roll as i64).sum::<i64>());
+    }
+
+    #[test]
+    fn rolls_stay_in_range_and_describe_themselves() {
+        let dice = DiceRoll::parse("3d6-2").unwrap();
+        let mut stream = test_stream();
+        for _ in 0..100 {
+            let result = dice.roll(&mut stream);
+            assert!(result.rolls.iter().all(|roll| (1..=6).contains(roll)));
+            assert!((1..=16).contains(&result.total));
+        }
+
+        let result = DiceResult { rolls: vec![4, 1, 6], total: 9 };
+        assert_eq!(dice.describe(&result), "4 + 1 + 6 - 2 = 9");
+        assert_eq!(DiceRoll::parse("d20").unwrap().describe(&DiceResult { rolls: vec![17], total: 17 }), "17");
+    }
+}
\ No newline at end of file
@@ -1,4 +1,4 @@
-use egui::{Color32, Painter, Pos2, Rect};
+use egui::{Button, Color32, Painter, Pos2, Rect, Response, Ui, Vec2};
 
 pub fn draw_vertical_line(painter: &Painter, x: f32, y_start: f32, y_end: f32, width: f32, color: Color32) {
     let rect = painter.round_rect_to_pixels(Rect::from_min_max(Pos2::new(x, y_start), Pos2::new(x + width, y_end)));
@@ -15,4 +15,23 @@ pub fn draw_rect_stroke(painter: &Painter, rect: Rect, color: Color32) {
     draw_horizontal_line(painter, rect.min.x, rect.max.x, rect.max.y, 1.0, color);
     draw_vertical_line(painter, rect.min.x, rect.min.y, rect.max.y, 1.0, color);
     draw_vertical_line(painter, rect.max.x, rect.min.y, rect.max.y + 1.0, 1.0, color);
+}
+
+/// A 30px high button that is drawn green while `selected`, like the character set toggles.
+pub fn toggle_button(ui: &mut Ui, text: &str, width: f32, selected: bool) -> Response {
+    if selected {
+        ui.style_mut().visuals.widgets.inactive.weak_bg_fill = Color32::from_rgb(33, 63, 33);
+        ui.style_mut().visuals.widgets.hovered.weak_bg_fill = Color32::from_rgb(41, 79, 41);
+        ui.style_mut().visuals.widgets.active.weak_bg_fill = Color32::from_rgb(41, 79, 41);
+    }
+    let button = ui.add(Button::new(text).rounding(0.0).min_size(Vec2::new(width, 30.0)));
+    if selected {
+        draw_rect_stroke(ui.painter(), button.rect, Color32::from_rgb(54, 98, 54));
+        ui.style_mut().visuals.widgets.inactive.weak_bg_fill = Color32::from_rgb(35, 35, 38);
+        ui.style_mut().visuals.widgets.hovered.weak_bg_fill = Color32::from_rgb(42, 42, 46);
+        ui.style_mut().visuals.widgets.active.weak_bg_fill = Color32::from_rgb(50, 50, 55);
+    } else {
+        draw_rect_stroke(ui.painter(), button.rect, Color32::from_rgb(50, 50, 55));
+    }
+    button
 }
\ No newline at end of file
@@ -0,0 +1,10 @@
+pub mod analysis;
+pub mod config;
+pub mod crypto;
+pub mod encoding;
+pub mod entropy;
+pub mod generator;
+pub mod pool;
+pub mod rng;
+pub mod seed_file;
+pub mod self_test;
\ No newline at end of file
@@ -1,41 +1,120 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use std::sync::{Arc, Mutex};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use eframe::icon_data;
-use egui::{Button, Color32, FontId, Label, Margin, Pos2, Stroke, TextEdit, Vec2};
-use generator::GeneratePasswordParams;
+use egui::{Button, Color32, FontId, Label, Margin, Pos2, RichText, Stroke, TextEdit, Vec2, ViewportCommand};
+use pgen::config::{self, Config};
+use pgen::generator;
+use pgen::entropy::collector::{CollectionMode, MouseCollector};
+use pgen::entropy::dice::DiceEntropyMode;
+use pgen::entropy::health::HealthStatus;
+use pgen::generator::GeneratePasswordParams;
+use pgen::generator::identifier::{self, GenerateIdentifierParams, IdentifierKind, UlidGenerator};
+use pgen::generator::stream::StreamGenerator;
+use pgen::generator::token::{self, GenerateTokenParams, TokenEncoding};
+use pgen::generator::utilities::{self, DiceRoll};
+use pgen::pool::{EntropyGateMode, EntropyPool, OperatingMode};
+use pgen::seed_file::{self, SeedFileStatus};
+use pgen::self_test::{self, SelfTestStatus};
 
+mod cli;
 mod gui;
-mod crypto;
-mod entropy;
 mod ui_theme;
-mod generator;
+
+const WINDOW_HEIGHT: f32 = 222.0;
+const WINDOW_HEIGHT_WITH_VISUALIZER: f32 = 466.0;
+/// How often the GUI refreshes the seed file while running, on top of startup and exit.
+const SEED_FILE_SAVE_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum GeneratorMode {
+    Password,
+    Token,
+    Identifier,
+    Random,
+    Dice,
+    Entropy,
+}
+
+impl GeneratorMode {
+    const ALL: [GeneratorMode; 6] = [GeneratorMode::Password, GeneratorMode::Token, GeneratorMode::Identifier, GeneratorMode::Random, GeneratorMode::Dice, GeneratorMode::Entropy];
+
+    fn name(&self) -> &'static str {
+        match self {
+            GeneratorMode::Password   => "Password",
+            GeneratorMode::Token      => "Token",
+            GeneratorMode::Identifier => "Identifier",
+            GeneratorMode::Random     => "Random",
+            GeneratorMode::Dice       => "Dice",
+            GeneratorMode::Entropy    => "Entropy",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum RandomUtility {
+    Integer,
+    Float,
+    Dice,
+    Shuffle,
+    Sample,
+}
+
+impl RandomUtility {
+    const ALL: [RandomUtility; 5] = [RandomUtility::Integer, RandomUtility::Float, RandomUtility::Dice, RandomUtility::Shuffle, RandomUtility::Sample];
+
+    fn name(&self) -> &'static str {
+        match self {
+            RandomUtility::Integer => "Integer",
+            RandomUtility::Float   => "Float",
+            RandomUtility::Dice    => "Dice",
+            RandomUtility::Shuffle => "Shuffle",
+            RandomUtility::Sample  => "Sample",
+        }
+    }
+}
 
 pub struct PigeonInstance {
     should_init: bool,
-    last_used_entropy: Mutex<Vec<u8>>,
-    mouse_position_entropy: Arc<Mutex<Vec<u8>>>,
-    program_start_time: u128,
-    generated_streams_count: u32,
+    pool: Arc<EntropyPool>,
+    mode: GeneratorMode,
     password_size_str: String,
     password_size_str_last: String,
     password_params: GeneratePasswordParams,
     password_textedit: String,
+    token_params: GenerateTokenParams,
+    token_textedit: String,
+    identifier_params: GenerateIdentifierParams,
+    identifier_textedit: String,
+    ulid_generator: UlidGenerator,
+    random_utility: RandomUtility,
+    random_minimum_str: String,
+    random_maximum_str: String,
+    random_dice_str: String,
+    random_list_str: String,
+    random_sample_size_str: String,
+    random_textedit: String,
+    dice_input_str: String,
+    dice_status: String,
+    user_text_str: String,
+    user_entropy_status: String,
+    show_visualizer: bool,
+    mouse_collector: Option<MouseCollector>,
+    seed_file_path: Option<PathBuf>,
+    seed_file_status: String,
+    config_error: Option<String>,
+    last_seed_save: Instant,
 }
 
 impl Default for PigeonInstance {
     fn default() -> Self {
-        let mut default_mouse_position_entropy = Vec::new();
-        default_mouse_position_entropy.append(&mut crypto::hashes::whirlpool_512_compute(b"P-GEN DEFAULT MOUSE POSITION ENTROPY"));
-        default_mouse_position_entropy.append(&mut vec![0; 192]);
-        
         Self {
             should_init: true,
-            last_used_entropy: Mutex::new(Vec::new()),
-            mouse_position_entropy: Arc::from(Mutex::new(default_mouse_position_entropy)),
-            program_start_time: entropy::get_current_time_ns(),
-            generated_streams_count: 0,
+            pool: Arc::new(EntropyPool::default()),
+            mode: GeneratorMode::Password,
             password_size_str: "20".to_string(),
             password_size_str_last: "20".to_string(),
             password_params: GeneratePasswordParams {
@@ -50,254 +129,844 @@ impl Default for PigeonInstance {
                 use_maths_symbols: false,
                 use_brackets: false
             },
-            password_textedit: "Before generating a password, you should move your mouse around randomly to make your password more secure".to_string()
+            password_textedit: "Before generating a password, you should move your mouse around randomly to make your password more secure".to_string(),
+            token_params: GenerateTokenParams {
+                size: 32,
+                encoding: TokenEncoding::HexLower,
+                padding: false
+            },
+            token_textedit: "Before generating a token, you should move your mouse around randomly to make your token more secure".to_string(),
+            identifier_params: GenerateIdentifierParams::default(),
+            identifier_textedit: String::new(),
+            ulid_generator: UlidGenerator::new(true),
+            random_utility: RandomUtility::Integer,
+            random_minimum_str: "1".to_string(),
+            random_maximum_str: "100".to_string(),
+            random_dice_str: "3d6".to_string(),
+            random_list_str: String::new(),
+            random_sample_size_str: "1".to_string(),
+            random_textedit: String::new(),
+            dice_input_str: String::new(),
+            dice_status: String::new(),
+            user_text_str: String::new(),
+            user_entropy_status: String::new(),
+            show_visualizer: false,
+            mouse_collector: None,
+            seed_file_path: None,
+            seed_file_status: "No seed file".to_string(),
+            config_error: None,
+            last_seed_save: Instant::now()
         }
     }
 }
 
 impl PigeonInstance {
-    pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
-        let instance = PigeonInstance::default();
+    pub fn new(_cc: &eframe::CreationContext<'_>) -> Self {
+        let mut instance = PigeonInstance::default();
+        instance.pool.entropy_gate().mode = EntropyGateMode::Warn;
+        let config = match config::default_path() {
+            Some(path) => Config::load(&path),
+            None => Ok(Config::default()),
+        };
+        let config_mode = config.as_ref().map(|config| config.operating_mode).unwrap_or_default();
+        instance.config_error = config.err();
+        instance.pool.set_operating_mode(OperatingMode::from_environment().or(config_mode).unwrap_or_default());
 
-        let mp_entropy = instance.mouse_position_entropy.clone();
-        std::thread::spawn(move || {
-            entropy::mouse_position_entropy_updater_thread(mp_entropy);
-        });
+        // The seed file relies on algorithms that are not FIPS-approved.
+        if instance.pool.operating_mode() == OperatingMode::FipsApproved {
+            instance.seed_file_status = "No seed file in FIPS-approved mode".to_string();
+        } else {
+            instance.seed_file_path = seed_file::default_path();
+        }
+        if let Some(path) = &instance.seed_file_path {
+            instance.seed_file_status = match seed_file::load(&instance.pool, path) {
+                Ok(SeedFileStatus::Loaded) => format!("Seed file {} loaded", path.display()),
+                Ok(SeedFileStatus::Missing) => format!("Seed file {} created", path.display()),
+                Ok(SeedFileStatus::Corrupted(reason)) => format!("Seed file {} replaced: {}", path.display(), reason),
+                Err(error) => format!("Seed file {} could not be written: {}", path.display(), error),
+            };
+        }
+
+        instance.mouse_collector = Some(MouseCollector::spawn(instance.pool.clone()));
 
         return instance;
     }
-}
 
-impl eframe::App for PigeonInstance {
-    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
-        if self.should_init {
-            egui_extras::install_image_loaders(ctx);
-            ui_theme::load_theme(ctx);
+    fn password_mode_ui(&mut self, ui: &mut egui::Ui) {
+        ui.allocate_ui_at_rect(egui::Rect::from_two_pos(Pos2::new(20.0, 60.0), Pos2::new(800.0, 240.0)), |ui| {
+            let text_edit_resp = ui.add(TextEdit::singleline(&mut self.password_textedit).desired_width(654.0).margin(Margin::symmetric(6.0, 4.0)));
+            gui::draw_rect_stroke(ui.painter(), text_edit_resp.rect.shrink2(Vec2::new(1.0, 0.0)), Color32::from_rgb(35, 35, 38));
+            gui::draw_rect_stroke(ui.painter(), text_edit_resp.rect, Color32::from_rgb(54, 98, 54));
+        });
+        ui.allocate_ui_at_rect(egui::Rect::from_two_pos(Pos2::new(693.0, 60.0), Pos2::new(800.0, 240.0)), |ui| {
+            let regenerate_password_button = ui.add(Button::new("  🔄").rounding(0.0).min_size(Vec2::new(28.0, 25.0)));
+            gui::draw_rect_stroke(ui.painter(), regenerate_password_button.rect, Color32::from_rgb(54, 98, 54));
+            if regenerate_password_button.clicked() { self.password_textedit = generator::generate_password(&self.pool, &self.password_params).unwrap_or_else(|error| error.to_string()); }
+        });
+
+        ui.allocate_ui_at_rect(egui::Rect::from_two_pos(Pos2::new(20.0, 100.0), Pos2::new(744.0, 180.0)), |ui| {
+            ui.add(Label::new("Password Length").selectable(false));
+        });
+
+        ui.allocate_ui_at_rect(egui::Rect::from_two_pos(Pos2::new(120.0, 100.0), Pos2::new(744.0, 180.0)), |ui| {
+            let previous_fill = ui.style().visuals.widgets.inactive.bg_fill;
+            ui.style_mut().visuals.widgets.inactive.bg_fill = Color32::from_rgb(35, 35, 38); 
+            ui.add(egui::Slider::new(&mut self.password_params.size, 0..=100).show_value(false).handle_shape(egui::style::HandleShape::Rect { aspect_ratio: 1.0 }));
+            ui.style_mut().visuals.widgets.inactive.bg_fill = previous_fill;
+            ui.allocate_ui_at_rect(egui::Rect::from_two_pos(Pos2::new(685.0, 100.0), Pos2::new(744.0, 180.0)), |ui| {
+                ui.add(TextEdit::singleline(&mut self.password_size_str).char_limit(3).font(FontId::proportional(10.0)).desired_width(30.0));
+            });
+        });
+
+        let mut textedit_updated = self.password_size_str != self.password_size_str_last;
+        let mut reverted = false;
+        if textedit_updated {
+            if self.password_size_str.is_empty() {
+                self.password_size_str = self.password_size_str_last.clone();
+                textedit_updated = false;
+            } else {
+                for c in self.password_size_str.chars() {
+                    if !"0123456789".contains(c) {
+                        self.password_size_str = self.password_size_str_last.clone();
+                        reverted = true;
+                        break;
+                    }
+                }
+            }
+        }
+        self.password_size_str_last = self.password_size_str.clone();
+        if textedit_updated {
+            self.password_params.size = self.password_size_str.parse::<u32>().unwrap();
+            if !reverted {
+                self.password_textedit = generator::generate_password(&self.pool, &self.password_params).unwrap_or_else(|error| error.to_string());
+            }
+        } else {
+            if self.password_params.size != self.password_size_str.parse::<u32>().unwrap() {
+                self.password_size_str = self.password_params.size.to_string();
+                self.password_textedit = generator::generate_password(&self.pool, &self.password_params).unwrap_or_else(|error| error.to_string());
+            }
         }
 
-        let main_panel_frame = egui::containers::Frame {
-            fill: Color32::from_rgb(47, 47, 48),
-            ..Default::default()
-        };
-        
-        egui::TopBottomPanel::top("main_panel").exact_height(510.0)
-        .show_separator_line(false)
-        .resizable(false)
-        .frame(main_panel_frame).show(ctx, |ui| {
-            let visuals = ui.visuals_mut();
-            visuals.selection.stroke = Stroke::NONE;
-            visuals.extreme_bg_color = Color32::from_rgb(35, 35, 38);
-            ui.spacing_mut().slider_width = 560.0;
-            ui.allocate_ui_at_rect(egui::Rect::from_two_pos(Pos2::new(20.0, 20.0), Pos2::new(800.0, 200.0)), |ui| {
-                let text_edit_resp = ui.add(TextEdit::singleline(&mut self.password_textedit).desired_width(654.0).margin(Margin::symmetric(6.0, 4.0)));
-                gui::draw_rect_stroke(ui.painter(), text_edit_resp.rect.shrink2(Vec2::new(1.0, 0.0)), Color32::from_rgb(35, 35, 38));
-                gui::draw_rect_stroke(ui.painter(), text_edit_resp.rect, Color32::from_rgb(54, 98, 54));
+        ui.allocate_ui_at_rect(egui::Rect::from_two_pos(Pos2::new(20.0, 130.0), Pos2::new(744.0, 180.0)), |ui| {
+            ui.horizontal(|ui| {
+                let uppercase_chars_before = self.password_params.use_uppercase_chars;
+                let lowercase_chars_before = self.password_params.use_lowercase_chars;
+                let numbers_before = self.password_params.use_numbers;
+                let logograms_before = self.password_params.use_logograms;
+                let punctuation_before = self.password_params.use_punctuation;
+                let quotation_marks_before = self.password_params.use_quotation_marks;
+                let dashes_and_slashes_before = self.password_params.use_dashes_and_slashes;
+                let maths_symbols_before = self.password_params.use_maths_symbols;
+                let brackets_before = self.password_params.use_brackets;
+
+                if uppercase_chars_before {
+                    ui.style_mut().visuals.widgets.inactive.weak_bg_fill = Color32::from_rgb(33, 63, 33);
+                    ui.style_mut().visuals.widgets.hovered.weak_bg_fill = Color32::from_rgb(41, 79, 41);
+                    ui.style_mut().visuals.widgets.active.weak_bg_fill = Color32::from_rgb(41, 79, 41);
+                }
+                let uppercase_characters_button = ui.add(Button::new("A-Z").rounding(0.0).min_size(Vec2::new(60.0, 30.0)));
+                if uppercase_chars_before {
+                    gui::draw_rect_stroke(ui.painter(), uppercase_characters_button.rect, Color32::from_rgb(54, 98, 54));
+                    ui.style_mut().visuals.widgets.inactive.weak_bg_fill = Color32::from_rgb(35, 35, 38);
+                    ui.style_mut().visuals.widgets.hovered.weak_bg_fill = Color32::from_rgb(42, 42, 46);
+                    ui.style_mut().visuals.widgets.active.weak_bg_fill = Color32::from_rgb(50, 50, 55);
+                } else {
+                    gui::draw_rect_stroke(ui.painter(), uppercase_characters_button.rect, Color32::from_rgb(50, 50, 55));
+                }
+                if lowercase_chars_before {
+                    ui.style_mut().visuals.widgets.inactive.weak_bg_fill = Color32::from_rgb(33, 63, 33);
+                    ui.style_mut().visuals.widgets.hovered.weak_bg_fill = Color32::from_rgb(41, 79, 41);
+                    ui.style_mut().visuals.widgets.active.weak_bg_fill = Color32::from_rgb(41, 79, 41);
+                }
+                let lowercase_characters_button = ui.add(Button::new("a-z").rounding(0.0).min_size(Vec2::new(60.0, 30.0)));
+                if lowercase_chars_before {
+                    gui::draw_rect_stroke(ui.painter(), lowercase_characters_button.rect, Color32::from_rgb(54, 98, 54));
+                    ui.style_mut().visuals.widgets.inactive.weak_bg_fill = Color32::from_rgb(35, 35, 38);
+                    ui.style_mut().visuals.widgets.hovered.weak_bg_fill = Color32::from_rgb(42, 42, 46);
+                    ui.style_mut().visuals.widgets.active.weak_bg_fill = Color32::from_rgb(50, 50, 55);
+                } else {
+                    gui::draw_rect_stroke(ui.painter(), lowercase_characters_button.rect, Color32::from_rgb(50, 50, 55));
+                }
+                if numbers_before {
+                    ui.style_mut().visuals.widgets.inactive.weak_bg_fill = Color32::from_rgb(33, 63, 33);
+                    ui.style_mut().visuals.widgets.hovered.weak_bg_fill = Color32::from_rgb(41, 79, 41);
+                    ui.style_mut().visuals.widgets.active.weak_bg_fill = Color32::from_rgb(41, 79, 41);
+                }
+                let numbers_button = ui.add(Button::new("0-9").rounding(0.0).min_size(Vec2::new(60.0, 30.0)));
+                if numbers_before {
+                    gui::draw_rect_stroke(ui.painter(), numbers_button.rect, Color32::from_rgb(54, 98, 54));
+                    ui.style_mut().visuals.widgets.inactive.weak_bg_fill = Color32::from_rgb(35, 35, 38);
+                    ui.style_mut().visuals.widgets.hovered.weak_bg_fill = Color32::from_rgb(42, 42, 46);
+                    ui.style_mut().visuals.widgets.active.weak_bg_fill = Color32::from_rgb(50, 50, 55);
+                } else {
+                    gui::draw_rect_stroke(ui.painter(), numbers_button.rect, Color32::from_rgb(50, 50, 55));
+                }
+                if logograms_before {
+                    ui.style_mut().visuals.widgets.inactive.weak_bg_fill = Color32::from_rgb(33, 63, 33);
+                    ui.style_mut().visuals.widgets.hovered.weak_bg_fill = Color32::from_rgb(41, 79, 41);
+                    ui.style_mut().visuals.widgets.active.weak_bg_fill = Color32::from_rgb(41, 79, 41);
+                }
+                let logograms_button = ui.add(Button::new("# $ % & @ ^ ` ~").rounding(0.0).min_size(Vec2::new(120.0, 30.0)));
+                if logograms_before {
+                    gui::draw_rect_stroke(ui.painter(), logograms_button.rect, Color32::from_rgb(54, 98, 54));
+                    ui.style_mut().visuals.widgets.inactive.weak_bg_fill = Color32::from_rgb(35, 35, 38);
+                    ui.style_mut().visuals.widgets.hovered.weak_bg_fill = Color32::from_rgb(42, 42, 46);
+                    ui.style_mut().visuals.widgets.active.weak_bg_fill = Color32::from_rgb(50, 50, 55);
+                } else {
+                    gui::draw_rect_stroke(ui.painter(), logograms_button.rect, Color32::from_rgb(50, 50, 55));
+                }
+                if punctuation_before {
+                    ui.style_mut().visuals.widgets.inactive.weak_bg_fill = Color32::from_rgb(33, 63, 33);
+                    ui.style_mut().visuals.widgets.hovered.weak_bg_fill = Color32::from_rgb(41, 79, 41);
+                    ui.style_mut().visuals.widgets.active.weak_bg_fill = Color32::from_rgb(41, 79, 41);
+                }
+                let punctuation_button = ui.add(Button::new(". , : ;").rounding(0.0).min_size(Vec2::new(60.0, 30.0)));
+                if punctuation_before {
+                    gui::draw_rect_stroke(ui.painter(), punctuation_button.rect, Color32::from_rgb(54, 98, 54));
+                    ui.style_mut().visuals.widgets.inactive.weak_bg_fill = Color32::from_rgb(35, 35, 38);
+                    ui.style_mut().visuals.widgets.hovered.weak_bg_fill = Color32::from_rgb(42, 42, 46);
+                    ui.style_mut().visuals.widgets.active.weak_bg_fill = Color32::from_rgb(50, 50, 55);
+                } else {
+                    gui::draw_rect_stroke(ui.painter(), punctuation_button.rect, Color32::from_rgb(50, 50, 55));
+                }
+                if quotation_marks_before {
+                    ui.style_mut().visuals.widgets.inactive.weak_bg_fill = Color32::from_rgb(33, 63, 33);
+                    ui.style_mut().visuals.widgets.hovered.weak_bg_fill = Color32::from_rgb(41, 79, 41);
+                    ui.style_mut().visuals.widgets.active.weak_bg_fill = Color32::from_rgb(41, 79, 41);
+                }
+                let quotation_marks_button = ui.add(Button::new("\" '").rounding(0.0).min_size(Vec2::new(60.0, 30.0)));
+                if quotation_marks_before {
+                    gui::draw_rect_stroke(ui.painter(), quotation_marks_button.rect, Color32::from_rgb(54, 98, 54));
+                    ui.style_mut().visuals.widgets.inactive.weak_bg_fill = Color32::from_rgb(35, 35, 38);
+                    ui.style_mut().visuals.widgets.hovered.weak_bg_fill = Color32::from_rgb(42, 42, 46);
+                    ui.style_mut().visuals.widgets.active.weak_bg_fill = Color32::from_rgb(50, 50, 55);
+                } else {
+                    gui::draw_rect_stroke(ui.painter(), quotation_marks_button.rect, Color32::from_rgb(50, 50, 55));
+                }
+                if dashes_and_slashes_before {
+                    ui.style_mut().visuals.widgets.inactive.weak_bg_fill = Color32::from_rgb(33, 63, 33);
+                    ui.style_mut().visuals.widgets.hovered.weak_bg_fill = Color32::from_rgb(41, 79, 41);
+                    ui.style_mut().visuals.widgets.active.weak_bg_fill = Color32::from_rgb(41, 79, 41);
+                }
+                let dashes_and_slashes_button = ui.add(Button::new("\\ / | _ -").rounding(0.0).min_size(Vec2::new(60.0, 30.0)));
+                if dashes_and_slashes_before {
+                    gui::draw_rect_stroke(ui.painter(), dashes_and_slashes_button.rect, Color32::from_rgb(54, 98, 54));
+                    ui.style_mut().visuals.widgets.inactive.weak_bg_fill = Color32::from_rgb(35, 35, 38);
+                    ui.style_mut().visuals.widgets.hovered.weak_bg_fill = Color32::from_rgb(42, 42, 46);
+                    ui.style_mut().visuals.widgets.active.weak_bg_fill = Color32::from_rgb(50, 50, 55);
+                } else {
+                    gui::draw_rect_stroke(ui.painter(), dashes_and_slashes_button.rect, Color32::from_rgb(50, 50, 55));
+                }
+                if maths_symbols_before {
+                    ui.style_mut().visuals.widgets.inactive.weak_bg_fill = Color32::from_rgb(33, 63, 33);
+                    ui.style_mut().visuals.widgets.hovered.weak_bg_fill = Color32::from_rgb(41, 79, 41);
+                    ui.style_mut().visuals.widgets.active.weak_bg_fill = Color32::from_rgb(41, 79, 41);
+                }
+                let maths_symbols_button = ui.add(Button::new("< > * + ! ? =").rounding(0.0).min_size(Vec2::new(90.0, 30.0)));
+                if maths_symbols_before {
+                    gui::draw_rect_stroke(ui.painter(), maths_symbols_button.rect, Color32::from_rgb(54, 98, 54));
+                    ui.style_mut().visuals.widgets.inactive.weak_bg_fill = Color32::from_rgb(35, 35, 38);
+                    ui.style_mut().visuals.widgets.hovered.weak_bg_fill = Color32::from_rgb(42, 42, 46);
+                    ui.style_mut().visuals.widgets.active.weak_bg_fill = Color32::from_rgb(50, 50, 55);
+                } else {
+                    gui::draw_rect_stroke(ui.painter(), maths_symbols_button.rect, Color32::from_rgb(50, 50, 55));
+                }
+                if brackets_before {
+                    ui.style_mut().visuals.widgets.inactive.weak_bg_fill = Color32::from_rgb(33, 63, 33);
+                    ui.style_mut().visuals.widgets.hovered.weak_bg_fill = Color32::from_rgb(41, 79, 41);
+                    ui.style_mut().visuals.widgets.active.weak_bg_fill = Color32::from_rgb(41, 79, 41);
+                }
+                let use_brackets_button = ui.add(Button::new("( ) [ ] { }").rounding(0.0).min_size(Vec2::new(69.0, 30.0)));
+                if brackets_before {
+                    gui::draw_rect_stroke(ui.painter(), use_brackets_button.rect, Color32::from_rgb(54, 98, 54));
+                } else {
+                    gui::draw_rect_stroke(ui.painter(), use_brackets_button.rect, Color32::from_rgb(50, 50, 55));
+                }
+                if uppercase_characters_button.clicked() {self.password_params.use_uppercase_chars = !self.password_params.use_uppercase_chars; self.password_textedit = generator::generate_password(&self.pool, &self.password_params).unwrap_or_else(|error| error.to_string());};
+                if lowercase_characters_button.clicked() {self.password_params.use_lowercase_chars = !self.password_params.use_lowercase_chars; self.password_textedit = generator::generate_password(&self.pool, &self.password_params).unwrap_or_else(|error| error.to_string());};
+                if numbers_button.clicked() {self.password_params.use_numbers = !self.password_params.use_numbers; self.password_textedit = generator::generate_password(&self.pool, &self.password_params).unwrap_or_else(|error| error.to_string());};
+                if logograms_button.clicked() {self.password_params.use_logograms = !self.password_params.use_logograms; self.password_textedit = generator::generate_password(&self.pool, &self.password_params).unwrap_or_else(|error| error.to_string());};
+                if punctuation_button.clicked() {self.password_params.use_punctuation = !self.password_params.use_punctuation; self.password_textedit = generator::generate_password(&self.pool, &self.password_params).unwrap_or_else(|error| error.to_string());};
+                if quotation_marks_button.clicked() {self.password_params.use_quotation_marks = !self.password_params.use_quotation_marks; self.password_textedit = generator::generate_password(&self.pool, &self.password_params).unwrap_or_else(|error| error.to_string());};
+                if dashes_and_slashes_button.clicked() {self.password_params.use_dashes_and_slashes = !self.password_params.use_dashes_and_slashes; self.password_textedit = generator::generate_password(&self.pool, &self.password_params).unwrap_or_else(|error| error.to_string());};
+                if maths_symbols_button.clicked() {self.password_params.use_maths_symbols = !self.password_params.use_maths_symbols; self.password_textedit = generator::generate_password(&self.pool, &self.password_params).unwrap_or_else(|error| error.to_string());};
+                if use_brackets_button.clicked() {self.password_params.use_brackets = !self.password_params.use_brackets; self.password_textedit = generator::generate_password(&self.pool, &self.password_params).unwrap_or_else(|error| error.to_string());};
             });
-            ui.allocate_ui_at_rect(egui::Rect::from_two_pos(Pos2::new(693.0, 20.0), Pos2::new(800.0, 200.0)), |ui| {
-                let regenerate_password_button = ui.add(Button::new("  🔄").rounding(0.0).min_size(Vec2::new(28.0, 25.0)));
-                gui::draw_rect_stroke(ui.painter(), regenerate_password_button.rect, Color32::from_rgb(54, 98, 54));
-                if regenerate_password_button.clicked() { self.password_textedit = generator::generate_password(self, &self.password_params); self.generated_streams_count += 1; }
+        });
+    }
+
+    fn token_mode_ui(&mut self, ui: &mut egui::Ui) {
+        ui.allocate_ui_at_rect(egui::Rect::from_two_pos(Pos2::new(20.0, 60.0), Pos2::new(800.0, 240.0)), |ui| {
+            let text_edit_resp = ui.add(TextEdit::singleline(&mut self.token_textedit).desired_width(654.0).margin(Margin::symmetric(6.0, 4.0)));
+            gui::draw_rect_stroke(ui.painter(), text_edit_resp.rect.shrink2(Vec2::new(1.0, 0.0)), Color32::from_rgb(35, 35, 38));
+            gui::draw_rect_stroke(ui.painter(), text_edit_resp.rect, Color32::from_rgb(54, 98, 54));
+        });
+        ui.allocate_ui_at_rect(egui::Rect::from_two_pos(Pos2::new(693.0, 60.0), Pos2::new(800.0, 240.0)), |ui| {
+            let regenerate_token_button = ui.add(Button::new("  🔄").rounding(0.0).min_size(Vec2::new(28.0, 25.0)));
+            gui::draw_rect_stroke(ui.painter(), regenerate_token_button.rect, Color32::from_rgb(54, 98, 54));
+            if regenerate_token_button.clicked() { self.token_textedit = token::generate_token(&self.pool, &self.token_params).unwrap_or_else(|error| error.to_string()); }
+        });
+
+        ui.allocate_ui_at_rect(egui::Rect::from_two_pos(Pos2::new(20.0, 100.0), Pos2::new(744.0, 180.0)), |ui| {
+            ui.add(Label::new("Token Strength").selectable(false));
+        });
+
+        let token_size_before = self.token_params.size;
+        ui.allocate_ui_at_rect(egui::Rect::from_two_pos(Pos2::new(120.0, 100.0), Pos2::new(744.0, 180.0)), |ui| {
+            let previous_fill = ui.style().visuals.widgets.inactive.bg_fill;
+            ui.style_mut().visuals.widgets.inactive.bg_fill = Color32::from_rgb(35, 35, 38);
+            ui.add(egui::Slider::new(&mut self.token_params.size, 1..=128).show_value(false).handle_shape(egui::style::HandleShape::Rect { aspect_ratio: 1.0 }));
+            ui.style_mut().visuals.widgets.inactive.bg_fill = previous_fill;
+            ui.allocate_ui_at_rect(egui::Rect::from_two_pos(Pos2::new(685.0, 100.0), Pos2::new(744.0, 180.0)), |ui| {
+                ui.add(Label::new(format!("{} bits", self.token_params.size * 8)).selectable(false));
             });
+        });
 
-            ui.allocate_ui_at_rect(egui::Rect::from_two_pos(Pos2::new(20.0, 60.0), Pos2::new(744.0, 140.0)), |ui| {
-                ui.add(Label::new("Password Length").selectable(false));
+        let encoding_before = self.token_params.encoding;
+        let padding_before = self.token_params.padding;
+        ui.allocate_ui_at_rect(egui::Rect::from_two_pos(Pos2::new(20.0, 130.0), Pos2::new(744.0, 180.0)), |ui| {
+            ui.horizontal(|ui| {
+                for encoding in TokenEncoding::ALL {
+                    if gui::toggle_button(ui, encoding.name(), 70.0, encoding_before == encoding).clicked() {
+                        self.token_params.encoding = encoding;
+                    }
+                }
+                if encoding_before.supports_padding() && gui::toggle_button(ui, "padding", 70.0, padding_before).clicked() {
+                    self.token_params.padding = !self.token_params.padding;
+                }
             });
+        });
+
+        if self.token_params.size != token_size_before || self.token_params.encoding != encoding_before || self.token_params.padding != padding_before {
+            self.token_textedit = token::generate_token(&self.pool, &self.token_params).unwrap_or_else(|error| error.to_string());
+        }
+    }
+
+    fn identifier_mode_ui(&mut self, ui: &mut egui::Ui) {
+        ui.allocate_ui_at_rect(egui::Rect::from_two_pos(Pos2::new(20.0, 60.0), Pos2::new(800.0, 240.0)), |ui| {
+            let text_edit_resp = ui.add(TextEdit::singleline(&mut self.identifier_textedit).desired_width(654.0).margin(Margin::symmetric(6.0, 4.0)));
+            gui::draw_rect_stroke(ui.painter(), text_edit_resp.rect.shrink2(Vec2::new(1.0, 0.0)), Color32::from_rgb(35, 35, 38));
+            gui::draw_rect_stroke(ui.painter(), text_edit_resp.rect, Color32::from_rgb(54, 98, 54));
+        });
+        let mut regenerate = false;
+        ui.allocate_ui_at_rect(egui::Rect::from_two_pos(Pos2::new(693.0, 60.0), Pos2::new(800.0, 240.0)), |ui| {
+            let regenerate_identifier_button = ui.add(Button::new("  🔄").rounding(0.0).min_size(Vec2::new(28.0, 25.0)));
+            gui::draw_rect_stroke(ui.painter(), regenerate_identifier_button.rect, Color32::from_rgb(54, 98, 54));
+            regenerate = regenerate_identifier_button.clicked();
+        });
 
-            ui.allocate_ui_at_rect(egui::Rect::from_two_pos(Pos2::new(120.0, 60.0), Pos2::new(744.0, 140.0)), |ui| {
+        let nanoid_size_before = self.identifier_params.nanoid_size;
+        if self.identifier_params.kind == IdentifierKind::NanoId {
+            ui.allocate_ui_at_rect(egui::Rect::from_two_pos(Pos2::new(20.0, 100.0), Pos2::new(744.0, 180.0)), |ui| {
+                ui.add(Label::new("NanoID Length").selectable(false));
+            });
+            ui.allocate_ui_at_rect(egui::Rect::from_two_pos(Pos2::new(120.0, 100.0), Pos2::new(744.0, 180.0)), |ui| {
                 let previous_fill = ui.style().visuals.widgets.inactive.bg_fill;
-                ui.style_mut().visuals.widgets.inactive.bg_fill = Color32::from_rgb(35, 35, 38); 
-                ui.add(egui::Slider::new(&mut self.password_params.size, 0..=100).show_value(false).handle_shape(egui::style::HandleShape::Rect { aspect_ratio: 1.0 }));
+                ui.style_mut().visuals.widgets.inactive.bg_fill = Color32::from_rgb(35, 35, 38);
+                ui.add(egui::Slider::new(&mut self.identifier_params.nanoid_size, 2..=64).show_value(false).handle_shape(egui::style::HandleShape::Rect { aspect_ratio: 1.0 }));
                 ui.style_mut().visuals.widgets.inactive.bg_fill = previous_fill;
-                ui.allocate_ui_at_rect(egui::Rect::from_two_pos(Pos2::new(685.0, 60.0), Pos2::new(744.0, 140.0)), |ui| {
-                    ui.add(TextEdit::singleline(&mut self.password_size_str).char_limit(3).font(FontId::proportional(10.0)).desired_width(30.0));
+                ui.allocate_ui_at_rect(egui::Rect::from_two_pos(Pos2::new(685.0, 100.0), Pos2::new(744.0, 180.0)), |ui| {
+                    ui.add(Label::new(self.identifier_params.nanoid_size.to_string()).selectable(false));
                 });
             });
+        }
 
-            let mut textedit_updated = self.password_size_str != self.password_size_str_last;
-            let mut reverted = false;
-            if textedit_updated {
-                if self.password_size_str.len() == 0 {
-                    self.password_size_str = self.password_size_str_last.clone();
-                    textedit_updated = false;
-                } else {
-                    for c in self.password_size_str.chars() {
-                        if !"0123456789".contains(c) {
-                            self.password_size_str = self.password_size_str_last.clone();
-                            reverted = true;
-                            break;
-                        }
+        let kind_before = self.identifier_params.kind;
+        ui.allocate_ui_at_rect(egui::Rect::from_two_pos(Pos2::new(20.0, 130.0), Pos2::new(744.0, 180.0)), |ui| {
+            ui.horizontal(|ui| {
+                for (kind, label) in IdentifierKind::ALL.into_iter().zip(["UUIDv4", "UUIDv7", "ULID", "NanoID"]) {
+                    if gui::toggle_button(ui, label, 70.0, kind_before == kind).clicked() {
+                        self.identifier_params.kind = kind;
                     }
                 }
-            }
-            self.password_size_str_last = self.password_size_str.clone();
-            if textedit_updated {
-                self.password_params.size = self.password_size_str.parse::<u32>().unwrap();
-                if !reverted {
-                    self.password_textedit = generator::generate_password(self, &self.password_params); self.generated_streams_count += 1;
-                }
-            } else {
-                if self.password_params.size != self.password_size_str.parse::<u32>().unwrap() {
-                    self.password_size_str = self.password_params.size.to_string();
-                    self.password_textedit = generator::generate_password(self, &self.password_params); self.generated_streams_count += 1;
-                }
-            }
+            });
+        });
 
-            ui.allocate_ui_at_rect(egui::Rect::from_two_pos(Pos2::new(20.0, 90.0), Pos2::new(744.0, 140.0)), |ui| {
-                ui.horizontal(|ui| {
-                    let uppercase_chars_before = self.password_params.use_uppercase_chars.clone();
-                    let lowercase_chars_before = self.password_params.use_lowercase_chars.clone();
-                    let numbers_before = self.password_params.use_numbers.clone();
-                    let logograms_before = self.password_params.use_logograms.clone();
-                    let punctuation_before = self.password_params.use_punctuation.clone();
-                    let quotation_marks_before = self.password_params.use_quotation_marks.clone();
-                    let dashes_and_slashes_before = self.password_params.use_dashes_and_slashes.clone();
-                    let maths_symbols_before = self.password_params.use_maths_symbols.clone();
-                    let brackets_before = self.password_params.use_brackets.clone();
-    
-                    if uppercase_chars_before {
-                        ui.style_mut().visuals.widgets.inactive.weak_bg_fill = Color32::from_rgb(33, 63, 33);
-                        ui.style_mut().visuals.widgets.hovered.weak_bg_fill = Color32::from_rgb(41, 79, 41);
-                        ui.style_mut().visuals.widgets.active.weak_bg_fill = Color32::from_rgb(41, 79, 41);
-                    }
-                    let uppercase_characters_button = ui.add(Button::new("A-Z").rounding(0.0).min_size(Vec2::new(60.0, 30.0)));
-                    if uppercase_chars_before {
-                        gui::draw_rect_stroke(ui.painter(), uppercase_characters_button.rect, Color32::from_rgb(54, 98, 54));
-                        ui.style_mut().visuals.widgets.inactive.weak_bg_fill = Color32::from_rgb(35, 35, 38);
-                        ui.style_mut().visuals.widgets.hovered.weak_bg_fill = Color32::from_rgb(42, 42, 46);
-                        ui.style_mut().visuals.widgets.active.weak_bg_fill = Color32::from_rgb(50, 50, 55);
-                    } else {
-                        gui::draw_rect_stroke(ui.painter(), uppercase_characters_button.rect, Color32::from_rgb(50, 50, 55));
-                    }
-                    if lowercase_chars_before {
-                        ui.style_mut().visuals.widgets.inactive.weak_bg_fill = Color32::from_rgb(33, 63, 33);
-                        ui.style_mut().visuals.widgets.hovered.weak_bg_fill = Color32::from_rgb(41, 79, 41);
-                        ui.style_mut().visuals.widgets.active.weak_bg_fill = Color32::from_rgb(41, 79, 41);
-                    }
-                    let lowercase_characters_button = ui.add(Button::new("a-z").rounding(0.0).min_size(Vec2::new(60.0, 30.0)));
-                    if lowercase_chars_before {
-                        gui::draw_rect_stroke(ui.painter(), lowercase_characters_button.rect, Color32::from_rgb(54, 98, 54));
-                        ui.style_mut().visuals.widgets.inactive.weak_bg_fill = Color32::from_rgb(35, 35, 38);
-                        ui.style_mut().visuals.widgets.hovered.weak_bg_fill = Color32::from_rgb(42, 42, 46);
-                        ui.style_mut().visuals.widgets.active.weak_bg_fill = Color32::from_rgb(50, 50, 55);
-                    } else {
-                        gui::draw_rect_stroke(ui.painter(), lowercase_characters_button.rect, Color32::from_rgb(50, 50, 55));
-                    }
-                    if numbers_before {
-                        ui.style_mut().visuals.widgets.inactive.weak_bg_fill = Color32::from_rgb(33, 63, 33);
-                        ui.style_mut().visuals.widgets.hovered.weak_bg_fill = Color32::from_rgb(41, 79, 41);
-                        ui.style_mut().visuals.widgets.active.weak_bg_fill = Color32::from_rgb(41, 79, 41);
-                    }
-                    let numbers_button = ui.add(Button::new("0-9").rounding(0.0).min_size(Vec2::new(60.0, 30.0)));
-                    if numbers_before {
-                        gui::draw_rect_stroke(ui.painter(), numbers_button.rect, Color32::from_rgb(54, 98, 54));
-                        ui.style_mut().visuals.widgets.inactive.weak_bg_fill = Color32::from_rgb(35, 35, 38);
-                        ui.style_mut().visuals.widgets.hovered.weak_bg_fill = Color32::from_rgb(42, 42, 46);
-                        ui.style_mut().visuals.widgets.active.weak_bg_fill = Color32::from_rgb(50, 50, 55);
-                    } else {
-                        gui::draw_rect_stroke(ui.painter(), numbers_button.rect, Color32::from_rgb(50, 50, 55));
-                    }
-                    if logograms_before {
-                        ui.style_mut().visuals.widgets.inactive.weak_bg_fill = Color32::from_rgb(33, 63, 33);
-                        ui.style_mut().visuals.widgets.hovered.weak_bg_fill = Color32::from_rgb(41, 79, 41);
-                        ui.style_mut().visuals.widgets.active.weak_bg_fill = Color32::from_rgb(41, 79, 41);
-                    }
-                    let logograms_button = ui.add(Button::new("# $ % & @ ^ ` ~").rounding(0.0).min_size(Vec2::new(120.0, 30.0)));
-                    if logograms_before {
-                        gui::draw_rect_stroke(ui.painter(), logograms_button.rect, Color32::from_rgb(54, 98, 54));
-                        ui.style_mut().visuals.widgets.inactive.weak_bg_fill = Color32::from_rgb(35, 35, 38);
-                        ui.style_mut().visuals.widgets.hovered.weak_bg_fill = Color32::from_rgb(42, 42, 46);
-                        ui.style_mut().visuals.widgets.active.weak_bg_fill = Color32::from_rgb(50, 50, 55);
-                    } else {
-                        gui::draw_rect_stroke(ui.painter(), logograms_button.rect, Color32::from_rgb(50, 50, 55));
-                    }
-                    if punctuation_before {
-                        ui.style_mut().visuals.widgets.inactive.weak_bg_fill = Color32::from_rgb(33, 63, 33);
-                        ui.style_mut().visuals.widgets.hovered.weak_bg_fill = Color32::from_rgb(41, 79, 41);
-                        ui.style_mut().visuals.widgets.active.weak_bg_fill = Color32::from_rgb(41, 79, 41);
+        if regenerate || self.identifier_params.kind != kind_before || self.identifier_params.nanoid_size != nanoid_size_before {
+            self.identifier_textedit = match self.identifier_params.kind {
+                IdentifierKind::Ulid => self.ulid_generator.generate(&self.pool),
+                _ => identifier::generate_identifiers(&self.pool, &self.identifier_params, 1).map(|identifiers| identifiers.into_iter().next().unwrap_or_default()),
+            }.unwrap_or_else(|error| error.to_string());
+        }
+    }
+
+    fn random_mode_ui(&mut self, ui: &mut egui::Ui) {
+        ui.allocate_ui_at_rect(egui::Rect::from_two_pos(Pos2::new(20.0, 60.0), Pos2::new(800.0, 240.0)), |ui| {
+            let text_edit_resp = ui.add(TextEdit::singleline(&mut self.random_textedit).desired_width(654.0).margin(Margin::symmetric(6.0, 4.0)));
+            gui::draw_rect_stroke(ui.painter(), text_edit_resp.rect.shrink2(Vec2::new(1.0, 0.0)), Color32::from_rgb(35, 35, 38));
+            gui::draw_rect_stroke(ui.painter(), text_edit_resp.rect, Color32::from_rgb(54, 98, 54));
+        });
+        let mut regenerate = false;
+        ui.allocate_ui_at_rect(egui::Rect::from_two_pos(Pos2::new(693.0, 60.0), Pos2::new(800.0, 240.0)), |ui| {
+            let regenerate_random_button = ui.add(Button::new("  🔄").rounding(0.0).min_size(Vec2::new(28.0, 25.0)));
+            gui::draw_rect_stroke(ui.painter(), regenerate_random_button.rect, Color32::from_rgb(54, 98, 54));
+            regenerate = regenerate_random_button.clicked();
+        });
+
+        ui.allocate_ui_at_rect(egui::Rect::from_two_pos(Pos2::new(20.0, 100.0), Pos2::new(744.0, 180.0)), |ui| {
+            ui.horizontal(|ui| {
+                match self.random_utility {
+                    RandomUtility::Integer | RandomUtility::Float => {
+                        ui.add(Label::new("Minimum").selectable(false));
+                        ui.add(TextEdit::singleline(&mut self.random_minimum_str).desired_width(250.0));
+                        ui.add(Label::new("Maximum").selectable(false));
+                        ui.add(TextEdit::singleline(&mut self.random_maximum_str).desired_width(250.0));
                     }
-                    let punctuation_button = ui.add(Button::new(". , : ;").rounding(0.0).min_size(Vec2::new(60.0, 30.0)));
-                    if punctuation_before {
-                        gui::draw_rect_stroke(ui.painter(), punctuation_button.rect, Color32::from_rgb(54, 98, 54));
-                        ui.style_mut().visuals.widgets.inactive.weak_bg_fill = Color32::from_rgb(35, 35, 38);
-                        ui.style_mut().visuals.widgets.hovered.weak_bg_fill = Color32::from_rgb(42, 42, 46);
-                        ui.style_mut().visuals.widgets.active.weak_bg_fill = Color32::from_rgb(50, 50, 55);
-                    } else {
-                        gui::draw_rect_stroke(ui.painter(), punctuation_button.rect, Color32::from_rgb(50, 50, 55));
+                    RandomUtility::Dice => {
+                        ui.add(Label::new("Dice").selectable(false));
+                        ui.add(TextEdit::singleline(&mut self.random_dice_str).desired_width(120.0));
                     }
-                    if quotation_marks_before {
-                        ui.style_mut().visuals.widgets.inactive.weak_bg_fill = Color32::from_rgb(33, 63, 33);
-                        ui.style_mut().visuals.widgets.hovered.weak_bg_fill = Color32::from_rgb(41, 79, 41);
-                        ui.style_mut().visuals.widgets.active.weak_bg_fill = Color32::from_rgb(41, 79, 41);
+                    RandomUtility::Shuffle => {
+                        ui.add(Label::new("List").selectable(false));
+                        ui.add(TextEdit::singleline(&mut self.random_list_str).hint_text("Alice, Bob, Carol").desired_width(600.0));
                     }
-                    let quotation_marks_button = ui.add(Button::new("\" '").rounding(0.0).min_size(Vec2::new(60.0, 30.0)));
-                    if quotation_marks_before {
-                        gui::draw_rect_stroke(ui.painter(), quotation_marks_button.rect, Color32::from_rgb(54, 98, 54));
-                        ui.style_mut().visuals.widgets.inactive.weak_bg_fill = Color32::from_rgb(35, 35, 38);
-                        ui.style_mut().visuals.widgets.hovered.weak_bg_fill = Color32::from_rgb(42, 42, 46);
-                        ui.style_mut().visuals.widgets.active.weak_bg_fill = Color32::from_rgb(50, 50, 55);
-                    } else {
-                        gui::draw_rect_stroke(ui.painter(), quotation_marks_button.rect, Color32::from_rgb(50, 50, 55));
+                    RandomUtility::Sample => {
+                        ui.add(Label::new("List").selectable(false));
+                        ui.add(TextEdit::singleline(&mut self.random_list_str).hint_text("Alice, Bob, Carol").desired_width(500.0));
+                        ui.add(Label::new("Pick").selectable(false));
+                        ui.add(TextEdit::singleline(&mut self.random_sample_size_str).desired_width(40.0));
                     }
-                    if dashes_and_slashes_before {
-                        ui.style_mut().visuals.widgets.inactive.weak_bg_fill = Color32::from_rgb(33, 63, 33);
-                        ui.style_mut().visuals.widgets.hovered.weak_bg_fill = Color32::from_rgb(41, 79, 41);
-                        ui.style_mut().visuals.widgets.active.weak_bg_fill = Color32::from_rgb(41, 79, 41);
+                }
+            });
+        });
+
+        let utility_before = self.random_utility;
+        ui.allocate_ui_at_rect(egui::Rect::from_two_pos(Pos2::new(20.0, 130.0), Pos2::new(744.0, 180.0)), |ui| {
+            ui.horizontal(|ui| {
+                for utility in RandomUtility::ALL {
+                    if gui::toggle_button(ui, utility.name(), 70.0, utility_before == utility).clicked() {
+                        self.random_utility = utility;
                     }
-                    let dashes_and_slashes_button = ui.add(Button::new("\\ / | _ -").rounding(0.0).min_size(Vec2::new(60.0, 30.0)));
-                    if dashes_and_slashes_before {
-                        gui::draw_rect_stroke(ui.painter(), dashes_and_slashes_button.rect, Color32::from_rgb(54, 98, 54));
-                        ui.style_mut().visuals.widgets.inactive.weak_bg_fill = Color32::from_rgb(35, 35, 38);
-                        ui.style_mut().visuals.widgets.hovered.weak_bg_fill = Color32::from_rgb(42, 42, 46);
-                        ui.style_mut().visuals.widgets.active.weak_bg_fill = Color32::from_rgb(50, 50, 55);
-                    } else {
-                        gui::draw_rect_stroke(ui.painter(), dashes_and_slashes_button.rect, Color32::from_rgb(50, 50, 55));
+                }
+            });
+        });
+
+        if regenerate || self.random_utility != utility_before {
+            self.random_textedit = self.run_random_utility();
+        }
+    }
+
+    fn dice_mode_ui(&mut self, ui: &mut egui::Ui) {
+        let mut add_rolls = false;
+        ui.allocate_ui_at_rect(egui::Rect::from_two_pos(Pos2::new(20.0, 60.0), Pos2::new(800.0, 240.0)), |ui| {
+            let text_edit_resp = ui.add(TextEdit::singleline(&mut self.dice_input_str).hint_text("Type your rolls, e.g. 3 6 1 4 or HTTH").desired_width(654.0).margin(Margin::symmetric(6.0, 4.0)));
+            gui::draw_rect_stroke(ui.painter(), text_edit_resp.rect.shrink2(Vec2::new(1.0, 0.0)), Color32::from_rgb(35, 35, 38));
+            gui::draw_rect_stroke(ui.painter(), text_edit_resp.rect, Color32::from_rgb(54, 98, 54));
+            add_rolls = text_edit_resp.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter));
+        });
+        ui.allocate_ui_at_rect(egui::Rect::from_two_pos(Pos2::new(693.0, 60.0), Pos2::new(800.0, 240.0)), |ui| {
+            let add_rolls_button = ui.add(Button::new("  ➕").rounding(0.0).min_size(Vec2::new(28.0, 25.0)));
+            gui::draw_rect_stroke(ui.painter(), add_rolls_button.rect, Color32::from_rgb(54, 98, 54));
+            add_rolls |= add_rolls_button.clicked();
+        });
+
+        let mut dice_entropy = self.pool.dice_entropy();
+        if add_rolls {
+            let sides = dice_entropy.sides();
+            match dice_entropy.add_typed_rolls(sides, &self.dice_input_str) {
+                Ok(roll_count) => {
+                    self.dice_status = format!("Added {} rolls", roll_count);
+                    self.dice_input_str.clear();
+                }
+                Err(message) => self.dice_status = message,
+            }
+        }
+
+        ui.allocate_ui_at_rect(egui::Rect::from_two_pos(Pos2::new(20.0, 100.0), Pos2::new(744.0, 180.0)), |ui| {
+            ui.add(Label::new("Required Bits").selectable(false));
+        });
+        ui.allocate_ui_at_rect(egui::Rect::from_two_pos(Pos2::new(120.0, 100.0), Pos2::new(744.0, 180.0)), |ui| {
+            let previous_fill = ui.style().visuals.widgets.inactive.bg_fill;
+            ui.style_mut().visuals.widgets.inactive.bg_fill = Color32::from_rgb(35, 35, 38);
+            ui.add(egui::Slider::new(&mut dice_entropy.required_bits, 128..=512).step_by(8.0).show_value(false).handle_shape(egui::style::HandleShape::Rect { aspect_ratio: 1.0 }));
+            ui.style_mut().visuals.widgets.inactive.bg_fill = previous_fill;
+            ui.allocate_ui_at_rect(egui::Rect::from_two_pos(Pos2::new(685.0, 100.0), Pos2::new(744.0, 180.0)), |ui| {
+                ui.add(Label::new(format!("{}/{}", dice_entropy.available_bits().min(dice_entropy.required_bits), dice_entropy.required_bits)).selectable(false));
+            });
+        });
+
+        let sides_before = dice_entropy.sides();
+        let mode_before = dice_entropy.mode;
+        ui.allocate_ui_at_rect(egui::Rect::from_two_pos(Pos2::new(20.0, 130.0), Pos2::new(744.0, 180.0)), |ui| {
+            ui.horizontal(|ui| {
+                for (sides, label) in [(2, "Coin"), (4, "d4"), (6, "d6"), (8, "d8"), (10, "d10"), (12, "d12"), (20, "d20")] {
+                    if gui::toggle_button(ui, label, 45.0, sides_before == sides).clicked() {
+                        dice_entropy.set_sides(sides);
                     }
-                    if maths_symbols_before {
-                        ui.style_mut().visuals.widgets.inactive.weak_bg_fill = Color32::from_rgb(33, 63, 33);
-                        ui.style_mut().visuals.widgets.hovered.weak_bg_fill = Color32::from_rgb(41, 79, 41);
-                        ui.style_mut().visuals.widgets.active.weak_bg_fill = Color32::from_rgb(41, 79, 41);
+                }
+                ui.add_space(10.0);
+                for (mode, label) in [(DiceEntropyMode::Disabled, "Off"), (DiceEntropyMode::Mixed, "Mix"), (DiceEntropyMode::Exclusive, "Only")] {
+                    if gui::toggle_button(ui, label, 45.0, mode_before == mode).clicked() {
+                        dice_entropy.mode = mode;
                     }
-                    let maths_symbols_button = ui.add(Button::new("< > * + ! ? =").rounding(0.0).min_size(Vec2::new(90.0, 30.0)));
-                    if maths_symbols_before {
-                        gui::draw_rect_stroke(ui.painter(), maths_symbols_button.rect, Color32::from_rgb(54, 98, 54));
-                        ui.style_mut().visuals.widgets.inactive.weak_bg_fill = Color32::from_rgb(35, 35, 38);
-                        ui.style_mut().visuals.widgets.hovered.weak_bg_fill = Color32::from_rgb(42, 42, 46);
-                        ui.style_mut().visuals.widgets.active.weak_bg_fill = Color32::from_rgb(50, 50, 55);
-                    } else {
-                        gui::draw_rect_stroke(ui.painter(), maths_symbols_button.rect, Color32::from_rgb(50, 50, 55));
+                }
+                ui.add(Label::new(&self.dice_status).selectable(false));
+            });
+        });
+    }
+
+    fn entropy_mode_ui(&mut self, ui: &mut egui::Ui) {
+        let mut add_text = false;
+        ui.allocate_ui_at_rect(egui::Rect::from_two_pos(Pos2::new(20.0, 60.0), Pos2::new(800.0, 240.0)), |ui| {
+            let text_edit_resp = ui.add(TextEdit::singleline(&mut self.user_text_str).hint_text("Mash the keyboard or paste some text, then press Enter").desired_width(654.0).margin(Margin::symmetric(6.0, 4.0)));
+            gui::draw_rect_stroke(ui.painter(), text_edit_resp.rect.shrink2(Vec2::new(1.0, 0.0)), Color32::from_rgb(35, 35, 38));
+            gui::draw_rect_stroke(ui.painter(), text_edit_resp.rect, Color32::from_rgb(54, 98, 54));
+            if text_edit_resp.has_focus() {
+                let keys = ui.input(|input| input.events.iter().filter_map(|event| match event {
+                    egui::Event::Key { key, pressed: true, repeat: false, .. } => Some(key.name()),
+                    _ => None,
+                }).collect::<Vec<&str>>());
+                for key in keys {
+                    self.pool.add_user_keystroke(key);
+                }
+            }
+            add_text = text_edit_resp.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter));
+        });
+        ui.allocate_ui_at_rect(egui::Rect::from_two_pos(Pos2::new(693.0, 60.0), Pos2::new(800.0, 240.0)), |ui| {
+            let add_text_button = ui.add(Button::new("  ➕").rounding(0.0).min_size(Vec2::new(28.0, 25.0)));
+            gui::draw_rect_stroke(ui.painter(), add_text_button.rect, Color32::from_rgb(54, 98, 54));
+            add_text |= add_text_button.clicked();
+        });
+
+        if add_text && !self.user_text_str.is_empty() {
+            let credited_bits = self.pool.add_user_content(self.user_text_str.as_bytes());
+            self.user_entropy_status = format!("Mixed in {} characters, {:.0} bits credited", self.user_text_str.chars().count(), credited_bits);
+            self.user_text_str.clear();
+        }
+
+        ui.allocate_ui_at_rect(egui::Rect::from_two_pos(Pos2::new(20.0, 100.0), Pos2::new(744.0, 180.0)), |ui| {
+            ui.add(Label::new("Drop files such as photos or recordings anywhere on this window to mix them in as well.").selectable(false));
+        });
+
+        let mut user_entropy = self.pool.user_entropy();
+        ui.allocate_ui_at_rect(egui::Rect::from_two_pos(Pos2::new(20.0, 130.0), Pos2::new(744.0, 180.0)), |ui| {
+            ui.horizontal(|ui| {
+                let required = user_entropy.required;
+                if gui::toggle_button(ui, "Require", 60.0, required).on_hover_text("Refuse to generate until something new has been added").clicked() {
+                    user_entropy.required = !required;
+                }
+                ui.add_space(10.0);
+                ui.add(Label::new(&self.user_entropy_status).selectable(false));
+            });
+        });
+    }
+
+    /// Mixes in files dropped on the window, whichever tab is showing.
+    fn mix_in_dropped_files(&mut self, ctx: &egui::Context) {
+        let dropped_files = ctx.input(|input| input.raw.dropped_files.clone());
+        for dropped_file in dropped_files {
+            let content = match (&dropped_file.bytes, &dropped_file.path) {
+                (Some(bytes), _) => Ok(bytes.to_vec()),
+                (None, Some(path)) => std::fs::read(path),
+                (None, None) => continue,
+            };
+            let name = dropped_file.path.as_ref().and_then(|path| path.file_name()).map_or(dropped_file.name.clone(), |name| name.to_string_lossy().into_owned());
+            self.user_entropy_status = match content {
+                Ok(mut content) => {
+                    let credited_bits = self.pool.add_user_content(&content);
+                    content.fill(0);
+                    format!("Mixed in {}, {:.0} bits credited", name, credited_bits)
+                }
+                Err(error) => format!("Could not read {}: {}", name, error),
+            };
+            self.mode = GeneratorMode::Entropy;
+        }
+    }
+
+    /// Progress towards the entropy gate, with toggles to warn about or wait for it.
+    fn entropy_meter_ui(&mut self, ui: &mut egui::Ui) {
+        let gate = *self.pool.entropy_gate();
+        let gathered_bits = self.pool.gathered_entropy_bits();
+        let (text, fill) = match self.pool.last_generation_shortfall() {
+            Some(bits) if gate.mode == EntropyGateMode::Warn && gathered_bits < gate.threshold_bits => (
+                format!("Last one was generated with only {:.0} of {:.0} bits gathered; keep moving your mouse", bits, gate.threshold_bits),
+                Color32::from_rgb(120, 90, 40),
+            ),
+            _ => (format!("Entropy gathered: {:.0} / {:.0} bits", gathered_bits.min(gate.threshold_bits), gate.threshold_bits), Color32::from_rgb(33, 63, 33)),
+        };
+
+        ui.allocate_ui_at_rect(egui::Rect::from_two_pos(Pos2::new(20.0, 172.0), Pos2::new(744.0, 222.0)), |ui| {
+            let progress = (gathered_bits / gate.threshold_bits).min(1.0) as f32;
+            let entropy_bar = ui.add(egui::ProgressBar::new(progress).text(text).fill(fill).desired_width(494.0).desired_height(30.0).rounding(0.0));
+            gui::draw_rect_stroke(ui.painter(), entropy_bar.rect, Color32::from_rgb(50, 50, 55));
+            entropy_bar.on_hover_text(self.pool.entropy_credits().iter().map(|credit| {
+                format!("{}: {:.0} bits gathered, {:.0} since startup", credit.kind.name(), credit.pending_bits, credit.total_bits)
+            }).collect::<Vec<String>>().join("\n"));
+        });
+        ui.allocate_ui_at_rect(egui::Rect::from_two_pos(Pos2::new(522.0, 172.0), Pos2::new(744.0, 222.0)), |ui| {
+            ui.horizontal(|ui| {
+                if let Some(mouse_collector) = &self.mouse_collector {
+                    let collecting = mouse_collector.mode() == CollectionMode::Collecting;
+                    let collect_button = gui::toggle_button(ui, "Collect", 60.0, collecting)
+                        .on_hover_text("Keep sampling the cursor while P-GEN is in the background, until enough entropy is gathered");
+                    if collect_button.clicked() {
+                        if collecting { mouse_collector.resume() } else { mouse_collector.start() }
                     }
-                    if brackets_before {
-                        ui.style_mut().visuals.widgets.inactive.weak_bg_fill = Color32::from_rgb(33, 63, 33);
-                        ui.style_mut().visuals.widgets.hovered.weak_bg_fill = Color32::from_rgb(41, 79, 41);
-                        ui.style_mut().visuals.widgets.active.weak_bg_fill = Color32::from_rgb(41, 79, 41);
+                }
+                for (mode, label) in [(EntropyGateMode::Warn, "Warn"), (EntropyGateMode::Refuse, "Wait")] {
+                    if gui::toggle_button(ui, label, 60.0, gate.mode == mode).clicked() {
+                        self.pool.entropy_gate().mode = if gate.mode == mode { EntropyGateMode::Off } else { mode };
                     }
-                    let use_brackets_button = ui.add(Button::new("( ) [ ] { }").rounding(0.0).min_size(Vec2::new(69.0, 30.0)));
-                    if brackets_before {
-                        gui::draw_rect_stroke(ui.painter(), use_brackets_button.rect, Color32::from_rgb(54, 98, 54));
-                    } else {
-                        gui::draw_rect_stroke(ui.painter(), use_brackets_button.rect, Color32::from_rgb(50, 50, 55));
+                }
+            });
+        });
+    }
+
+    /// Button that opens the visualizer below the main controls by growing the window.
+    fn visualizer_toggle_ui(&mut self, ui: &mut egui::Ui) {
+        ui.allocate_ui_at_rect(egui::Rect::from_two_pos(Pos2::new(693.0, 20.0), Pos2::new(800.0, 240.0)), |ui| {
+            let visualizer_button = ui.add(Button::new(" 📊").rounding(0.0).min_size(Vec2::new(28.0, 30.0)))
+                .on_hover_text("Show what the entropy sources are doing");
+            gui::draw_rect_stroke(ui.painter(), visualizer_button.rect, Color32::from_rgb(54, 98, 54));
+            if visualizer_button.clicked() {
+                self.show_visualizer = !self.show_visualizer;
+                let window_size = Vec2::new(744.0, if self.show_visualizer { WINDOW_HEIGHT_WITH_VISUALIZER } else { WINDOW_HEIGHT });
+                ui.ctx().send_viewport_cmd(ViewportCommand::MaxInnerSize(window_size));
+                ui.ctx().send_viewport_cmd(ViewportCommand::MinInnerSize(window_size));
+                ui.ctx().send_viewport_cmd(ViewportCommand::InnerSize(window_size));
+            }
+        });
+    }
+
+    /// The cursor trail on the left; per-source health and credit and the pool fingerprint on
+    /// the right.
+    fn visualizer_ui(&mut self, ui: &mut egui::Ui) {
+        let mouse_position_entropy = self.pool.mouse_position_entropy();
+        let (trail, last_movement) = {
+            let mouse_position_entropy = mouse_position_entropy.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            (mouse_position_entropy.trail().iter().copied().collect::<Vec<(u16, u16)>>(), mouse_position_entropy.last_movement())
+        };
+
+        let canvas = egui::Rect::from_two_pos(Pos2::new(20.0, 232.0), Pos2::new(360.0, 446.0));
+        let painter = ui.painter_at(canvas);
+        painter.rect_filled(canvas, 0.0, Color32::from_rgb(35, 35, 38));
+        gui::draw_rect_stroke(ui.painter(), canvas, Color32::from_rgb(50, 50, 55));
+        if trail.is_empty() {
+            painter.text(canvas.center(), egui::Align2::CENTER_CENTER, "No cursor movement sampled yet", FontId::proportional(12.0), Color32::from_rgb(140, 140, 145));
+        } else {
+            // Fit the trail's bounding box into the canvas, keeping its aspect ratio.
+            let min_x = trail.iter().map(|position| position.0).min().unwrap_or(0) as f32;
+            let max_x = trail.iter().map(|position| position.0).max().unwrap_or(0) as f32;
+            let min_y = trail.iter().map(|position| position.1).min().unwrap_or(0) as f32;
+            let max_y = trail.iter().map(|position| position.1).max().unwrap_or(0) as f32;
+            let drawing_area = canvas.shrink(10.0);
+            let scale = (drawing_area.width() / (max_x - min_x).max(100.0)).min(drawing_area.height() / (max_y - min_y).max(100.0));
+            let origin = drawing_area.center() - Vec2::new((min_x + max_x) / 2.0, (min_y + max_y) / 2.0) * scale;
+            let to_canvas = |position: &(u16, u16)| origin + Vec2::new(position.0 as f32, position.1 as f32) * scale;
+
+            for (age, segment) in trail.windows(2).rev().enumerate() {
+                let alpha = 255 - (age * 255 / trail.len()) as u8;
+                painter.line_segment([to_canvas(&segment[0]), to_canvas(&segment[1])], Stroke::new(1.5, Color32::from_rgba_unmultiplied(84, 160, 84, alpha)));
+            }
+            painter.circle_filled(to_canvas(trail.last().unwrap()), 3.0, Color32::from_rgb(120, 200, 120));
+        }
+        let last_movement_text = match last_movement {
+            Some(instant) => format!("Cursor last moved {:.1} s ago", instant.elapsed().as_secs_f32()),
+            None => "The cursor has not moved".to_string(),
+        };
+        painter.text(canvas.left_bottom() + Vec2::new(6.0, -4.0), egui::Align2::LEFT_BOTTOM, last_movement_text, FontId::proportional(11.0), Color32::from_rgb(140, 140, 145));
+
+        let report = self.pool.health_report();
+        let credits = self.pool.entropy_credits();
+        ui.allocate_ui_at_rect(egui::Rect::from_two_pos(Pos2::new(380.0, 232.0), Pos2::new(724.0, 446.0)), |ui| {
+            egui::Grid::new("source_grid").num_columns(5).spacing(Vec2::new(14.0, 6.0)).show(ui, |ui| {
+                for heading in ["Source", "Status", "Samples", "Bits/sample", "Credited"] {
+                    ui.add(Label::new(RichText::new(heading).strong()).selectable(false));
+                }
+                ui.end_row();
+
+                for (source, credit) in report.iter().zip(&credits) {
+                    ui.add(Label::new(source.kind.name()).selectable(false));
+                    ui.add(Label::new(RichText::new(source.status.name()).color(health_status_color(source.status))).selectable(false))
+                        .on_hover_text(source.last_failure.as_deref().unwrap_or("No failures"));
+                    ui.add(Label::new(source.samples.to_string()).selectable(false));
+                    let entropy_text = match source.min_entropy_estimate {
+                        Some(estimate) => format!("{:.2} ({:.2} est.)", source.assessed_entropy, estimate),
+                        None => format!("{:.2}", source.assessed_entropy),
+                    };
+                    ui.add(Label::new(entropy_text).selectable(false));
+                    ui.add(Label::new(format!("{:.0} / {:.0}", credit.pending_bits, credit.total_bits)).selectable(false))
+                        .on_hover_text("Bits since the last generation / since startup");
+                    ui.end_row();
+                }
+            });
+            ui.add_space(12.0);
+            ui.add(Label::new(RichText::new(format!("Pool fingerprint  {}", self.pool.fingerprint())).monospace()).selectable(false))
+                .on_hover_text(format!("Changes whenever new entropy reaches the pool\n{}", self.seed_file_status));
+        });
+    }
+
+    /// Writes a fresh seed file, so entropy gathered this session carries over to the next.
+    fn save_seed_file(&mut self) {
+        self.last_seed_save = Instant::now();
+        if let Some(path) = &self.seed_file_path {
+            if let Err(error) = seed_file::save(&self.pool, path) {
+                self.seed_file_status = format!("Seed file {} could not be written: {}", path.display(), error);
+            }
+        }
+    }
+
+    /// One coloured mark per entropy source next to the tabs; hovering shows the details.
+    fn source_health_ui(&self, ui: &mut egui::Ui) {
+        let report = self.pool.health_report();
+        ui.allocate_ui_at_rect(egui::Rect::from_two_pos(Pos2::new(470.0, 26.0), Pos2::new(744.0, 180.0)), |ui| {
+            ui.horizontal(|ui| {
+                for source in &report {
+                    let mark = match source.status {
+                        HealthStatus::Healthy  => "✔",
+                        HealthStatus::Startup  => "…",
+                        HealthStatus::Degraded => "⚠",
+                    };
+                    ui.add(Label::new(RichText::new(format!("{} {}", source.kind.name(), mark)).color(health_status_color(source.status))).selectable(false))
+                        .on_hover_text(source.to_string());
+                }
+                let (mark, status, color) = match self_test::status() {
+                    SelfTestStatus::Failed { test, reason } => ("⚠", format!("The {} self-test failed ({}); generation is disabled", test, reason), health_status_color(HealthStatus::Degraded)),
+                    SelfTestStatus::Passed => ("✔", "Every known-answer test passed".to_string(), health_status_color(HealthStatus::Healthy)),
+                    SelfTestStatus::NotRun => ("…", "The known-answer tests have not run yet".to_string(), health_status_color(HealthStatus::Startup)),
+                };
+                if ui.add(Label::new(RichText::new(format!("Self-test {}", mark)).color(color)).selectable(false).sense(egui::Sense::click()))
+                    .on_hover_text(format!("{}. Click to run the tests again.", status))
+                    .clicked() {
+                    self_test::run();
+                }
+                if let Some(error) = &self.config_error {
+                    ui.add(Label::new(RichText::new("Config ⚠").color(health_status_color(HealthStatus::Degraded))).selectable(false))
+                        .on_hover_text(format!("The configuration file was ignored: {}", error));
+                }
+                let operating_mode = self.pool.operating_mode();
+                if operating_mode == OperatingMode::FipsApproved {
+                    ui.add(Label::new(RichText::new("FIPS").color(health_status_color(HealthStatus::Healthy))).selectable(false))
+                        .on_hover_text(format!("{} mode: {}. The cursor and your own text are not mixed in.", operating_mode.name(), operating_mode.algorithms()));
+                }
+            });
+        });
+    }
+
+    fn run_random_utility(&self) -> String {
+        let mut stream = match StreamGenerator::new(&self.pool, 0) {
+            Ok(stream) => stream,
+            Err(error) => return error.to_string(),
+        };
+        match self.random_utility {
+            RandomUtility::Integer => {
+                let (Ok(minimum), Ok(maximum)) = (self.random_minimum_str.trim().parse(), self.random_maximum_str.trim().parse()) else {
+                    return "Minimum and maximum must be whole numbers".to_string();
+                };
+                utilities::uniform_bigint(&mut stream, &minimum, &maximum).map(|value| value.to_string()).unwrap_or("The minimum is larger than the maximum".to_string())
+            }
+            RandomUtility::Float => {
+                let (Ok(minimum), Ok(maximum)) = (self.random_minimum_str.trim().parse(), self.random_maximum_str.trim().parse()) else {
+                    return "Minimum and maximum must be numbers".to_string();
+                };
+                utilities::uniform_f64(&mut stream, minimum, maximum).map(|value| value.to_string()).unwrap_or("The range is empty".to_string())
+            }
+            RandomUtility::Dice => match DiceRoll::parse(&self.random_dice_str) {
+                Ok(dice) => dice.describe(&dice.roll(&mut stream)),
+                Err(message) => message,
+            },
+            RandomUtility::Shuffle => {
+                let mut items = utilities::parse_list(&self.random_list_str);
+                utilities::shuffle(&mut stream, &mut items);
+                items.join(", ")
+            }
+            RandomUtility::Sample => {
+                let items = utilities::parse_list(&self.random_list_str);
+                let Ok(sample_size) = self.random_sample_size_str.trim().parse() else {
+                    return "The number of items to pick must be a whole number".to_string();
+                };
+                utilities::sample(&mut stream, &items, sample_size).map(|sample| sample.join(", ")).unwrap_or("The list has fewer items than that".to_string())
+            }
+        }
+    }
+}
+
+impl eframe::App for PigeonInstance {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        if self.should_init {
+            egui_extras::install_image_loaders(ctx);
+            ui_theme::load_theme(ctx);
+        }
+        // Keep the entropy meter moving while the cursor is outside the window.
+        ctx.request_repaint_after(Duration::from_millis(100));
+        if let Some(mouse_collector) = &self.mouse_collector {
+            mouse_collector.set_focused(ctx.input(|input| input.focused));
+            // The cursor is not mixed in under FIPS-approved mode, so there is nothing to poll for.
+            if self.pool.operating_mode() != OperatingMode::FipsApproved {
+                mouse_collector.request_bits(self.pool.entropy_gate().threshold_bits - self.pool.gathered_entropy_bits());
+            }
+        }
+        self.mix_in_dropped_files(ctx);
+        if self.last_seed_save.elapsed() >= SEED_FILE_SAVE_INTERVAL {
+            self.save_seed_file();
+        }
+
+        let main_panel_frame = egui::containers::Frame {
+            fill: Color32::from_rgb(47, 47, 48),
+            ..Default::default()
+        };
+        
+        egui::TopBottomPanel::top("main_panel").exact_height(510.0)
+        .show_separator_line(false)
+        .resizable(false)
+        .frame(main_panel_frame).show(ctx, |ui| {
+            let visuals = ui.visuals_mut();
+            visuals.selection.stroke = Stroke::NONE;
+            visuals.extreme_bg_color = Color32::from_rgb(35, 35, 38);
+            ui.spacing_mut().slider_width = 560.0;
+            ui.allocate_ui_at_rect(egui::Rect::from_two_pos(Pos2::new(20.0, 20.0), Pos2::new(744.0, 180.0)), |ui| {
+                ui.horizontal(|ui| {
+                    for mode in GeneratorMode::ALL {
+                        if gui::toggle_button(ui, mode.name(), 66.0, self.mode == mode).clicked() {
+                            self.mode = mode;
+                        }
                     }
-                    if uppercase_characters_button.clicked() {self.password_params.use_uppercase_chars = !self.password_params.use_uppercase_chars; self.password_textedit = generator::generate_password(self, &self.password_params); self.generated_streams_count += 1; self.generated_streams_count += 1;};
-                    if lowercase_characters_button.clicked() {self.password_params.use_lowercase_chars = !self.password_params.use_lowercase_chars; self.password_textedit = generator::generate_password(self, &self.password_params); self.generated_streams_count += 1};
-                    if numbers_button.clicked() {self.password_params.use_numbers = !self.password_params.use_numbers; self.password_textedit = generator::generate_password(self, &self.password_params); self.generated_streams_count += 1};
-                    if logograms_button.clicked() {self.password_params.use_logograms = !self.password_params.use_logograms; self.password_textedit = generator::generate_password(self, &self.password_params); self.generated_streams_count += 1};
-                    if punctuation_button.clicked() {self.password_params.use_punctuation = !self.password_params.use_punctuation; self.password_textedit = generator::generate_password(self, &self.password_params); self.generated_streams_count += 1};
-                    if quotation_marks_button.clicked() {self.password_params.use_quotation_marks = !self.password_params.use_quotation_marks; self.password_textedit = generator::generate_password(self, &self.password_params); self.generated_streams_count += 1};
-                    if dashes_and_slashes_button.clicked() {self.password_params.use_dashes_and_slashes = !self.password_params.use_dashes_and_slashes; self.password_textedit = generator::generate_password(self, &self.password_params); self.generated_streams_count += 1};
-                    if maths_symbols_button.clicked() {self.password_params.use_maths_symbols = !self.password_params.use_maths_symbols; self.password_textedit = generator::generate_password(self, &self.password_params); self.generated_streams_count += 1};
-                    if use_brackets_button.clicked() {self.password_params.use_brackets = !self.password_params.use_brackets; self.password_textedit = generator::generate_password(self, &self.password_params); self.generated_streams_count += 1};
                 });
             });
+
+            self.source_health_ui(ui);
+            self.visualizer_toggle_ui(ui);
+            self.entropy_meter_ui(ui);
+            if self.show_visualizer {
+                self.visualizer_ui(ui);
+            }
+
+            match self.mode {
+                GeneratorMode::Password => self.password_mode_ui(ui),
+                GeneratorMode::Token => self.token_mode_ui(ui),
+                GeneratorMode::Identifier => self.identifier_mode_ui(ui),
+                GeneratorMode::Random => self.random_mode_ui(ui),
+                GeneratorMode::Dice => self.dice_mode_ui(ui),
+                GeneratorMode::Entropy => self.entropy_mode_ui(ui),
+            }
         });
     }
+
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        self.save_seed_file();
+    }
+}
+
+fn health_status_color(status: HealthStatus) -> Color32 {
+    match status {
+        HealthStatus::Healthy  => Color32::from_rgb(84, 160, 84),
+        HealthStatus::Startup  => Color32::from_rgb(140, 140, 145),
+        HealthStatus::Degraded => Color32::from_rgb(210, 150, 50),
+    }
 }
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        std::process::exit(cli::run(&args));
+    }
+
     let native_options: eframe::NativeOptions = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
-            .with_inner_size([744.0, 140.0])
-            .with_max_inner_size([744.0, 140.0])
+            .with_inner_size([744.0, WINDOW_HEIGHT])
+            .with_max_inner_size([744.0, WINDOW_HEIGHT])
             .with_resizable(false)
+            .with_drag_and_drop(true)
             .with_maximize_button(false)
             .with_minimize_button(false)
             .with_icon(Arc::from(icon_data::from_png_bytes(include_bytes!("../P-GEN.png")).unwrap())),
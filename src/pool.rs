@@ -0,0 +1,520 @@
+use std::fmt;
+use std::sync::{atomic::{AtomicU32, Ordering}, Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use crate::entropy::{dice::{DiceEntropy, DiceEntropyMode}, jitter::JitterEntropy, system::SystemEntropy, user::UserEntropy, EntropySource, MousePositionEntropy, OsEntropySource};
+use crate::entropy::health::{EntropySourceKind, HealthTests, SourceHealth};
+use crate::{crypto, encoding, self_test};
+
+/// Why the pool refused to seed a stream.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GenerationError {
+    InsufficientDiceEntropy { available_bits: usize, required_bits: usize },
+    InsufficientEntropy { gathered_bits: usize, required_bits: usize },
+    MissingUserEntropy,
+    /// A known-answer or continuous self-test failed; see `self_test`.
+    SelfTestFailed { test: String, reason: String },
+    /// The feature relies on algorithms that are not FIPS-approved.
+    UnavailableInFipsMode { feature: &'static str },
+}
+
+impl fmt::Display for GenerationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GenerationError::InsufficientDiceEntropy { available_bits, required_bits } => {
+                write!(f, "Enter more dice rolls first: {} of {} bits collected", available_bits, required_bits)
+            }
+            GenerationError::InsufficientEntropy { gathered_bits, required_bits } => {
+                write!(f, "Gather more entropy first, by moving your mouse, entering dice rolls or waiting for system activity: {} of {} bits", gathered_bits, required_bits)
+            }
+            GenerationError::MissingUserEntropy => {
+                write!(f, "Add some entropy of your own first: drop a file, paste some text or mash the keyboard")
+            }
+            GenerationError::SelfTestFailed { test, reason } => {
+                write!(f, "Generation is disabled because the {} self-test failed ({}); run the self-tests again to re-enable it", test, reason)
+            }
+            GenerationError::UnavailableInFipsMode { feature } => {
+                write!(f, "Not available in FIPS-approved mode: {}", feature)
+            }
+        }
+    }
+}
+
+impl std::error::Error for GenerationError {}
+
+/// Which algorithms generation may use.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum OperatingMode {
+    #[default]
+    Standard,
+    /// Only FIPS-approved algorithms. Streams come from an SP 800-90A HMAC_DRBG with SHA-512,
+    /// instantiated from the OS RNG, with CPU jitter, kernel state and dice conditioned by
+    /// SHA-3 and SHA-512 as additional input. The cursor and user input, the chaining value
+    /// and everything else that goes through Whirlpool, BLAKE3, ChaCha20 or the joined digest
+    /// are left out, and features that can't do without them are refused.
+    FipsApproved,
+}
+
+impl OperatingMode {
+    /// Environment variable that turns FIPS-approved mode on when set to `1`, `true` or `on`,
+    /// and off when set to `0`, `false` or `off`.
+    pub const ENVIRONMENT_VARIABLE: &'static str = "PGEN_FIPS_MODE";
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            OperatingMode::Standard     => "standard",
+            OperatingMode::FipsApproved => "FIPS-approved",
+        }
+    }
+
+    /// The algorithms output is generated with, for reporting alongside it.
+    pub fn algorithms(&self) -> &'static str {
+        match self {
+            OperatingMode::Standard     => "ChaCha20 keyed through SHA-3, BLAKE3 and Whirlpool",
+            OperatingMode::FipsApproved => "HMAC_DRBG (SP 800-90A) with SHA-512, SHA-3 and SHA-512 conditioning",
+        }
+    }
+
+    /// The mode set through `ENVIRONMENT_VARIABLE`, or `None` if it is unset or neither on
+    /// nor off.
+    pub fn from_environment() -> Option<Self> {
+        Self::parse_switch(&std::env::var(Self::ENVIRONMENT_VARIABLE).ok()?)
+    }
+
+    /// Reads a FIPS mode switch: on is FIPS-approved and off is standard.
+    pub fn parse_switch(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "1" | "true" | "on"  => Some(OperatingMode::FipsApproved),
+            "0" | "false" | "off" => Some(OperatingMode::Standard),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EntropyGateMode {
+    Off,
+    /// Generate anyway, but remember the shortfall so it can be shown.
+    Warn,
+    /// Refuse to generate until the threshold is met.
+    Refuse,
+}
+
+/// What to do when too little entropy has been gathered. Only sources that gather ahead of
+/// time count (see `EntropySourceKind::is_gathered`), since the OS RNG and CPU jitter are
+/// always there on demand.
+#[derive(Clone, Copy, Debug)]
+pub struct EntropyGate {
+    pub mode: EntropyGateMode,
+    pub threshold_bits: f64,
+    /// Count from the last generation rather than from startup.
+    pub reset_on_generation: bool,
+}
+
+impl Default for EntropyGate {
+    fn default() -> Self {
+        Self {
+            mode: EntropyGateMode::Off,
+            threshold_bits: 256.0,
+            reset_on_generation: true,
+        }
+    }
+}
+
+/// Bits credited by one source since startup, and since the gate last started counting.
+#[derive(Clone, Copy, Debug)]
+pub struct EntropyCredit {
+    pub kind: EntropySourceKind,
+    pub total_bits: f64,
+    pub pending_bits: f64,
+}
+
+/// Bits of CPU jitter entropy credited to every stream when jitter collection is enabled.
+pub const JITTER_ENTROPY_BITS: usize = 256;
+/// The OS RNG is assessed at full entropy; its health tests only catch outright breakage.
+const OS_ASSESSED_ENTROPY: f64 = 8.0;
+/// Bytes drawn from the source and tested before the first stream.
+const OS_STARTUP_SAMPLES: u64 = 1024;
+
+/// State shared by every generator: the injected entropy source, the cursor buffer fed by
+/// the mouse thread, the optional CPU jitter and kernel state collectors and the chaining
+/// value left behind by the previous stream.
+pub struct EntropyPool {
+    source: Mutex<Box<dyn EntropySource>>,
+    pub(crate) last_used_entropy: Mutex<Vec<u8>>,
+    pub(crate) mouse_position_entropy: Arc<Mutex<MousePositionEntropy>>,
+    pub(crate) dice_entropy: Mutex<DiceEntropy>,
+    pub(crate) user_entropy: Mutex<UserEntropy>,
+    pub(crate) jitter_entropy: Mutex<Option<JitterEntropy>>,
+    pub(crate) system_entropy: Mutex<Option<SystemEntropy>>,
+    os_health: Mutex<HealthTests>,
+    entropy_gate: Mutex<EntropyGate>,
+    operating_mode: Mutex<OperatingMode>,
+    credit_baseline: Mutex<Vec<(EntropySourceKind, f64)>>,
+    last_generation_shortfall: Mutex<Option<f64>>,
+    /// Digest of the last block seen under each continuous test name.
+    continuous_tests: Mutex<Vec<(&'static str, [u8; 32])>>,
+    pub(crate) start_time: u128,
+    generated_streams_count: AtomicU32,
+}
+
+impl Default for EntropyPool {
+    fn default() -> Self {
+        Self::new(Box::new(OsEntropySource)).with_jitter_entropy().with_system_entropy()
+    }
+}
+
+impl EntropyPool {
+    pub fn new(mut source: Box<dyn EntropySource>) -> Self {
+        self_test::power_on();
+        let start_time = source.get_current_time_ns();
+
+        let mut os_health = HealthTests::new(OS_ASSESSED_ENTROPY, OS_STARTUP_SAMPLES);
+        for byte in source.generate_bytes(OS_STARTUP_SAMPLES as u32) {
+            os_health.test(byte as u64);
+        }
+
+        Self {
+            source: Mutex::new(source),
+            last_used_entropy: Mutex::new(Vec::new()),
+            mouse_position_entropy: Arc::new(Mutex::new(MousePositionEntropy::default())),
+            dice_entropy: Mutex::new(DiceEntropy::default()),
+            user_entropy: Mutex::new(UserEntropy::default()),
+            jitter_entropy: Mutex::new(None),
+            system_entropy: Mutex::new(None),
+            os_health: Mutex::new(os_health),
+            entropy_gate: Mutex::new(EntropyGate::default()),
+            operating_mode: Mutex::new(OperatingMode::default()),
+            credit_baseline: Mutex::new(Vec::new()),
+            last_generation_shortfall: Mutex::new(None),
+            continuous_tests: Mutex::new(Vec::new()),
+            start_time,
+            generated_streams_count: AtomicU32::new(0),
+        }
+    }
+
+    /// Mixes CPU jitter into every stream from now on. Pools built with `new` leave it out so
+    /// that a deterministic source stays reproducible. If the startup test fails, the pool
+    /// carries on without jitter and `health_report` says why.
+    pub fn with_jitter_entropy(self) -> Self {
+        *self.jitter_entropy() = Some(JitterEntropy::new());
+        self
+    }
+
+    /// Mixes a snapshot of the kernel's counters into every stream from now on, and lets
+    /// `collect_system_entropy` harvest them. Does nothing where there is no `/proc` to read.
+    pub fn with_system_entropy(self) -> Self {
+        *self.system_entropy() = SystemEntropy::new();
+        self
+    }
+
+    /// Health of every source feeding the pool: the OS RNG, the cursor, CPU jitter and
+    /// kernel state if enabled, and dice and user input if they are in use.
+    pub fn health_report(&self) -> Vec<SourceHealth> {
+        let mut report = vec![
+            self.os_health.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).report(EntropySourceKind::Os),
+            self.mouse_position_entropy.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).health().report(EntropySourceKind::Mouse),
+        ];
+        if let Some(jitter_entropy) = self.jitter_entropy().as_ref() {
+            report.push(jitter_entropy.health().report(EntropySourceKind::Jitter));
+        }
+        if let Some(system_entropy) = self.system_entropy().as_ref() {
+            report.push(system_entropy.health().report(EntropySourceKind::System));
+        }
+        let dice_entropy = self.dice_entropy();
+        if dice_entropy.mode != DiceEntropyMode::Disabled {
+            report.push(dice_entropy.health().report(EntropySourceKind::Dice));
+        }
+        drop(dice_entropy);
+        let user_entropy = self.user_entropy();
+        if user_entropy.is_used() {
+            report.push(user_entropy.health().report(EntropySourceKind::User));
+        }
+        report
+    }
+
+    pub fn operating_mode(&self) -> OperatingMode {
+        *self.operating_mode.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn set_operating_mode(&self, operating_mode: OperatingMode) {
+        *self.operating_mode.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = operating_mode;
+    }
+
+    /// Fails in FIPS-approved mode, for features built on algorithms that are not approved.
+    pub(crate) fn require_standard_mode(&self, feature: &'static str) -> Result<(), GenerationError> {
+        match self.operating_mode() {
+            OperatingMode::Standard     => Ok(()),
+            OperatingMode::FipsApproved => Err(GenerationError::UnavailableInFipsMode { feature }),
+        }
+    }
+
+    pub fn entropy_gate(&self) -> MutexGuard<'_, EntropyGate> {
+        self.entropy_gate.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// A short digest of the pool's current state: the cursor buffer and the chaining value
+    /// left by the last stream. It changes as entropy comes in, so it can be shown to prove
+    /// that something is happening, but is domain separated so it reveals nothing usable.
+    pub fn fingerprint(&self) -> String {
+        let mut fingerprint_input = b"P-GEN POOL FINGERPRINT".to_vec();
+        fingerprint_input.extend_from_slice(&self.mouse_position_entropy.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).data);
+        fingerprint_input.extend_from_slice(&self.last_used_entropy.lock().unwrap_or_else(|poisoned| poisoned.into_inner()));
+        let fingerprint = crypto::hashes::blake3_256_compute(&fingerprint_input);
+        fingerprint_input.fill(0);
+        encoding::hex_encode(&fingerprint[..4], false)
+    }
+
+    /// Mixes a seed saved by an earlier run into the pool, without crediting it.
+    pub fn mix_seed(&self, seed: &[u8]) {
+        let mut material = b"P-GEN SEED FILE".to_vec();
+        material.extend_from_slice(seed);
+        self.mouse_position_entropy.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).mix_in(&material);
+        material.fill(0);
+    }
+
+    /// Derives a seed for the next run from the pool's state and fresh source bytes. The
+    /// derivation is one-way and domain separated from stream seeding.
+    pub(crate) fn derive_seed(&self) -> Vec<u8> {
+        let mut seed_input = b"P-GEN SEED FILE DERIVATION".to_vec();
+        seed_input.extend_from_slice(&self.mouse_position_entropy.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).data);
+        seed_input.extend_from_slice(&self.last_used_entropy.lock().unwrap_or_else(|poisoned| poisoned.into_inner()));
+        let mut source = self.source();
+        seed_input.extend_from_slice(&source.get_current_time_ns().to_be_bytes());
+        seed_input.append(&mut source.generate_bytes(64));
+        let seed = crypto::hashes::whirlpool_512_compute(&seed_input);
+        seed_input.fill(0);
+        seed
+    }
+
+    /// Bits credited by each source, in the same order as `health_report`.
+    pub fn entropy_credits(&self) -> Vec<EntropyCredit> {
+        let baseline = self.credit_baseline.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        self.health_report().into_iter().map(|source| {
+            let baseline_bits = baseline.iter().find(|(kind, _)| *kind == source.kind).map_or(0.0, |(_, bits)| *bits);
+            EntropyCredit {
+                kind: source.kind,
+                total_bits: source.credited_bits,
+                pending_bits: source.credited_bits - baseline_bits,
+            }
+        }).collect()
+    }
+
+    /// Bits gathered towards the entropy gate, from the sources the operating mode uses.
+    pub fn gathered_entropy_bits(&self) -> f64 {
+        let fips_mode = self.operating_mode() == OperatingMode::FipsApproved;
+        self.entropy_credits().iter()
+            .filter(|credit| credit.kind.is_gathered() && (!fips_mode || credit.kind.is_used_in_fips_mode()))
+            .map(|credit| credit.pending_bits)
+            .sum()
+    }
+
+    /// The bits gathered when the last stream was generated below the threshold in `Warn`
+    /// mode, or `None` if it met the threshold.
+    pub fn last_generation_shortfall(&self) -> Option<f64> {
+        *self.last_generation_shortfall.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub(crate) fn check_entropy_gate(&self) -> Result<(), GenerationError> {
+        let gate = *self.entropy_gate();
+        let gathered_bits = match gate.mode {
+            EntropyGateMode::Off => None,
+            _ => Some(self.gathered_entropy_bits()).filter(|&bits| bits < gate.threshold_bits),
+        };
+        if let (EntropyGateMode::Refuse, Some(bits)) = (gate.mode, gathered_bits) {
+            return Err(GenerationError::InsufficientEntropy { gathered_bits: bits as usize, required_bits: gate.threshold_bits as usize });
+        }
+        *self.last_generation_shortfall.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = gathered_bits;
+        Ok(())
+    }
+
+    /// Starts counting towards the gate again after a generation, if the gate says so.
+    pub(crate) fn reset_entropy_credits(&self) {
+        if !self.entropy_gate().reset_on_generation {
+            return;
+        }
+        let credits = self.health_report().into_iter().map(|source| (source.kind, source.credited_bits)).collect();
+        *self.credit_baseline.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = credits;
+    }
+
+    pub fn source(&self) -> MutexGuard<'_, Box<dyn EntropySource>> {
+        self.source.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn dice_entropy(&self) -> MutexGuard<'_, DiceEntropy> {
+        self.dice_entropy.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn user_entropy(&self) -> MutexGuard<'_, UserEntropy> {
+        self.user_entropy.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub(crate) fn jitter_entropy(&self) -> MutexGuard<'_, Option<JitterEntropy>> {
+        self.jitter_entropy.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub(crate) fn system_entropy(&self) -> MutexGuard<'_, Option<SystemEntropy>> {
+        self.system_entropy.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn mouse_position_entropy(&self) -> Arc<Mutex<MousePositionEntropy>> {
+        self.mouse_position_entropy.clone()
+    }
+
+    /// Pulls up to `max_samples` cursor samples out of the source and mixes them in. The
+    /// mouse collector calls it one sample at a time while it polls; calling it directly makes
+    /// a replayed trace land in the pool at a well-defined point. Returns the number of
+    /// samples read.
+    pub fn collect_mouse_position_entropy(&self, max_samples: usize) -> usize {
+        let mut source = self.source();
+        let mut mouse_position_entropy = self.mouse_position_entropy.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        let mut collected_samples = 0;
+        while collected_samples < max_samples {
+            let Some(position) = source.get_cursor_position() else { break };
+            mouse_position_entropy.feed(position);
+            collected_samples += 1;
+        }
+        collected_samples
+    }
+
+    /// Mixes a file or text supplied by the user into the pool. Returns the bits credited,
+    /// which is far less than the content may hold; see `entropy::user`.
+    pub fn add_user_content(&self, content: &[u8]) -> f64 {
+        let (mut material, credited_bits) = self.user_entropy().add_content(content);
+        self.mouse_position_entropy.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).mix_in(&material);
+        material.fill(0);
+        credited_bits
+    }
+
+    /// Mixes a key press and its timing into the pool. Returns the bits credited.
+    pub fn add_user_keystroke(&self, key: &str) -> f64 {
+        let time_ns = self.source().get_current_time_ns();
+        let (mut material, credited_bits) = self.user_entropy().add_keystroke(key, time_ns);
+        self.mouse_position_entropy.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).mix_in(&material);
+        material.fill(0);
+        credited_bits
+    }
+
+    /// Harvests kernel state until `bits` bits have been credited or `timeout` has passed.
+    /// This is the gathered source on headless hosts. Returns the bits credited, which is
+    /// always 0 if system entropy is not enabled.
+    pub fn collect_system_entropy(&self, bits: f64, timeout: Duration) -> f64 {
+        self.system_entropy().as_mut().map_or(0.0, |system_entropy| system_entropy.harvest(bits, timeout))
+    }
+
+    /// Runs bytes drawn from the source through the OS RNG health tests.
+    pub(crate) fn test_os_bytes(&self, bytes: &[u8]) {
+        let mut os_health = self.os_health.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        for &byte in bytes {
+            if os_health.test(byte as u64) {
+                let credited_entropy = os_health.credited_entropy();
+                os_health.credit(credited_entropy);
+            }
+        }
+    }
+
+    /// Continuous output test: fails, and puts the library in the self-test error state, if
+    /// `block` is the same as the previous block tested under `name`.
+    pub(crate) fn continuous_test(&self, name: &'static str, block: &[u8]) -> Result<(), GenerationError> {
+        let digest: [u8; 32] = crypto::hashes::sha3_256_compute(block).try_into().unwrap();
+        let mut continuous_tests = self.continuous_tests.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        match continuous_tests.iter_mut().find(|(test_name, _)| *test_name == name) {
+            Some((_, last_digest)) if *last_digest == digest => {
+                let reason = "the same block came out twice in a row".to_string();
+                self_test::report_failure(name, reason.clone());
+                return Err(GenerationError::SelfTestFailed { test: name.to_string(), reason });
+            }
+            Some((_, last_digest)) => *last_digest = digest,
+            None => continuous_tests.push((name, digest)),
+        }
+        Ok(())
+    }
+
+    /// Returns the number of streams generated so far and counts the one about to be made.
+    pub(crate) fn next_stream_index(&self) -> u32 {
+        self.generated_streams_count.fetch_add(1, Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entropy::deterministic::seeded_pool;
+    use crate::generator;
+
+    /// Credits `bits` bits of dice entropy through von Neumann pairs.
+    fn add_coin_bits(pool: &EntropyPool, bits: usize) {
+        let mut dice_entropy = pool.dice_entropy();
+        dice_entropy.mode = DiceEntropyMode::Mixed;
+        dice_entropy.required_bits = 0;
+        dice_entropy.add_typed_rolls(2, &"HTTH".repeat(bits / 2)).unwrap();
+    }
+
+    #[test]
+    fn refusing_gate_waits_for_gathered_entropy() {
+        let pool = seeded_pool(b"pool");
+        *pool.entropy_gate() = EntropyGate { mode: EntropyGateMode::Refuse, threshold_bits: 8.0, reset_on_generation: true };
+        assert_eq!(generator::generate_stream(&pool, 16), Err(GenerationError::InsufficientEntropy { gathered_bits: 0, required_bits: 8 }));
+
+        add_coin_bits(&pool, 8);
+        assert_eq!(pool.gathered_entropy_bits(), 8.0);
+        generator::generate_stream(&pool, 16).unwrap();
+
+        // The credit was used up by that generation.
+        assert_eq!(pool.gathered_entropy_bits(), 0.0);
+        assert!(generator::generate_stream(&pool, 16).is_err());
+        let dice_credit = pool.entropy_credits().into_iter().find(|credit| credit.kind == EntropySourceKind::Dice).unwrap();
+        assert_eq!((dice_credit.total_bits, dice_credit.pending_bits), (8.0, 0.0));
+    }
+
+    #[test]
+    fn gate_can_count_from_startup() {
+        let pool = seeded_pool(b"pool");
+        *pool.entropy_gate() = EntropyGate { mode: EntropyGateMode::Refuse, threshold_bits: 8.0, reset_on_generation: false };
+        add_coin_bits(&pool, 8);
+        generator::generate_stream(&pool, 16).unwrap();
+        generator::generate_stream(&pool, 16).unwrap();
+        assert_eq!(pool.gathered_entropy_bits(), 8.0);
+    }
+
+    #[test]
+    fn warning_gate_records_the_shortfall() {
+        let pool = seeded_pool(b"pool");
+        *pool.entropy_gate() = EntropyGate { mode: EntropyGateMode::Warn, threshold_bits: 8.0, reset_on_generation: true };
+        add_coin_bits(&pool, 4);
+        generator::generate_stream(&pool, 16).unwrap();
+        assert_eq!(pool.last_generation_shortfall(), Some(4.0));
+
+        add_coin_bits(&pool, 8);
+        generator::generate_stream(&pool, 16).unwrap();
+        assert_eq!(pool.last_generation_shortfall(), None);
+    }
+
+    #[test]
+    fn fips_mode_leaves_user_input_out_of_the_gate() {
+        let pool = seeded_pool(b"pool");
+        let credited_bits = pool.add_user_content(b"The quick brown fox jumps over the lazy dog, twice over and then some more.");
+        assert!(credited_bits > 0.0);
+        assert_eq!(pool.gathered_entropy_bits(), credited_bits);
+
+        pool.set_operating_mode(OperatingMode::FipsApproved);
+        assert_eq!(pool.gathered_entropy_bits(), 0.0);
+        assert!(pool.require_standard_mode("test").is_err());
+    }
+
+    #[test]
+    fn fingerprint_follows_the_pool_state() {
+        let pool = seeded_pool(b"pool");
+        let fingerprint = pool.fingerprint();
+        assert_eq!(fingerprint.len(), 8);
+        assert_eq!(pool.fingerprint(), fingerprint);
+        assert_eq!(seeded_pool(b"pool").fingerprint(), fingerprint);
+
+        pool.mix_seed(b"saved seed");
+        let mixed = pool.fingerprint();
+        assert_ne!(mixed, fingerprint);
+        generator::generate_stream(&pool, 16).unwrap();
+        assert_ne!(pool.fingerprint(), mixed);
+    }
+}
\ No newline at end of file
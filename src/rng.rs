@@ -0,0 +1,105 @@
+use rand::{CryptoRng, Error, RngCore, SeedableRng};
+
+use crate::generator::stream::StreamGenerator;
+use crate::pool::{EntropyPool, GenerationError};
+
+/// A `rand` compatible handle on the P-GEN generator, so anything that takes an `RngCore`
+/// (`shuffle`, `choose`, `Uniform`, ...) can draw from the pool.
+///
+/// `PgenRng::new` seeds a fresh stream from the pool, which mixes in the cursor buffer, the
+/// chaining value of the previous stream and the pool's entropy source. `from_seed` instead
+/// gives a reproducible stream for a fixed 32-byte seed and never touches a pool.
+pub struct PgenRng {
+    stream: StreamGenerator,
+}
+
+impl PgenRng {
+    pub fn new(pool: &EntropyPool) -> Result<Self, GenerationError> {
+        Ok(Self {
+            stream: StreamGenerator::new(pool, 0)?,
+        })
+    }
+}
+
+impl RngCore for PgenRng {
+    fn next_u32(&mut self) -> u32 {
+        let mut bytes = [0u8; 4];
+        self.stream.fill_bytes(&mut bytes);
+        u32::from_le_bytes(bytes)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut bytes = [0u8; 8];
+        self.stream.fill_bytes(&mut bytes);
+        u64::from_le_bytes(bytes)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.stream.fill_bytes(dest);
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.stream.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl CryptoRng for PgenRng {}
+
+impl SeedableRng for PgenRng {
+    type Seed = [u8; 32];
+
+    fn from_seed(seed: Self::Seed) -> Self {
+        Self {
+            stream: StreamGenerator::from_key(&seed, &[0u8; 12]),
+        }
+    }
+
+    /// Seeds from a new pool backed by the OS entropy source rather than from the OS alone.
+    /// Panics if that pool refuses to generate, like the default does when the OS fails.
+    fn from_entropy() -> Self {
+        Self::new(&EntropyPool::default()).expect("a default pool refused to generate")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use rand::seq::SliceRandom;
+
+    use crate::entropy::deterministic::seeded_pool;
+
+    #[test]
+    fn from_seed_is_the_stream_for_that_key() {
+        let mut rng = PgenRng::from_seed([3; 32]);
+        let mut expected = [0u8; 12];
+        StreamGenerator::from_key(&[3; 32], &[0; 12]).fill_bytes(&mut expected);
+
+        assert_eq!(rng.next_u32().to_le_bytes(), expected[..4]);
+        assert_eq!(rng.next_u64().to_le_bytes(), expected[4..]);
+    }
+
+    #[test]
+    fn same_seed_gives_same_draws() {
+        let mut first = PgenRng::from_seed([5; 32]);
+        let mut second = PgenRng::from_seed([5; 32]);
+        let mut deck: Vec<u32> = (0..52).collect();
+        let mut other_deck = deck.clone();
+        deck.shuffle(&mut first);
+        other_deck.shuffle(&mut second);
+        assert_eq!(deck, other_deck);
+        assert_eq!(first.gen_range(0..1000), second.gen_range(0..1000));
+    }
+
+    #[test]
+    fn new_draws_from_the_pool() {
+        let mut first = PgenRng::new(&seeded_pool(b"rng")).unwrap();
+        let mut second = PgenRng::new(&seeded_pool(b"rng")).unwrap();
+        assert_eq!(first.next_u64(), second.next_u64());
+
+        let mut bytes = [0u8; 16];
+        first.try_fill_bytes(&mut bytes).unwrap();
+        assert_ne!(bytes, [0; 16]);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,244 @@
+//! A random seed carried over between runs, like systemd's `random-seed`.
+//!
+//! At startup the seed is read and mixed into the pool, then the file is immediately
+//! overwritten with a new seed derived from the updated pool, so no seed is ever used twice.
+//! It is written again periodically and at exit, so entropy gathered during a session is not
+//! lost. The seed is never credited: it only guards against a pool that starts out with
+//! nothing better than the built-in constants.
+//!
+//! The file holds a header, an XChaCha20-encrypted seed and a keyed BLAKE3 tag over both.
+//! The keys are bound to this machine, this user and the file's path, so a copy that ends up
+//! in a backup or on another machine can't be decrypted or passed off as valid. They are not
+//! secret from someone who can already read the file as this user; against that, only the
+//! file permissions (owner-only on Unix) help. A file that fails the integrity check is
+//! ignored and replaced.
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::crypto::chacha20::{self, ExtendedNonce, Key};
+use crate::generator::bytes;
+use crate::pool::EntropyPool;
+
+const MAGIC: &[u8] = b"P-GEN SEED";
+const VERSION: u8 = 1;
+pub const SEED_SIZE: usize = 64;
+const NONCE_SIZE: usize = chacha20::EXTENDED_NONCE_SIZE;
+const TAG_SIZE: usize = 32;
+const FILE_SIZE: usize = MAGIC.len() + 1 + NONCE_SIZE + SEED_SIZE + TAG_SIZE;
+
+/// What `load` found.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SeedFileStatus {
+    /// A valid seed was mixed into the pool.
+    Loaded,
+    /// There was no seed file yet.
+    Missing,
+    /// The file was damaged or belongs to another machine or user, and was ignored.
+    Corrupted(String),
+}
+
+/// `%APPDATA%\P-GEN\random-seed` on Windows, `$XDG_DATA_HOME/p-gen/random-seed` (by default
+/// under `~/.local/share`) elsewhere. `None` if no home directory can be found.
+pub fn default_path() -> Option<PathBuf> {
+    if cfg!(windows) {
+        return std::env::var_os("APPDATA").map(|app_data| PathBuf::from(app_data).join("P-GEN").join("random-seed"));
+    }
+    let data_home = match std::env::var_os("XDG_DATA_HOME").filter(|path| !path.is_empty()) {
+        Some(data_home) => PathBuf::from(data_home),
+        None => PathBuf::from(std::env::var_os("HOME")?).join(".local").join("share"),
+    };
+    Some(data_home.join("p-gen").join("random-seed"))
+}
+
+/// Mixes the seed at `path` into the pool, then replaces it with a fresh one. A missing or
+/// corrupted file is not an error; only failing to write the replacement is.
+pub fn load(pool: &EntropyPool, path: &Path) -> io::Result<SeedFileStatus> {
+    let status = match fs::read(path) {
+        Ok(contents) => match open(&contents, path) {
+            Ok(mut seed) => {
+                pool.mix_seed(&seed);
+                seed.fill(0);
+                SeedFileStatus::Loaded
+            }
+            Err(reason) => SeedFileStatus::Corrupted(reason),
+        },
+        Err(error) if error.kind() == io::ErrorKind::NotFound => SeedFileStatus::Missing,
+        Err(error) => return Err(error),
+    };
+    save(pool, path)?;
+    Ok(status)
+}
+
+/// Writes a new seed derived from the pool to `path`, replacing the old one atomically.
+pub fn save(pool: &EntropyPool, path: &Path) -> io::Result<()> {
+    if let Some(directory) = path.parent().filter(|directory| !directory.as_os_str().is_empty()) {
+        create_private_directory(directory)?;
+    }
+
+    let mut seed = pool.derive_seed();
+    let mut nonce = [0u8; NONCE_SIZE];
+    pool.source().fill_bytes(&mut nonce);
+    let contents = seal(&seed, &ExtendedNonce::new(nonce), path);
+    seed.fill(0);
+
+    let temporary_path = bytes::temporary_path_for(path);
+    let _ = fs::remove_file(&temporary_path);
+    let result = bytes::create_new_private_file(&temporary_path)
+        .and_then(|mut file| file.write_all(&contents).and_then(|_| file.sync_all()))
+        .and_then(|_| fs::rename(&temporary_path, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&temporary_path);
+    }
+    result
+}
+
+fn seal(seed: &[u8], nonce: &ExtendedNonce, path: &Path) -> Vec<u8> {
+    let (encryption_key, mac_key) = derive_keys(path);
+    let mut contents = MAGIC.to_vec();
+    contents.push(VERSION);
+    contents.extend_from_slice(nonce.as_bytes());
+
+    let mut encrypted_seed = seed.to_vec();
+    chacha20::xchacha20_apply_keystream(&encryption_key, nonce, &mut encrypted_seed);
+    contents.append(&mut encrypted_seed);
+
+    let tag = blake3::keyed_hash(&mac_key, &contents);
+    contents.extend_from_slice(tag.as_bytes());
+    contents
+}
+
+fn open(contents: &[u8], path: &Path) -> Result<Vec<u8>, String> {
+    if contents.len() != FILE_SIZE || !contents.starts_with(MAGIC) {
+        return Err("not a P-GEN seed file".to_string());
+    }
+    if contents[MAGIC.len()] != VERSION {
+        return Err(format!("unsupported seed file version {}", contents[MAGIC.len()]));
+    }
+
+    let (encryption_key, mac_key) = derive_keys(path);
+    let (authenticated, tag) = contents.split_at(FILE_SIZE - TAG_SIZE);
+    let mut expected_tag = [0u8; TAG_SIZE];
+    expected_tag.copy_from_slice(tag);
+    // `blake3::Hash` compares in constant time.
+    if blake3::keyed_hash(&mac_key, authenticated) != blake3::Hash::from(expected_tag) {
+        return Err("integrity check failed".to_string());
+    }
+
+    let nonce_start = MAGIC.len() + 1;
+    let nonce = ExtendedNonce::try_from(&contents[nonce_start..nonce_start + NONCE_SIZE]).map_err(|error| error.to_string())?;
+    let mut seed = contents[nonce_start + NONCE_SIZE..nonce_start + NONCE_SIZE + SEED_SIZE].to_vec();
+    chacha20::xchacha20_apply_keystream(&encryption_key, &nonce, &mut seed);
+    Ok(seed)
+}
+
+/// Encryption and MAC keys bound to this machine, this user and the file's location.
+fn derive_keys(path: &Path) -> (Key, [u8; 32]) {
+    let mut binding = Vec::new();
+    let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    for part in [machine_id(), home_directory(), Some(path.to_string_lossy().into_owned())] {
+        let part = part.unwrap_or_default();
+        binding.extend_from_slice(&(part.len() as u64).to_be_bytes());
+        binding.extend_from_slice(part.as_bytes());
+    }
+    let encryption_key = Key::new(blake3::derive_key("P-GEN seed file v1 encryption key", &binding));
+    let mac_key = blake3::derive_key("P-GEN seed file v1 MAC key", &binding);
+    (encryption_key, mac_key)
+}
+
+fn machine_id() -> Option<String> {
+    if cfg!(windows) {
+        return std::env::var("COMPUTERNAME").ok();
+    }
+    ["/etc/machine-id", "/var/lib/dbus/machine-id"].iter()
+        .find_map(|path| fs::read_to_string(path).ok())
+        .map(|id| id.trim().to_string())
+}
+
+fn home_directory() -> Option<String> {
+    std::env::var(if cfg!(windows) { "USERPROFILE" } else { "HOME" }).ok()
+}
+
+fn create_private_directory(directory: &Path) -> io::Result<()> {
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+
+    builder.create(directory)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entropy::deterministic::{scratch_directory, seeded_pool};
+
+    #[test]
+    fn sealed_seeds_open_only_at_their_path() {
+        let path = Path::new("/tmp/pgen/random-seed");
+        let seed = [0x5a; SEED_SIZE];
+        let contents = seal(&seed, &ExtendedNonce::new([7; NONCE_SIZE]), path);
+        assert_eq!(contents.len(), FILE_SIZE);
+        assert_ne!(contents[MAGIC.len() + 1 + NONCE_SIZE..][..SEED_SIZE], seed);
+        assert_eq!(open(&contents, path).unwrap(), seed);
+        assert_eq!(open(&contents, Path::new("/tmp/pgen/other-seed")), Err("integrity check failed".to_string()));
+    }
+
+    #[test]
+    fn damaged_files_are_rejected() {
+        let path = Path::new("/tmp/pgen/random-seed");
+        let contents = seal(&[1; SEED_SIZE], &ExtendedNonce::new([2; NONCE_SIZE]), path);
+        for position in [MAGIC.len() + 1, MAGIC.len() + 1 + NONCE_SIZE, FILE_SIZE - 1] {
+            let mut damaged = contents.clone();
+            damaged[position] ^= 1;
+            assert_eq!(open(&damaged, path), Err("integrity check failed".to_string()));
+        }
+
+        let mut other_version = contents.clone();
+        other_version[MAGIC.len()] = 2;
+        assert_eq!(open(&other_version, path), Err("unsupported seed file version 2".to_string()));
+        assert!(open(&contents[1..], path).is_err());
+        assert!(open(b"", path).is_err());
+    }
+
+    #[test]
+    fn loading_replaces_the_seed() {
+        let directory = scratch_directory("seed-load");
+        let path = directory.join("nested").join("random-seed");
+        let pool = seeded_pool(b"seed file");
+
+        assert_eq!(load(&pool, &path).unwrap(), SeedFileStatus::Missing);
+        let first = fs::read(&path).unwrap();
+        assert_eq!(first.len(), FILE_SIZE);
+
+        let fingerprint = pool.fingerprint();
+        assert_eq!(load(&pool, &path).unwrap(), SeedFileStatus::Loaded);
+        assert_ne!(pool.fingerprint(), fingerprint);
+        assert_ne!(fs::read(&path).unwrap(), first);
+        assert_eq!(fs::read_dir(path.parent().unwrap()).unwrap().count(), 1);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn corrupted_files_are_replaced() {
+        let directory = scratch_directory("seed-corrupted");
+        let path = directory.join("random-seed");
+        fs::write(&path, b"not a seed").unwrap();
+
+        let pool = seeded_pool(b"seed file");
+        assert_eq!(load(&pool, &path).unwrap(), SeedFileStatus::Corrupted("not a P-GEN seed file".to_string()));
+        assert_eq!(load(&pool, &path).unwrap(), SeedFileStatus::Loaded);
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
\ No newline at end of file
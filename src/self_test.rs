@@ -0,0 +1,490 @@
+//! Power-on self-tests, in the style of FIPS 140.
+//!
+//! Every primitive the generator relies on is checked against known-answer vectors: the
+//! published ones where they exist (NIST for SHA3 and SHA-512, the BLAKE3 reference
+//! vectors, the ISO vectors for Whirlpool, RFC 4231 for HMAC, Wycheproof for HKDF,
+//! RFC 8439 for ChaCha20 and ChaCha20-Poly1305, the XChaCha20 draft for
+//! XChaCha20-Poly1305, RFC 7914 for PBKDF2 and scrypt, the Argon2 reference
+//! implementation, and the BIP39 wordlist digests and reference vectors), a fixed answer
+//! for the joined digest, which is P-GEN's own construction, and the NIST CAVP vectors
+//! for HMAC_DRBG. The tests run once before the first stream is seeded, and again
+//! whenever `run` is called.
+//!
+//! Alongside them, the pool runs continuous tests on the blocks it draws from its sources
+//! and on every stream key, and fails if one comes out the same twice in a row. Any
+//! failure puts the library in an error state in which every generation is refused. Only
+//! a clean run of the known-answer tests gets it out again.
+
+use std::sync::{Mutex, Once};
+
+use crate::crypto;
+use crate::crypto::chacha20::{self, CryptoError, ExtendedNonce, Key, Nonce};
+use crate::crypto::drbg::HmacDrbg;
+use crate::crypto::hashes::{HashAlgorithm, IncrementalHasher};
+use crate::encoding;
+use crate::generator::bip39::{Bip39Language, Mnemonic, MnemonicError};
+use crate::generator::stream::StreamGenerator;
+use crate::pool::GenerationError;
+
+struct KnownAnswerTest {
+    name: &'static str,
+    run: fn() -> Result<(), String>,
+}
+
+/// Every known-answer test, in the order they run.
+const KNOWN_ANSWER_TESTS: &[KnownAnswerTest] = &[
+    KnownAnswerTest { name: "SHA3-256", run: sha3_256_test },
+    KnownAnswerTest { name: "SHA3-512", run: sha3_512_test },
+    KnownAnswerTest { name: "SHA-512", run: sha512_test },
+    KnownAnswerTest { name: "BLAKE3", run: blake3_test },
+    KnownAnswerTest { name: "BLAKE3 keyed", run: blake3_keyed_test },
+    KnownAnswerTest { name: "Whirlpool", run: whirlpool_test },
+    KnownAnswerTest { name: "Joined digest", run: joined_digest_test },
+    KnownAnswerTest { name: "Incremental hashers", run: incremental_hasher_test },
+    KnownAnswerTest { name: "HMAC", run: hmac_test },
+    KnownAnswerTest { name: "HKDF-SHA-512", run: hkdf_test },
+    KnownAnswerTest { name: "HMAC_DRBG", run: hmac_drbg_test },
+    KnownAnswerTest { name: "PBKDF2", run: pbkdf2_test },
+    KnownAnswerTest { name: "scrypt", run: scrypt_test },
+    KnownAnswerTest { name: "Argon2id", run: argon2id_test },
+    KnownAnswerTest { name: "BIP39", run: bip39_test },
+    KnownAnswerTest { name: "ChaCha20", run: chacha20_test },
+    KnownAnswerTest { name: "ChaCha20-Poly1305", run: chacha20_poly1305_test },
+    KnownAnswerTest { name: "XChaCha20-Poly1305", run: xchacha20_poly1305_test },
+    KnownAnswerTest { name: "Stream generator", run: stream_generator_test },
+];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SelfTestStatus {
+    NotRun,
+    Passed,
+    /// Generation is refused until the known-answer tests pass again.
+    Failed { test: String, reason: String },
+}
+
+/// The outcome of one known-answer test.
+#[derive(Clone, Debug)]
+pub struct SelfTestResult {
+    pub name: &'static str,
+    pub result: Result<(), String>,
+}
+
+/// The plaintext of the RFC 8439 and XChaCha20 draft test vectors.
+const SUNSCREEN: &[u8] = b"Ladies and Gentlemen of the class of '99: If I could offer you only one tip for the future, sunscreen would be it.";
+const AEAD_ASSOCIATED_DATA: [u8; 12] = [0x50, 0x51, 0x52, 0x53, 0xc0, 0xc1, 0xc2, 0xc3, 0xc4, 0xc5, 0xc6, 0xc7];
+
+static POWER_ON: Once = Once::new();
+#[cfg(not(test))]
+static STATUS: Mutex<SelfTestStatus> = Mutex::new(SelfTestStatus::NotRun);
+// Unit tests run on parallel threads, and some put the library in the error state on purpose,
+// so each test thread gets a status of its own.
+#[cfg(test)]
+thread_local! {
+    static STATUS: Mutex<SelfTestStatus> = const { Mutex::new(SelfTestStatus::NotRun) };
+}
+
+/// Runs every known-answer test and updates the status: `Passed` if all of them pass, which
+/// also clears an earlier failure, or `Failed` with the first one that didn't.
+pub fn run() -> Vec<SelfTestResult> {
+    run_tests(KNOWN_ANSWER_TESTS)
+}
+
+fn run_tests(tests: &[KnownAnswerTest]) -> Vec<SelfTestResult> {
+    let results: Vec<SelfTestResult> = tests.iter().map(|test| SelfTestResult { name: test.name, result: (test.run)() }).collect();
+    let outcome = match results.iter().find(|result| result.result.is_err()) {
+        Some(SelfTestResult { name, result: Err(reason) }) => SelfTestStatus::Failed { test: name.to_string(), reason: reason.clone() },
+        _ => SelfTestStatus::Passed,
+    };
+    with_status(|status| *status = outcome);
+    results
+}
+
+/// Runs the known-answer tests if they have not run yet in this process.
+pub fn power_on() {
+    POWER_ON.call_once(|| {
+        run();
+    });
+}
+
+pub fn status() -> SelfTestStatus {
+    with_status(|status| status.clone())
+}
+
+/// Fails if the self-tests have failed, after running them if they haven't run yet.
+pub(crate) fn check() -> Result<(), GenerationError> {
+    power_on();
+    match status() {
+        SelfTestStatus::Failed { test, reason } => Err(GenerationError::SelfTestFailed { test, reason }),
+        _ => Ok(()),
+    }
+}
+
+/// Puts the library in the error state, for failures found outside the known-answer tests.
+pub(crate) fn report_failure(test: &str, reason: String) {
+    with_status(|status| *status = SelfTestStatus::Failed { test: test.to_string(), reason });
+}
+
+fn with_status<T>(f: impl FnOnce(&mut SelfTestStatus) -> T) -> T {
+    let locked = |status: &Mutex<SelfTestStatus>| f(&mut status.lock().unwrap_or_else(|poisoned| poisoned.into_inner()));
+    #[cfg(not(test))]
+    return locked(&STATUS);
+    #[cfg(test)]
+    return STATUS.with(locked);
+}
+
+/// Compares `actual` with the expected value in hex.
+fn expect(case: &str, actual: &[u8], expected: &str) -> Result<(), String> {
+    let actual = encoding::hex_encode(actual, false);
+    if actual != expected {
+        return Err(format!("{}: expected {}, got {}", case, expected, actual));
+    }
+    Ok(())
+}
+
+fn expect_text(case: &str, actual: &str, expected: &str) -> Result<(), String> {
+    if actual != expected {
+        return Err(format!("{}: expected {:?}, got {:?}", case, expected, actual));
+    }
+    Ok(())
+}
+
+fn sha3_256_test() -> Result<(), String> {
+    expect("empty input", &crypto::hashes::sha3_256_compute(b""), "a7ffc6f8bf1ed76651c14756a061d662f580ff4de43b49fa82d80a4b80f8434a")?;
+    expect("\"abc\"", &crypto::hashes::sha3_256_compute(b"abc"), "3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532")
+}
+
+fn sha3_512_test() -> Result<(), String> {
+    expect("empty input", &crypto::hashes::sha3_512_compute(b""), "a69f73cca23a9ac5c8b567dc185a756e97c982164fe25859e0d1dcc1475c80a615b2123af1f5f94c11e3e9402c3ac558f500199d95b6d3e301758586281dcd26")?;
+    expect("\"abc\"", &crypto::hashes::sha3_512_compute(b"abc"), "b751850b1a57168a5693cd924b6b096e08f621827444f70d884f5d0240d2712e10e116e9192af3c91a7ec57647e3934057340b4cf408d5a56592f8274eec53f0")
+}
+
+fn blake3_test() -> Result<(), String> {
+    expect("empty input, 64-byte output", &crypto::hashes::blake3_512_compute(b""), "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262e00f03e7b69af26b7faaf09fcd333050338ddfe085b8cc869ca98b206c08243a")?;
+    expect("one zero byte", &crypto::hashes::blake3_256_compute(&[0]), "2d3adedff11b61f14c886e35afa036736dcd87a74d27b5c1510225d0f592e213")
+}
+
+fn sha512_test() -> Result<(), String> {
+    expect("\"abc\"", &crypto::hashes::sha512_compute(b"abc"), "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f")
+}
+
+/// From the BLAKE3 reference test vectors.
+fn blake3_keyed_test() -> Result<(), String> {
+    expect("keyed, empty input", &crypto::hashes::blake3_keyed_compute(b"whats the Elvish word for friend", b"", 32), "92b2b75604ed3c761f9d6f62392c8a9227ad0ea3f09573e783f1498a4ed60d26")?;
+    expect("derive_key, empty input", &crypto::hashes::blake3_derive_key("BLAKE3 2019-12-27 16:29:52 test vectors context", b""), "2cc39783c223154fea8dfb7c1b1660f2ac2dcbd1c1de8277b0b0dd39b7e50d7d")
+}
+
+fn whirlpool_test() -> Result<(), String> {
+    expect("empty input", &crypto::hashes::whirlpool_512_compute(b""), "19fa61d75522a4669b44e39c1d2e1726c530232130d407f89afee0964997f7a73e83be698b288febcf88e3e03c4f0757ea8964e59b63d93708b138cc42a66eb3")?;
+    expect("\"abc\"", &crypto::hashes::whirlpool_512_compute(b"abc"), "4e2448a4c6f486bb16b6562c73b4020bf3043e3a731bce721ae1b303d97e6d4c7181eebdb6c57e277d0e34957114cbd6c797fc9d95d8b582d225292076d4eef5")
+}
+
+fn joined_digest_test() -> Result<(), String> {
+    expect("pangram", &crypto::hashes::perform_joined_digest(b"The quick brown fox jumps over the lazy dog".to_vec()), "060ed9d139c4bac8c900580b24d496106491474a049540301f12e15e2d8b2183")
+}
+
+type OneShotHash = fn(&[u8]) -> Vec<u8>;
+
+/// Feeding the input in pieces must give the same digest as hashing it at once.
+fn incremental_hasher_test() -> Result<(), String> {
+    let input = b"The quick brown fox jumps over the lazy dog";
+    let hashers: [(HashAlgorithm, OneShotHash); 6] = [
+        (HashAlgorithm::Sha3_256, crypto::hashes::sha3_256_compute),
+        (HashAlgorithm::Sha3_512, crypto::hashes::sha3_512_compute),
+        (HashAlgorithm::Sha512, crypto::hashes::sha512_compute),
+        (HashAlgorithm::Blake3_256, crypto::hashes::blake3_256_compute),
+        (HashAlgorithm::Blake3_512, crypto::hashes::blake3_512_compute),
+        (HashAlgorithm::Whirlpool512, crypto::hashes::whirlpool_512_compute),
+    ];
+    for (algorithm, compute) in hashers {
+        let mut hasher = IncrementalHasher::new(algorithm);
+        hasher.update(&input[..10]).update(&input[10..]);
+        expect(&format!("{:?}", algorithm), &hasher.finalize(), &encoding::hex_encode(&compute(input), false))?;
+    }
+    let key = b"whats the Elvish word for friend";
+    let mut hasher = IncrementalHasher::new_blake3_keyed(key, 32);
+    hasher.update(&input[..10]).update(&input[10..]);
+    expect("keyed BLAKE3", &hasher.finalize(), &encoding::hex_encode(&crypto::hashes::blake3_keyed_compute(key, input, 32), false))
+}
+
+/// RFC 4231 test case 2 for HMAC-SHA-256 and HMAC-SHA-512, and the same inputs for HMAC-SHA3.
+fn hmac_test() -> Result<(), String> {
+    let (key, data): (&[u8], &[u8]) = (b"Jefe", b"what do ya want for nothing?");
+    expect("HMAC-SHA-256", &crypto::hashes::hmac_sha256_compute(key, data), "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843")?;
+    expect("HMAC-SHA-512", &crypto::hashes::hmac_sha512_compute(key, data), "164b7a7bfcf819e2e395fbe73b56e0a387bd64222e831fd610270cd7ea2505549758bf75c05a994a6d034f65f8f0e6fdcaeab1a34d4a6b4b636e070a38bce737")?;
+    expect("HMAC-SHA3-256", &crypto::hashes::hmac_sha3_256_compute(key, data), "c7d4072e788877ae3596bbb0da73b887c9171f93095b294ae857fbe2645e1ba5")?;
+    expect("HMAC-SHA3-512", &crypto::hashes::hmac_sha3_512_compute(key, data), "5a4bfeab6166427c7a3647b747292b8384537cdb89afb3bf5665e4c5e709350b287baec921fd7ca0ee7a0c31d022a95e1fc92ba9d77df883960275beb4e62024")?;
+
+    let mut tag = crypto::hashes::hmac_sha3_256_compute(key, data);
+    if !crypto::hashes::hmac_sha3_256_verify(key, data, &tag) {
+        return Err("a valid tag was rejected".to_string());
+    }
+    tag[0] ^= 1;
+    if crypto::hashes::hmac_sha3_256_verify(key, data, &tag) {
+        return Err("a modified tag was accepted".to_string());
+    }
+    Ok(())
+}
+
+/// Two of the Wycheproof HKDF-SHA512 vectors: one without salt or info, which tests the
+/// all-zero default salt, and one spanning two output blocks.
+fn hkdf_test() -> Result<(), String> {
+    let cases = [
+        ("d4dcb92a769f57c8bab8a420ee0aa351", "", "", "a8420281c08c5f087c9d54d5660847805b0fff2d6257f02bf849badfa8a29bee84ebe704a6eadc0beba0c33805d5843e167b1966aeba6a15b0f1f7b3db8c407a"),
+        (
+            "5be4b9756eff71ebe87fdb5933e5d88d51bcaf384cc289c16ec642d67f2e9236c04106e01ebe7956bac010e4107b6b788ed8b3916a39b59c7c01161cbc2671a7",
+            "336a871d315b7d2ae0a0d24febebf0702d9f039ca97146cc0aa2341728824e83553e6eb166e954dac33e4d6a2437309d0980e26d1c7665ddc79b2e9ba3354262",
+            "e496bea60a731eb7",
+            "02487460b110121d3df3746d7860332b6d67d746f96ff8e7bcb2d62481b653dcf67903b25aabaf9031b370959105c9136536b52dab810cf041862e73d3352f77747814aee2d74cfa29840dbfbca242f38b95ea26d4a540edbdab3fbeced1c767d35a73b4c2ff180eee75b4ada9739b7bd8c75c3bb03589ab",
+        ),
+    ];
+    for (input_key_material, salt, info, expected) in cases {
+        let [input_key_material, salt, info] = [input_key_material, salt, info].map(|value| encoding::hex_decode(value).ok_or("invalid hex"));
+        let pseudorandom_key = crypto::hashes::hkdf_sha512_extract(&salt?, &input_key_material?);
+        let output = crypto::hashes::hkdf_sha512_expand(&pseudorandom_key, &info?, expected.len() / 2).map_err(|error| error.to_string())?;
+        expect(&format!("{}-byte output", output.len()), &output, expected)?;
+    }
+
+    if crypto::hashes::hkdf_sha512_expand(&[0; 64], &[], crypto::hashes::HKDF_SHA512_MAX_OUTPUT_SIZE + 1).is_ok() {
+        return Err("an over-long output was accepted".to_string());
+    }
+    Ok(())
+}
+
+/// COUNT 0 of the SHA-512 CAVP HMAC_DRBG vectors without personalization string or
+/// additional input, from the no-reseed set and from the set that reseeds after instantiation.
+/// Each generates 256 bytes twice and checks the second.
+fn hmac_drbg_test() -> Result<(), String> {
+    let cases = [
+        (
+            "no reseed",
+            "35049f389a33c0ecb1293238fd951f8ffd517dfde06041d32945b3e26914ba15",
+            "f7328760be6168e6aa9fb54784989a11",
+            None,
+            "e76491b0260aacfded01ad39fbf1a66a88284caa5123368a2ad9330ee48335e3c9c9ba90e6cbc9429962d60c1a6661edcfaa31d972b8264b9d4562cf18494128a092c17a8da6f3113e8a7edfcd4427082bd390675e9662408144971717303d8dc352c9e8b95e7f35fa2ac9f549b292bc7c4bc7f01ee0a577859ef6e82d79ef23892d167c140d22aac32b64ccdfeee2730528a38763b24227f91ac3ffe47fb11538e435307e77481802b0f613f370ffb0dbeab774fe1efbb1a80d01154a9459e73ad361108bbc86b0914f095136cbe634555ce0bb263618dc5c367291ce0825518987154fe9ecb052b3f0a256fcc30cc14572531c9628973639beda456f2bddf6",
+        ),
+        (
+            "reseed",
+            "48c121b18733af15c27e1dd9ba66a9a81a5579cdba0f5b657ec53c2b9e90bbf6",
+            "bbb7c777428068fad9970891f879b1af",
+            Some("e0ffefdadb9ccf990504d568bdb4d862cbe17ccce6e22dfcab8b4804fd21421a"),
+            "05da6aac7d980da038f65f392841476d37fe70fbd3e369d1f80196e66e54b8fadb1d60e1a0f3d4dc173769d75fc3410549d7a843270a54a068b4fe767d7d9a59604510a875ad1e9731c8afd0fd50b825e2c50d062576175106a9981be37e02ec7c5cd0a69aa0ca65bddaee1b0de532e10cfa1f5bf6a026e47379736a099d6750ab121dbe3622b841baf8bdcbe875c85ba4b586b8b5b57b0fecbec08c12ff2a9453c47c6e32a52103d972c62ab9affb8e728a31fcefbbccc556c0f0a35f4b10ace2d96b906e36cbb72233201e536d3e13b045187b417d2449cad1edd192e061f12d22147b0a176ea8d9c4c35404395b6502ef333a813b6586037479e0fa3c6a23",
+        ),
+    ];
+    for (case, entropy_input, nonce, reseed_entropy_input, expected) in cases {
+        let entropy_input = encoding::hex_decode(entropy_input).ok_or("invalid hex")?;
+        let nonce = encoding::hex_decode(nonce).ok_or("invalid hex")?;
+        let mut drbg = HmacDrbg::instantiate(&entropy_input, &nonce, &[]).map_err(|error| error.to_string())?;
+        if let Some(reseed_entropy_input) = reseed_entropy_input {
+            drbg.reseed(&encoding::hex_decode(reseed_entropy_input).ok_or("invalid hex")?, &[]).map_err(|error| error.to_string())?;
+        }
+        let mut output = [0u8; 256];
+        drbg.generate(&mut output, &[]).map_err(|error| error.to_string())?;
+        drbg.generate(&mut output, &[]).map_err(|error| error.to_string())?;
+        expect(case, &output, expected)?;
+    }
+
+    if HmacDrbg::instantiate(&[0; 31], &[0; 16], &[]).is_ok() {
+        return Err("too little entropy input was accepted".to_string());
+    }
+    Ok(())
+}
+
+/// RFC 7914 section 11, PBKDF2-HMAC-SHA256 with one iteration.
+fn pbkdf2_test() -> Result<(), String> {
+    let output = crypto::kdf::pbkdf2_hmac_sha256(b"passwd", b"salt", 1, 64).map_err(|error| error.to_string())?;
+    expect("\"passwd\"", &output, "55ac046e56e3089fec1691c22544b605f94185216dde0465e68b9d57c20dacbc49ca9cccf179b645991664b39d77ef317c71b845b1e30bd509112041d3a19783")
+}
+
+/// RFC 7914 section 12, the first vector (N = 16, r = 1, p = 1). The others take too long
+/// for a power-on test.
+fn scrypt_test() -> Result<(), String> {
+    let output = crypto::kdf::scrypt(b"", b"", 4, 1, 1, 64).map_err(|error| error.to_string())?;
+    expect("empty input", &output, "77d6576238657b203b19ca42c18a0497f16b4844e3074ae8dfdffa3fede21442fcd0069ded0948f8326a753a0fc81f17e8d3e0fb2e0d3628cf35e20c38d18906")
+}
+
+/// The Argon2 reference implementation's Argon2id vector with 256 KiB, 2 passes and 1 lane.
+fn argon2id_test() -> Result<(), String> {
+    let output = crypto::kdf::argon2id(b"password", b"somesalt", 256, 2, 1, 32).map_err(|error| error.to_string())?;
+    expect("\"password\"", &output, "9dfeb910e80bad0311fee20f9c0e2b12c17987b4cac90c2ef54d5b3021c68bfe")
+}
+
+/// The SHA-256 digests of the BIP39 wordlists, as published next to them.
+const BIP39_WORDLIST_DIGESTS: [(Bip39Language, &str); 10] = [
+    (Bip39Language::English, "2f5eed53a4727b4bf8880d8f3f199efc90e58503646d9ff8eff3a2ed3b24dbda"),
+    (Bip39Language::ChineseSimplified, "5c5942792bd8340cb8b27cd592f1015edf56a8c5b26276ee18a482428e7c5726"),
+    (Bip39Language::ChineseTraditional, "417b26b3d8500a4ae3d59717d7011952db6fc2fb84b807f3f94ac734e89c1b5f"),
+    (Bip39Language::Czech, "7e80e161c3e93d9554c2efb78d4e3cebf8fc727e9c52e03b83b94406bdcc95fc"),
+    (Bip39Language::French, "ebc3959ab7801a1df6bac4fa7d970652f1df76b683cd2f4003c941c63d517e59"),
+    (Bip39Language::Italian, "d392c49fdb700a24cd1fceb237c1f65dcc128f6b34a8aacb58b59384b5c648c2"),
+    (Bip39Language::Japanese, "2eed0aef492291e061633d7ad8117f1a2b03eb80a29d0e4e3117ac2528d05ffd"),
+    (Bip39Language::Korean, "9e95f86c167de88f450f0aaf89e87f6624a57f973c67b516e338e8e8b8897f60"),
+    (Bip39Language::Portuguese, "2685e9c194c82ae67e10ba59d9ea5345a23dc093e92276fc5361f6667d79cd3f"),
+    (Bip39Language::Spanish, "46846a5a0139d1e3cb77293e521c2865f7bcdb82c44e8d0a06a2cd0ecba48c0b"),
+];
+
+/// The wordlist digests, then the first English vector of the BIP's reference vectors and the
+/// first Japanese one, whose passphrase needs NFKD. The seeds also cover PBKDF2-HMAC-SHA512.
+fn bip39_test() -> Result<(), String> {
+    for (language, digest) in BIP39_WORDLIST_DIGESTS {
+        expect(language.name(), &crypto::hashes::sha256_compute(language.wordlist_text().as_bytes()), digest)?;
+    }
+
+    let english = Mnemonic::from_entropy(&[0; 16], Bip39Language::English).map_err(|error| error.to_string())?;
+    expect_text("English phrase", &english.phrase(), "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about")?;
+    expect("English seed", &english.derive_seed("TREZOR"), "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04")?;
+    let japanese = Mnemonic::from_entropy(&[0; 16], Bip39Language::Japanese).map_err(|error| error.to_string())?;
+    expect("Japanese seed", &japanese.derive_seed("㍍ガバヴァぱばぐゞちぢ十人十色"), "a262d6fb6122ecf45be09c50492b31f92e9beb7d9a845987a02cefda57a15f9c467a17872029a9e92299b5cbdf306e3a0ee620245cbd508959b6cb7ca637bd55")?;
+
+    if Mnemonic::parse(&english.phrase(), None).map(|parsed| parsed.entropy() == english.entropy()) != Ok(true) {
+        return Err("the English phrase did not parse back".to_string());
+    }
+    if Mnemonic::parse(&english.phrase().replace("about", "abandon"), None).err() != Some(MnemonicError::InvalidChecksum) {
+        return Err("a phrase with a wrong checksum was accepted".to_string());
+    }
+    Ok(())
+}
+
+/// RFC 8439, appendix A.1 test vector 1 and section 2.4.2. The keystream starts at block 0,
+/// so the section 2.4.2 plaintext, which starts at block 1, is preceded by a block of zeros.
+fn chacha20_test() -> Result<(), String> {
+    let mut keystream = vec![0u8; 64];
+    chacha20::chacha20_apply_keystream(&Key::new([0; 32]), &Nonce::new([0; 12]), &mut keystream);
+    expect("all-zero key and nonce", &keystream, "76b8e0ada0f13d90405d6ae55386bd28bdd219b8a08ded1aa836efcc8b770dc7da41597c5157488d7724e03fb8d84a376a43b8f41518a11cc387b669b2ee6586")?;
+
+    let mut data = vec![0u8; 64];
+    data.extend_from_slice(SUNSCREEN);
+    chacha20::chacha20_apply_keystream(&Key::new(std::array::from_fn(|i| i as u8)), &Nonce::new([0, 0, 0, 0, 0, 0, 0, 0x4a, 0, 0, 0, 0]), &mut data);
+    expect("sunscreen", &data[64..], "6e2e359a2568f98041ba0728dd0d6981e97e7aec1d4360c20a27afccfd9fae0bf91b65c5524733ab8f593dabcd62b3571639d624e65152ab8f530c359f0861d807ca0dbf500d6a6156a38e088a22b65e52bc514d16ccf806818ce91ab77937365af90bbf74a35be6b40b8eedf2785e42874d")
+}
+
+/// RFC 8439 section 2.8.2.
+fn chacha20_poly1305_test() -> Result<(), String> {
+    let key = Key::new(std::array::from_fn(|i| 0x80 + i as u8));
+    let nonce = Nonce::new([0x07, 0, 0, 0, 0x40, 0x41, 0x42, 0x43, 0x44, 0x45, 0x46, 0x47]);
+    let sealed = chacha20::chacha20_poly1305_encrypt(&key, &nonce, &AEAD_ASSOCIATED_DATA, SUNSCREEN).map_err(|error| error.to_string())?;
+    expect("sunscreen", &sealed, "d31a8d34648e60db7b86afbc53ef7ec2a4aded51296e08fea9e2b5a736ee62d63dbea45e8ca9671282fafb69da92728b1a71de0a9e060b2905d6a5b67ecd3b3692ddbd7f2d778b8c9803aee328091b58fab324e4fad675945585808b4831d7bc3ff4def08e4b7a9de576d26586cec64b61161ae10b594f09e26a7e902ecbd0600691")?;
+    aead_round_trip(&sealed, |ciphertext| chacha20::chacha20_poly1305_decrypt(&key, &nonce, &AEAD_ASSOCIATED_DATA, ciphertext))
+}
+
+/// draft-irtf-cfrg-xchacha, appendix A.3.1. The bare XChaCha20 keystream from block 1 on must
+/// give the same ciphertext.
+fn xchacha20_poly1305_test() -> Result<(), String> {
+    let key = Key::new(std::array::from_fn(|i| 0x80 + i as u8));
+    let nonce = ExtendedNonce::new(std::array::from_fn(|i| 0x40 + i as u8));
+    let sealed = chacha20::xchacha20_poly1305_encrypt(&key, &nonce, &AEAD_ASSOCIATED_DATA, SUNSCREEN).map_err(|error| error.to_string())?;
+    expect("sunscreen", &sealed, "bd6d179d3e83d43b9576579493c0e939572a1700252bfaccbed2902c21396cbb731c7f1b0b4aa6440bf3a82f4eda7e39ae64c6708c54c216cb96b72e1213b4522f8c9ba40db5d945b11b69b982c1bb9e3f3fac2bc369488f76b2383565d3fff921f9664c97637da9768812f615c68b13b52ec0875924c1c7987947deafd8780acf49")?;
+
+    let mut data = vec![0u8; 64];
+    data.extend_from_slice(SUNSCREEN);
+    chacha20::xchacha20_apply_keystream(&key, &nonce, &mut data);
+    expect("XChaCha20 keystream", &data[64..], &encoding::hex_encode(&sealed[..SUNSCREEN.len()], false))?;
+    aead_round_trip(&sealed, |ciphertext| chacha20::xchacha20_poly1305_decrypt(&key, &nonce, &AEAD_ASSOCIATED_DATA, ciphertext))
+}
+
+/// Decrypting `sealed` must give the plaintext back, and must fail once a bit is flipped.
+fn aead_round_trip(sealed: &[u8], decrypt: impl Fn(&[u8]) -> Result<Vec<u8>, CryptoError>) -> Result<(), String> {
+    if decrypt(sealed).as_deref() != Ok(SUNSCREEN) {
+        return Err("decryption did not return the plaintext".to_string());
+    }
+    let mut tampered = sealed.to_vec();
+    tampered[0] ^= 1;
+    if decrypt(&tampered) != Err(CryptoError::AuthenticationFailed) {
+        return Err("a modified ciphertext was accepted".to_string());
+    }
+    Ok(())
+}
+
+/// A stream started from a key is the plain ChaCha20 keystream until its first rekey.
+fn stream_generator_test() -> Result<(), String> {
+    let mut output = [0u8; 64];
+    StreamGenerator::from_key(&[0; 32], &[0; 12]).fill_bytes(&mut output);
+    expect("all-zero key and nonce", &output, "76b8e0ada0f13d90405d6ae55386bd28bdd219b8a08ded1aa836efcc8b770dc7da41597c5157488d7724e03fb8d84a376a43b8f41518a11cc387b669b2ee6586")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entropy::deterministic::seeded_pool;
+    use crate::generator;
+    use crate::pool::EntropyPool;
+
+    const BROKEN_TESTS: &[KnownAnswerTest] = &[
+        KnownAnswerTest { name: "SHA3-256", run: sha3_256_test },
+        KnownAnswerTest { name: "Broken", run: broken_test },
+        KnownAnswerTest { name: "Also broken", run: broken_test },
+    ];
+
+    /// A known-answer test whose expected answer has been corrupted.
+    fn broken_test() -> Result<(), String> {
+        expect("\"abc\"", &crypto::hashes::sha3_256_compute(b"abc"), "0000000000000000000000000000000000000000000000000000000000000000")
+    }
+
+    fn assert_refused(pool: &EntropyPool, test: &str) {
+        match check() {
+            Err(GenerationError::SelfTestFailed { test: failed, .. }) => assert_eq!(failed, test),
+            other => panic!("expected a self-test failure, got {:?}", other.map(|_| ())),
+        }
+        assert!(matches!(generator::generate_stream(pool, 16), Err(GenerationError::SelfTestFailed { .. })));
+        let mnemonic = Mnemonic::from_entropy(&[0; 16], Bip39Language::English).unwrap();
+        assert!(matches!(mnemonic.to_seed(""), Err(MnemonicError::Generation(GenerationError::SelfTestFailed { .. }))));
+    }
+
+    #[test]
+    fn every_known_answer_test_passes() {
+        let results = run();
+        assert_eq!(results.len(), KNOWN_ANSWER_TESTS.len());
+        for result in &results {
+            assert_eq!(result.result, Ok(()), "{}", result.name);
+        }
+        assert_eq!(status(), SelfTestStatus::Passed);
+        assert!(check().is_ok());
+    }
+
+    #[test]
+    fn a_wrong_answer_refuses_generation_until_a_clean_run() {
+        let pool = seeded_pool(b"self-test");
+        assert!(generator::generate_stream(&pool, 16).is_ok());
+
+        let results = run_tests(BROKEN_TESTS);
+        assert_eq!(results[0].result, Ok(()));
+        assert!(results[1].result.as_ref().unwrap_err().contains("expected 0000"));
+        match status() {
+            SelfTestStatus::Failed { test, reason } => {
+                assert_eq!(test, "Broken");
+                assert!(reason.starts_with("\"abc\": expected"));
+            }
+            other => panic!("expected a failure, got {:?}", other),
+        }
+        assert_refused(&pool, "Broken");
+
+        assert!(run().iter().all(|result| result.result.is_ok()));
+        assert_eq!(status(), SelfTestStatus::Passed);
+        assert!(check().is_ok());
+        assert!(generator::generate_stream(&pool, 16).is_ok());
+    }
+
+    #[test]
+    fn a_continuous_test_failure_refuses_generation_until_a_clean_run() {
+        let pool = seeded_pool(b"self-test");
+        pool.continuous_test("Test block", &[1; 32]).unwrap();
+        pool.continuous_test("Test block", &[2; 32]).unwrap();
+        assert!(matches!(pool.continuous_test("Test block", &[2; 32]), Err(GenerationError::SelfTestFailed { .. })));
+        assert_refused(&pool, "Test block");
+
+        run();
+        assert!(check().is_ok());
+        assert!(generator::generate_stream(&pool, 16).is_ok());
+    }
+
+    #[test]
+    fn reported_failures_are_kept_until_a_clean_run() {
+        report_failure("Outside", "it broke".to_string());
+        assert_eq!(status(), SelfTestStatus::Failed { test: "Outside".to_string(), reason: "it broke".to_string() });
+        assert!(check().is_err());
+        assert!(check().is_err());
+
+        run_tests(&BROKEN_TESTS[..1]);
+        assert_eq!(status(), SelfTestStatus::Passed);
+        assert!(check().is_ok());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,128 @@
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+/// Runs P-GEN without a seed file, configuration file or FIPS mode from the environment,
+/// feeding it `stdin`.
+fn run(args: &[&str], stdin: &str) -> Output {
+    run_with(&["--no-seed-file", "--no-config"], args, stdin)
+}
+
+fn run_with(global_args: &[&str], args: &[&str], stdin: &str) -> Output {
+    run_command(Command::new(env!("CARGO_BIN_EXE_P-GEN")).env_remove("PGEN_FIPS_MODE").args(global_args).args(args), stdin)
+}
+
+fn run_command(command: &mut Command, stdin: &str) -> Output {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(stdin.as_bytes()).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout_lines(output: &Output) -> Vec<String> {
+    String::from_utf8(output.stdout.clone()).unwrap().lines().map(|line| line.to_string()).collect()
+}
+
+#[test]
+fn metadata_names_the_operating_mode() {
+    let output = run(&["--metadata", "token"], "");
+    assert!(output.status.success());
+    let lines = stdout_lines(&output);
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with("Operating mode: standard, ChaCha20"), "{}", lines[0]);
+    assert_eq!(lines[1].len(), 64);
+
+    let output = run(&["--fips", "--metadata", "id", "uuid4", "--count", "2"], "");
+    assert!(output.status.success());
+    let lines = stdout_lines(&output);
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].starts_with("Operating mode: FIPS-approved, HMAC_DRBG"), "{}", lines[0]);
+    assert!(output.stderr.is_empty());
+}
+
+#[test]
+fn metadata_covers_site_passwords_and_analysis() {
+    let output = run(&["--fips", "--metadata", "site-password", "example.org", "contact@example.org", "--lesspass"], "password\n");
+    assert!(output.status.success());
+    let lines = stdout_lines(&output);
+    assert!(lines[0].starts_with("Operating mode: FIPS-approved"), "{}", lines[0]);
+    assert_eq!(lines[1], "WHLpUL)e00[iHR+w");
+
+    // The tests may fail by chance; only the first line matters here.
+    let output = run(&["--metadata", "analyze", "--size", "4K", "--characters", "1000"], "");
+    assert!(stdout_lines(&output)[0].starts_with("Operating mode: standard"));
+}
+
+#[test]
+fn metadata_is_left_out_where_nothing_is_generated() {
+    let output = run(&["--metadata", "mnemonic", "check"], "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about\n");
+    assert!(output.status.success());
+    assert_eq!(stdout_lines(&output), ["Valid english phrase of 12 words, 128 bits of entropy"]);
+}
+
+#[test]
+fn metadata_is_refused_with_raw_bytes_on_stdout() {
+    let output = run(&["--metadata", "bytes", "16"], "");
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--metadata needs -o <file>"));
+}
+
+#[test]
+fn fips_mode_is_reported_on_stderr_without_metadata() {
+    let output = run(&["--fips", "site-password", "example.org", "contact@example.org", "--lesspass"], "password\n");
+    assert!(output.status.success());
+    assert_eq!(stdout_lines(&output), ["WHLpUL)e00[iHR+w"]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("generated in FIPS-approved mode"));
+
+    let output = run(&["token"], "");
+    assert!(output.status.success());
+    assert!(output.stderr.is_empty());
+}
+
+#[test]
+fn only_generating_commands_touch_the_seed_file() {
+    let directory = std::env::temp_dir().join(format!("pgen-cli-seed-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&directory);
+    let path = directory.join("random-seed");
+    let seed_file_args = ["--no-config", "--seed-file", path.to_str().unwrap()];
+
+    for args in [&["help"][..], &["self-test"], &["mnemonic", "check"], &["analyze", "--size", "4K", "--characters", "1000"]] {
+        run_with(&seed_file_args, args, "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about\n");
+        assert!(!path.exists(), "{:?} wrote the seed file", args);
+    }
+
+    assert!(run_with(&seed_file_args, &["token"], "").status.success());
+    let seed = std::fs::read(&path).unwrap();
+    assert!(run_with(&seed_file_args, &["help"], "").status.success());
+    assert_eq!(std::fs::read(&path).unwrap(), seed);
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn fips_mode_comes_from_the_command_line_environment_or_config_file() {
+    let directory = std::env::temp_dir().join(format!("pgen-cli-config-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&directory);
+    std::fs::create_dir_all(&directory).unwrap();
+    let path = directory.join("config");
+    std::fs::write(&path, "# compliance\nfips-mode = on\n").unwrap();
+    let config_args = ["--no-seed-file", "--config", path.to_str().unwrap()];
+    let operating_mode = |output: Output| stdout_lines(&output)[0].clone();
+
+    assert!(operating_mode(run_with(&config_args, &["--metadata", "token"], "")).starts_with("Operating mode: FIPS-approved"));
+    assert!(operating_mode(run_with(&config_args, &["--no-fips", "--metadata", "token"], "")).starts_with("Operating mode: standard"));
+    let environment = |value: &str, args: &[&str]| {
+        run_command(Command::new(env!("CARGO_BIN_EXE_P-GEN")).env("PGEN_FIPS_MODE", value).args(config_args).args(args), "")
+    };
+    assert!(operating_mode(environment("0", &["--metadata", "token"])).starts_with("Operating mode: standard"));
+    assert!(operating_mode(environment("0", &["--fips", "--metadata", "token"])).starts_with("Operating mode: FIPS-approved"));
+
+    std::fs::write(&path, "fips-mode = sometimes\n").unwrap();
+    let output = run_with(&config_args, &["token"], "");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("fips-mode is on or off"));
+    std::fs::remove_dir_all(&directory).unwrap();
+}
\ No newline at end of file